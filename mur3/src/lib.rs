@@ -364,6 +364,7 @@ mod hash32 {
 
     /// A 32-bit Murmur3 hasher.
     #[repr(C)]
+    #[derive(Clone)]
     pub struct Hasher32 {
         h: u32,
         buf: [u8; 4],
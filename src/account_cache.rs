@@ -0,0 +1,130 @@
+use {
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        fs,
+        io,
+        path::PathBuf,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedAccounts {
+    endpoint: String,
+    program: String,
+    data_size: Option<u64>,
+    slot: u64,
+    fetched_at: u64,
+    keys: Vec<String>,
+}
+
+/// Account sets fetched over RPC, stored one file per (endpoint, program,
+/// slot) so repeated runs can skip the download while the set is fresh.
+pub struct AccountCache {
+    dir: PathBuf,
+    max_age: Duration,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+impl AccountCache {
+    pub fn new(dir: PathBuf, max_age: Duration) -> Self {
+        Self { dir, max_age }
+    }
+
+    // Every slot's set for the same query shares this file name prefix.
+    fn prefix(endpoint: &str, program: &Pubkey, data_size: Option<u64>) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(endpoint.as_bytes());
+        hasher.update(program.as_ref());
+        hasher.update(&data_size.map_or([0xff; 9], |size| {
+            let mut bytes = [0; 9];
+            bytes[1..].copy_from_slice(&size.to_le_bytes());
+            bytes
+        }));
+        format!("{}-", &hasher.finalize().to_hex()[..16])
+    }
+
+    fn entries(&self, prefix: &str) -> io::Result<Vec<(u64, PathBuf)>> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Ok(Vec::new());
+        };
+        let mut entries = Vec::new();
+        for entry in dir {
+            let path = entry?.path();
+            let slot = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix(prefix))
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|slot| slot.parse().ok());
+            if let Some(slot) = slot {
+                entries.push((slot, path));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// The latest cached set for the query and the slot it was read at, if
+    /// one was fetched within the maximum age.
+    pub fn get(&self, endpoint: &str, program: &Pubkey, data_size: Option<u64>) -> io::Result<Option<(u64, Vec<Pubkey>)>> {
+        let Some((_, path)) = self.entries(&Self::prefix(endpoint, program, data_size))?.into_iter().max() else {
+            return Ok(None);
+        };
+        let cached: CachedAccounts = serde_json::from_reader(io::BufReader::new(fs::File::open(path)?))?;
+        if now().saturating_sub(cached.fetched_at) > self.max_age.as_secs() {
+            return Ok(None);
+        }
+        let keys = cached.keys.iter().map(|key| key.parse().map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{key}: {err}"))));
+        Ok(Some((cached.slot, keys.collect::<io::Result<_>>()?)))
+    }
+
+    /// Stores a freshly fetched set, replacing those of earlier slots.
+    pub fn put(&self, endpoint: &str, program: &Pubkey, data_size: Option<u64>, slot: u64, keys: &[Pubkey]) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let prefix = Self::prefix(endpoint, program, data_size);
+        let stale = self.entries(&prefix)?;
+        let path = self.dir.join(format!("{prefix}{slot}.json"));
+        let cached = CachedAccounts {
+            endpoint: endpoint.to_string(),
+            program: program.to_string(),
+            data_size,
+            slot,
+            fetched_at: now(),
+            keys: keys.iter().map(Pubkey::to_string).collect(),
+        };
+        serde_json::to_writer(io::BufWriter::new(fs::File::create(&path)?), &cached)?;
+        for (_, stale) in stale.into_iter().filter(|(_, stale)| *stale != path) {
+            fs::remove_file(stale)?;
+        }
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_expiry() {
+        let dir = std::env::temp_dir().join(format!("hash-to-bucket-account-cache-{}", std::process::id()));
+        let cache = AccountCache::new(dir.clone(), Duration::from_secs(3600));
+        let program = Pubkey::new_unique();
+        let keys = (0..10).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        assert!(cache.get("http://a", &program, None).unwrap().is_none());
+        cache.put("http://a", &program, None, 5, &keys[..3]).unwrap();
+        cache.put("http://a", &program, None, 7, &keys).unwrap();
+        assert_eq!(cache.get("http://a", &program, None).unwrap(), Some((7, keys.clone())));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(cache.get("http://b", &program, None).unwrap().is_none());
+        assert!(cache.get("http://a", &program, Some(165)).unwrap().is_none());
+        let mut cached: CachedAccounts = serde_json::from_reader(fs::File::open(dir.join(format!("{}7.json", AccountCache::prefix("http://a", &program, None)))).unwrap()).unwrap();
+        cached.fetched_at -= 7200;
+        serde_json::to_writer(fs::File::create(cache.put("http://a", &program, None, 7, &keys).unwrap()).unwrap(), &cached).unwrap();
+        assert!(cache.get("http://a", &program, None).unwrap().is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
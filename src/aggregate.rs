@@ -0,0 +1,125 @@
+use {
+    crate::analysis::BucketAnalysis,
+    serde::{Deserialize, Serialize},
+    std::{borrow::Cow, collections::HashMap, fmt, time::Duration},
+};
+
+pub const HEADER: &str = "hasher,epochs,worst_spread,worst_spread_epoch,mean_std_dev,std_dev_of_std_dev,worst_chi_squared,worst_chi_squared_epoch,total_time_us,mean_time_us";
+
+/// One hasher's per-epoch results summarized across every epoch run.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct HasherAggregate {
+    pub hasher: Cow<'static, str>,
+    pub epochs: u64,
+    pub worst_spread: usize,
+    pub worst_spread_epoch: u64,
+    pub mean_std_dev: f64,
+    /// Sample standard deviation of the per-epoch std_devs
+    pub std_dev_of_std_dev: f64,
+    pub worst_chi_squared: f64,
+    pub worst_chi_squared_epoch: u64,
+    pub total_time: Duration,
+}
+
+impl fmt::Display for HasherAggregate {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let total_time_us = self.total_time.as_secs_f64() * 1e6;
+        let mean_time_us = total_time_us / self.epochs.max(1) as f64;
+        write!(
+            formatter,
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.hasher,
+            self.epochs,
+            self.worst_spread,
+            self.worst_spread_epoch,
+            self.mean_std_dev,
+            self.std_dev_of_std_dev,
+            self.worst_chi_squared,
+            self.worst_chi_squared_epoch,
+            total_time_us,
+            mean_time_us
+        )
+    }
+}
+
+#[derive(Default)]
+struct Totals {
+    aggregate: HasherAggregate,
+    std_dev_sum: f64,
+    std_dev_squares: f64,
+}
+
+/// Accumulates every hasher's per-epoch analyses as a run emits them.
+#[derive(Default)]
+pub struct Aggregator {
+    names: Vec<&'static str>,
+    totals: HashMap<&'static str, Totals>,
+}
+
+impl Aggregator {
+    pub fn record(&mut self, hasher: &'static str, epoch: u64, analysis: &BucketAnalysis, time: Duration) {
+        let totals = self.totals.entry(hasher).or_insert_with(|| {
+            self.names.push(hasher);
+            Totals { aggregate: HasherAggregate { hasher: hasher.into(), ..HasherAggregate::default() }, ..Totals::default() }
+        });
+        let aggregate = &mut totals.aggregate;
+        if aggregate.epochs == 0 || analysis.spread > aggregate.worst_spread {
+            aggregate.worst_spread = analysis.spread;
+            aggregate.worst_spread_epoch = epoch;
+        }
+        if aggregate.epochs == 0 || analysis.chi_squared > aggregate.worst_chi_squared {
+            aggregate.worst_chi_squared = analysis.chi_squared;
+            aggregate.worst_chi_squared_epoch = epoch;
+        }
+        aggregate.epochs += 1;
+        aggregate.total_time += time;
+        totals.std_dev_sum += analysis.std_dev;
+        totals.std_dev_squares += analysis.std_dev * analysis.std_dev;
+    }
+
+    /// Each hasher's aggregate, in the order first recorded.
+    pub fn aggregates(&self) -> Vec<HasherAggregate> {
+        self.names
+            .iter()
+            .map(|name| {
+                let totals = &self.totals[name];
+                let epochs = totals.aggregate.epochs as f64;
+                let mean_std_dev = totals.std_dev_sum / epochs;
+                let std_dev_of_std_dev = if epochs > 1.0 {
+                    ((totals.std_dev_squares - epochs * mean_std_dev * mean_std_dev) / (epochs - 1.0)).max(0.0).sqrt()
+                } else {
+                    0.0
+                };
+                HasherAggregate { hasher: totals.aggregate.hasher.clone(), mean_std_dev, std_dev_of_std_dev, ..totals.aggregate }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregates() {
+        let analysis = |spread, std_dev, chi_squared| BucketAnalysis { spread, std_dev, chi_squared, ..BucketAnalysis::default() };
+        let mut aggregator = Aggregator::default();
+        aggregator.record("blake3", 0, &analysis(4, 1.0, 90.0), Duration::from_micros(10));
+        aggregator.record("fnv", 0, &analysis(2, 2.0, 80.0), Duration::from_micros(5));
+        aggregator.record("blake3", 1, &analysis(6, 3.0, 70.0), Duration::from_micros(30));
+        let aggregates = aggregator.aggregates();
+        assert_eq!(aggregates[0], HasherAggregate {
+            hasher: "blake3".into(),
+            epochs: 2,
+            worst_spread: 6,
+            worst_spread_epoch: 1,
+            mean_std_dev: 2.0,
+            std_dev_of_std_dev: 2.0f64.sqrt(),
+            worst_chi_squared: 90.0,
+            worst_chi_squared_epoch: 0,
+            total_time: Duration::from_micros(40),
+        });
+        assert_eq!(aggregates[1].to_string(), "fnv,1,2,0,2,0,80,0,5,5");
+        assert_eq!(serde_json::from_str::<HasherAggregate>(&serde_json::to_string(&aggregates[0]).unwrap()).unwrap(), aggregates[0]);
+    }
+}
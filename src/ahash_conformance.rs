@@ -0,0 +1,106 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::{fmt, hash::{BuildHasher, Hasher}},
+};
+
+// ahash's output depends on its version, the target, and whether it was
+// built with AES instructions, none of which it promises to keep stable. The
+// manifest pins ahash to one exact version, so it names the version built.
+const MANIFEST: &str = include_str!("../Cargo.toml");
+
+/// Everything known to change ahash's output, plus a fingerprint of the
+/// output itself.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AhashConfig {
+    pub version: String,
+    pub arch: String,
+    pub aes: bool,
+    pub fingerprint: String,
+}
+
+impl fmt::Display for AhashConfig {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "ahash {} on {}, aes {}, fingerprint {}", self.version, self.arch, self.aes, self.fingerprint)
+    }
+}
+
+impl AhashConfig {
+    pub fn current() -> Self {
+        Self {
+            version: pinned_version(MANIFEST, "ahash").unwrap_or_else(|| "unknown".to_string()),
+            arch: std::env::consts::ARCH.to_string(),
+            aes: cfg!(target_feature = "aes"),
+            fingerprint: fingerprint(),
+        }
+    }
+}
+
+/// The exact version a Cargo.toml pins `dependency` to with `=`, if it does.
+pub fn pinned_version(manifest: &str, dependency: &str) -> Option<String> {
+    let spec = manifest.lines().find_map(|line| line.strip_prefix(dependency)?.trim_start().strip_prefix('='))?.trim();
+    let version = match spec.strip_prefix('{') {
+        Some(table) => table.split(',').find_map(|field| field.trim().strip_prefix("version")?.trim_start().strip_prefix('='))?.trim(),
+        None => spec,
+    };
+    version.trim_matches('"').strip_prefix('=').map(str::to_string)
+}
+
+/// A digest of ahash's output for fixed addresses under the seeds the
+/// analysis gives epochs 0 to 7. Equal fingerprints mean equal assignments.
+pub fn fingerprint() -> String {
+    let mut digest = blake3::Hasher::new();
+    for epoch in 0..8u64 {
+        let state = ahash::random_state::RandomState::with_seeds(epoch, epoch, epoch, epoch);
+        for index in 0..64u64 {
+            let mut hasher = state.build_hasher();
+            hasher.write(blake3::hash(&index.to_le_bytes()).as_bytes());
+            digest.update(&hasher.finish().to_le_bytes());
+        }
+    }
+    digest.finalize().to_hex()[..16].to_string()
+}
+
+/// How `current` differs from `recorded`, worst first; empty when nothing
+/// known to affect ahash's output changed.
+pub fn differences(recorded: &AhashConfig, current: &AhashConfig) -> Vec<String> {
+    let mut differences = Vec::new();
+    if recorded.fingerprint != current.fingerprint {
+        differences.push(format!("output fingerprint {} differs from recorded {}", current.fingerprint, recorded.fingerprint));
+    }
+    for (name, recorded, current) in [
+        ("version", recorded.version.clone(), current.version.clone()),
+        ("arch", recorded.arch.clone(), current.arch.clone()),
+        ("aes", recorded.aes.to_string(), current.aes.to_string()),
+    ] {
+        if recorded != current {
+            differences.push(format!("{name} {current} differs from recorded {recorded}"));
+        }
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_version() {
+        let manifest = "[dependencies]\nahash = \"=0.8.12\"\nblake3 = { version = \"=1.5.0\", features = [\"std\"] }\nserde = \"1.0\"\n";
+        assert_eq!(pinned_version(manifest, "ahash").as_deref(), Some("0.8.12"));
+        assert_eq!(pinned_version(manifest, "blake3").as_deref(), Some("1.5.0"));
+        assert_eq!(pinned_version(manifest, "serde"), None);
+        assert_eq!(pinned_version(manifest, "rand"), None);
+        assert_ne!(AhashConfig::current().version, "unknown");
+    }
+
+    #[test]
+    fn test_differences() {
+        let current = AhashConfig::current();
+        assert_eq!(current.fingerprint, fingerprint());
+        assert!(differences(&current, &current).is_empty());
+        let recorded = AhashConfig { version: "0.0.1".to_string(), fingerprint: "0".repeat(16), ..current.clone() };
+        let differences = differences(&recorded, &current);
+        assert_eq!(differences.len(), 2);
+        assert!(differences[0].starts_with("output fingerprint"));
+    }
+}
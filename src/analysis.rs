@@ -0,0 +1,802 @@
+use {
+    clap::ValueEnum,
+    serde::{Deserialize, Serialize},
+    std::{fmt, hint::black_box, time::Instant},
+};
+
+pub const HEADER: &str = "min,max,spread,mean,median,mode,mode_count,mode_ties,mode_fraction,std_dev,variance,skewness,kurtosis,empty_buckets,occupied_fraction,spread_over_mean,max_over_mean,gini,chi_squared,p_value";
+pub const HEADER_WITHOUT_MODE: &str = "min,max,spread,mean,median,std_dev,variance,skewness,kurtosis,empty_buckets,occupied_fraction,spread_over_mean,max_over_mean,gini,chi_squared,p_value";
+pub const OCCUPANCY_HEADER: &str = "occupied,collisions,expected_empty";
+pub const LONG_HEADER: &str = "epoch,hasher,metric,value";
+pub const DEFAULT_PERCENTILES: [f64; 3] = [10.0, 90.0, 99.0];
+
+/// Other names [`AnalysisFormat::with_columns`] accepts for columns of
+/// [`HEADER`].
+pub const COLUMN_ALIASES: &[(&str, &str)] = &[("chi2", "chi_squared")];
+
+/// Shape of per-epoch results: one wide row per epoch and hasher, or one
+/// tidy row per epoch, hasher and metric.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Format {
+    #[default]
+    Wide,
+    Long,
+}
+
+/// Which bucket count is reported as the mode when several counts occur
+/// equally often.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ModeTieBreak {
+    #[default]
+    Lowest,
+    Highest,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct BucketAnalysis {
+    pub min: usize,
+    pub max: usize,
+    pub spread: usize,
+    pub mean: usize,
+    pub median: usize,
+    pub mode: usize,
+    pub mode_count: usize,
+    /// Number of distinct bucket counts that occur `mode_count` times
+    pub mode_ties: usize,
+    /// `mode_count` as a fraction of all buckets, comparable across bucket
+    /// counts
+    pub mode_fraction: f64,
+    /// Sample standard deviation of the bucket counts
+    pub std_dev: f64,
+    /// Sample variance of the bucket counts
+    pub variance: f64,
+    /// Moment coefficient of skewness; positive when a few buckets run heavy
+    pub skewness: f64,
+    /// Excess kurtosis, 0 for normally distributed counts; positive when
+    /// outlying buckets are more common than that
+    pub kurtosis: f64,
+    pub empty_buckets: usize,
+    pub occupied_fraction: f64,
+    // Scale-free counterparts of spread and max, comparable across datasets of
+    // different sizes.
+    pub spread_over_mean: f64,
+    pub max_over_mean: f64,
+    /// Gini coefficient of the bucket counts: 0 when every bucket holds the
+    /// same count, approaching 1 as one bucket holds everything
+    pub gini: f64,
+    /// Pearson's chi-squared statistic of the bucket counts against a
+    /// uniform expectation
+    pub chi_squared: f64,
+    /// Probability of a chi-squared at least this large were assignment
+    /// uniformly random
+    pub p_value: f64,
+    /// Nearest-rank bucket count at each requested percentile, in order;
+    /// serialized alongside the percentiles by whoever knows them
+    #[serde(skip)]
+    pub percentiles: Vec<usize>,
+}
+
+/// One column's value. Counts are written as integers and everything else
+/// as a float, at an [`AnalysisFormat`]'s precision if it sets one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    Count(usize),
+    Real(f64),
+}
+
+impl Metric {
+    pub fn value(self) -> f64 {
+        match self {
+            Self::Count(count) => count as f64,
+            Self::Real(value) => value,
+        }
+    }
+
+    fn write(self, formatter: &mut fmt::Formatter, precision: Option<usize>) -> fmt::Result {
+        match (self, precision) {
+            (Self::Count(count), _) => write!(formatter, "{count}"),
+            (Self::Real(value), Some(precision)) => write!(formatter, "{value:.precision$}"),
+            (Self::Real(value), None) => write!(formatter, "{value}"),
+        }
+    }
+
+    fn write_all(metrics: impl Iterator<Item = Self>, formatter: &mut fmt::Formatter, precision: Option<usize>) -> fmt::Result {
+        for (i, metric) in metrics.enumerate() {
+            if i > 0 {
+                formatter.write_str(",")?;
+            }
+            metric.write(formatter, precision)?;
+        }
+        Ok(())
+    }
+}
+
+impl BucketAnalysis {
+    /// The fixed columns' values, in [`HEADER`] order.
+    pub fn metrics(&self) -> [Metric; 20] {
+        use Metric::{Count, Real};
+        [
+            Count(self.min),
+            Count(self.max),
+            Count(self.spread),
+            Count(self.mean),
+            Count(self.median),
+            Count(self.mode),
+            Count(self.mode_count),
+            Count(self.mode_ties),
+            Real(self.mode_fraction),
+            Real(self.std_dev),
+            Real(self.variance),
+            Real(self.skewness),
+            Real(self.kurtosis),
+            Count(self.empty_buckets),
+            Real(self.occupied_fraction),
+            Real(self.spread_over_mean),
+            Real(self.max_over_mean),
+            Real(self.gini),
+            Real(self.chi_squared),
+            Real(self.p_value),
+        ]
+    }
+
+    // Column `index` of HEADER followed by the percentile columns, given
+    // the fixed columns' values; None for a percentile not analyzed.
+    fn metric(&self, metrics: &[Metric], index: usize) -> Option<Metric> {
+        metrics.get(index).copied().or_else(|| self.percentiles.get(index - metrics.len()).map(|count| Metric::Count(*count)))
+    }
+}
+
+/// Which columns of an analysis to write, and how many decimal places to give
+/// fractional values. The default is every column at full precision, as
+/// `Display` writes them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnalysisFormat {
+    /// Names from [`HEADER`] and the percentile columns, in output order
+    pub columns: Option<Vec<String>>,
+    pub precision: Option<usize>,
+    /// Percentiles the analyses were computed with, naming their columns
+    pub percentiles: Vec<f64>,
+    // `columns` resolved to indices into HEADER followed by the percentile
+    // columns, so rows are written without looking names up.
+    indices: Vec<usize>,
+}
+
+impl AnalysisFormat {
+    pub fn new(percentiles: &[f64]) -> Self {
+        Self { percentiles: percentiles.to_vec(), ..Self::default() }
+    }
+
+    /// Fails on the first column name no analysis has. Aliases from
+    /// [`COLUMN_ALIASES`] are written under the column's own name.
+    pub fn with_columns(mut self, columns: &[String]) -> Result<Self, String> {
+        let columns = columns
+            .iter()
+            .map(|column| COLUMN_ALIASES.iter().find(|(alias, _)| alias == column).map_or(column.as_str(), |(_, name)| name).to_string())
+            .collect::<Vec<_>>();
+        let percentile_header = percentile_header(&self.percentiles);
+        let known = HEADER.split(',').chain(percentile_header.split(',').filter(|column| !column.is_empty())).collect::<Vec<_>>();
+        self.indices = columns
+            .iter()
+            .map(|column| known.iter().position(|known| known == column).ok_or_else(|| format!("unknown column {column}; expected some of {}", known.join(","))))
+            .collect::<Result<_, _>>()?;
+        self.columns = Some(columns);
+        Ok(self)
+    }
+
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    pub fn header(&self) -> String {
+        match &self.columns {
+            Some(columns) => columns.join(","),
+            None if self.percentiles.is_empty() => HEADER.to_string(),
+            None => format!("{HEADER},{}", percentile_header(&self.percentiles)),
+        }
+    }
+
+    pub fn display<'a>(&'a self, analysis: &'a BucketAnalysis) -> Formatted<'a> {
+        Formatted { format: self, analysis }
+    }
+}
+
+/// An analysis written under an [`AnalysisFormat`], matching its
+/// [`header`](AnalysisFormat::header).
+pub struct Formatted<'a> {
+    format: &'a AnalysisFormat,
+    analysis: &'a BucketAnalysis,
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let (analysis, precision) = (self.analysis, self.format.precision);
+        let metrics = analysis.metrics();
+        match &self.format.columns {
+            Some(_) => Metric::write_all(self.format.indices.iter().filter_map(|index| analysis.metric(&metrics, *index)), formatter, precision),
+            None => Metric::write_all(metrics.into_iter().chain(analysis.percentiles.iter().map(|count| Metric::Count(*count))), formatter, precision),
+        }
+    }
+}
+
+impl fmt::Display for BucketAnalysis {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        AnalysisFormat::default().display(self).fmt(formatter)
+    }
+}
+
+/// Column names of `percentiles`, e.g. `p10,p90,p99`, which an analysis
+/// displays after its fixed columns.
+pub fn percentile_header(percentiles: &[f64]) -> String {
+    percentiles.iter().map(|percentile| format!("p{percentile}")).collect::<Vec<_>>().join(",")
+}
+
+// Zero-based index into the sorted counts of the nearest-rank `percentile`.
+fn percentile_rank(percentile: f64, num_buckets: usize) -> usize {
+    ((percentile / 100.0 * num_buckets as f64).ceil() as usize).clamp(1, num_buckets) - 1
+}
+
+/// Reshapes a wide `row` under `header`, both leading with the epoch, into
+/// [`LONG_HEADER`] lines, one per remaining column.
+pub fn long_rows(hasher: &str, header: &str, row: &str) -> String {
+    let mut values = row.split(',');
+    let epoch = values.next().unwrap_or_default();
+    header
+        .split(',')
+        .skip(1)
+        .zip(values)
+        .map(|(metric, value)| format!("{epoch},{hasher},{metric},{value}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn analyze_buckets(buckets: &mut [usize], tie_break: ModeTieBreak, percentiles: &[f64]) -> BucketAnalysis {
+    let num_buckets = buckets.len();
+    if num_buckets == 0 {
+        return BucketAnalysis { percentiles: vec![0; percentiles.len()], ..BucketAnalysis::default() };
+    }
+    buckets.sort();
+    let min = buckets[0];
+    let max = buckets[num_buckets - 1];
+    let spread = max - min;
+    let sum = buckets.iter().sum::<usize>();
+    let mean = sum / num_buckets;
+    let median = buckets[num_buckets / 2];
+    let (mut mode, mut mode_count, mut mode_ties) = (0, 0, 0);
+    for run in buckets.chunk_by(|a, b| a == b) {
+        if run.len() > mode_count {
+            (mode, mode_count, mode_ties) = (run[0], run.len(), 1);
+        } else if run.len() == mode_count {
+            mode_ties += 1;
+            if tie_break == ModeTieBreak::Highest {
+                mode = run[0];
+            }
+        }
+    }
+    let mode_fraction = mode_count as f64 / num_buckets as f64;
+    let (variance, skewness, kurtosis) = moments(buckets.chunk_by(|a, b| a == b).map(|run| (run[0], run.len())), sum, num_buckets);
+    let std_dev = variance.sqrt();
+    let empty_buckets = buckets.partition_point(|count| *count == 0);
+    let occupied_fraction = (num_buckets - empty_buckets) as f64 / num_buckets as f64;
+    let (spread_over_mean, max_over_mean) = normalized(spread, max, sum, num_buckets);
+    let gini = gini(buckets.chunk_by(|a, b| a == b).map(|run| (run[0], run.len())), sum, num_buckets);
+    let chi_squared = chi_squared(buckets.chunk_by(|a, b| a == b).map(|run| (run[0], run.len())), sum, num_buckets);
+    let p_value = chi_squared_p_value(chi_squared, num_buckets - 1);
+    let percentiles = percentiles.iter().map(|percentile| buckets[percentile_rank(*percentile, num_buckets)]).collect();
+
+    BucketAnalysis { min, max, spread, mean, median, mode, mode_count, mode_ties, mode_fraction, std_dev, variance, skewness, kurtosis, empty_buckets, occupied_fraction, spread_over_mean, max_over_mean, gini, chi_squared, p_value, percentiles }
+}
+
+// Sample variance, skewness and excess kurtosis over (bucket count, number of
+// buckets with that count) pairs. Skewness and kurtosis are the moment
+// coefficients, and 0 when every bucket holds the same count.
+fn moments(counts: impl Iterator<Item = (usize, usize)>, sum: usize, num_buckets: usize) -> (f64, f64, f64) {
+    let n = num_buckets as f64;
+    let mean = sum as f64 / n;
+    let (mut m2, mut m3, mut m4) = (0.0, 0.0, 0.0);
+    for (count, freq) in counts {
+        let deviation = count as f64 - mean;
+        let squared = deviation * deviation;
+        m2 += freq as f64 * squared;
+        m3 += freq as f64 * squared * deviation;
+        m4 += freq as f64 * squared * squared;
+    }
+    if m2 == 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    let variance = if num_buckets > 1 { m2 / (n - 1.0) } else { 0.0 };
+    let (m2, m3, m4) = (m2 / n, m3 / n, m4 / n);
+    (variance, m3 / m2.powf(1.5), m4 / (m2 * m2) - 3.0)
+}
+
+// Over (bucket count, number of buckets with that count) pairs in ascending
+// order of count, each run of equal counts taking its share of the ranks.
+fn gini(counts: impl Iterator<Item = (usize, usize)>, sum: usize, num_buckets: usize) -> f64 {
+    if sum == 0 {
+        return 0.0;
+    }
+    let (n, mut ranked, mut rank) = (num_buckets as f64, 0.0, 0.0);
+    for (count, freq) in counts {
+        let freq = freq as f64;
+        ranked += count as f64 * (freq * rank + freq * (freq + 1.0) / 2.0);
+        rank += freq;
+    }
+    2.0 * ranked / (n * sum as f64) - (n + 1.0) / n
+}
+
+// Over (bucket count, number of buckets with that count) pairs.
+fn chi_squared(counts: impl Iterator<Item = (usize, usize)>, sum: usize, num_buckets: usize) -> f64 {
+    if sum == 0 {
+        return 0.0;
+    }
+    let expected = sum as f64 / num_buckets as f64;
+    counts.map(|(count, freq)| freq as f64 * (count as f64 - expected).powi(2)).sum::<f64>() / expected
+}
+
+/// Upper tail probability of the chi-squared distribution with
+/// `degrees_of_freedom` at `statistic`.
+pub fn chi_squared_p_value(statistic: f64, degrees_of_freedom: usize) -> f64 {
+    if degrees_of_freedom == 0 || statistic <= 0.0 {
+        return 1.0;
+    }
+    upper_regularized_gamma(degrees_of_freedom as f64 / 2.0, statistic / 2.0)
+}
+
+/// Upper tail probability of Student's t distribution with
+/// `degrees_of_freedom` at `statistic`, a one-sided t-test's p-value.
+pub fn t_p_value(statistic: f64, degrees_of_freedom: usize) -> f64 {
+    if degrees_of_freedom == 0 || statistic.is_nan() {
+        return 1.0;
+    }
+    let df = degrees_of_freedom as f64;
+    let tail = 0.5 * regularized_beta(df / 2.0, 0.5, df / (df + statistic * statistic));
+    if statistic > 0.0 {
+        tail
+    } else {
+        1.0 - tail
+    }
+}
+
+// Lanczos approximation (g = 7, n = 9), accurate to ~15 digits for x > 0.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..].iter().enumerate().fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+// Q(a, x) by its power series below a + 1 and Lentz's continued fraction
+// above, as in Numerical Recipes' gammq.
+fn upper_regularized_gamma(a: f64, x: f64) -> f64 {
+    const EPSILON: f64 = 1e-15;
+    const MAX_ITERATIONS: usize = 100_000;
+    let prefactor = (a * x.ln() - x - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..MAX_ITERATIONS {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        (1.0 - sum * prefactor).max(0.0)
+    } else {
+        let tiny = f64::MIN_POSITIVE / EPSILON;
+        let mut b = x + 1.0 - a;
+        let (mut c, mut d) = (1.0 / tiny, 1.0 / b);
+        let mut fraction = d;
+        for i in 1..MAX_ITERATIONS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            fraction *= delta;
+            if (delta - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        fraction * prefactor
+    }
+}
+
+// I_x(a, b) by Lentz's continued fraction, as in Numerical Recipes' betai.
+fn regularized_beta(a: f64, b: f64, x: f64) -> f64 {
+    const EPSILON: f64 = 1e-15;
+    const MAX_ITERATIONS: usize = 10_000;
+    if x <= 0.0 || x >= 1.0 {
+        return x.clamp(0.0, 1.0);
+    }
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - regularized_beta(b, a, 1.0 - x);
+    }
+    let prefactor = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp() / a;
+    let tiny = f64::MIN_POSITIVE / EPSILON;
+    let (mut c, mut d) = (1.0, 1.0 - (a + b) * x / (a + 1.0));
+    if d.abs() < tiny {
+        d = tiny;
+    }
+    d = 1.0 / d;
+    let mut fraction = d;
+    for m in 1..MAX_ITERATIONS {
+        let m = m as f64;
+        for an in [m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)), -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0))] {
+            d = 1.0 + an * d;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = 1.0 + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            fraction *= d * c;
+        }
+        if (d * c - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    prefactor * fraction
+}
+
+fn normalized(spread: usize, max: usize, sum: usize, num_buckets: usize) -> (f64, f64) {
+    if sum == 0 {
+        return (0.0, 0.0);
+    }
+    let exact_mean = sum as f64 / num_buckets as f64;
+    (spread as f64 / exact_mean, max as f64 / exact_mean)
+}
+
+/// Single-pass equivalent of [`analyze_buckets`] that leaves `buckets`
+/// untouched. Order statistics come from a counting sort over the range of
+/// bucket counts, so when that range is wider than the number of buckets it
+/// falls back to sorting a copy.
+pub fn analyze_buckets_streaming(buckets: &[usize], tie_break: ModeTieBreak, percentiles: &[f64]) -> BucketAnalysis {
+    let num_buckets = buckets.len();
+    if num_buckets == 0 {
+        return BucketAnalysis { percentiles: vec![0; percentiles.len()], ..BucketAnalysis::default() };
+    }
+    let (mut min, mut max, mut sum) = (usize::MAX, 0, 0);
+    for count in buckets {
+        min = min.min(*count);
+        max = max.max(*count);
+        sum += count;
+    }
+    let spread = max - min;
+    if spread >= num_buckets {
+        return analyze_buckets(&mut buckets.to_vec(), tie_break, percentiles);
+    }
+    let mean = sum / num_buckets;
+    let mut histogram = vec![0usize; spread + 1];
+    for count in buckets {
+        histogram[count - min] += 1;
+    }
+    let (mut median, mut seen) = (min, 0);
+    let (mut mode, mut mode_count, mut mode_ties) = (0, 0, 0);
+    for (count, freq) in (min..).zip(histogram.iter().copied()).filter(|(_, freq)| *freq > 0) {
+        if seen <= num_buckets / 2 {
+            median = count;
+        }
+        seen += freq;
+        if freq > mode_count {
+            (mode, mode_count, mode_ties) = (count, freq, 1);
+        } else if freq == mode_count {
+            mode_ties += 1;
+            if tie_break == ModeTieBreak::Highest {
+                mode = count;
+            }
+        }
+    }
+    let mode_fraction = mode_count as f64 / num_buckets as f64;
+    let (variance, skewness, kurtosis) = moments((min..).zip(histogram.iter().copied()).filter(|(_, freq)| *freq > 0), sum, num_buckets);
+    let std_dev = variance.sqrt();
+    let empty_buckets = if min == 0 { histogram[0] } else { 0 };
+    let occupied_fraction = (num_buckets - empty_buckets) as f64 / num_buckets as f64;
+    let (spread_over_mean, max_over_mean) = normalized(spread, max, sum, num_buckets);
+    let gini = gini((min..).zip(histogram.iter().copied()).filter(|(_, freq)| *freq > 0), sum, num_buckets);
+    let chi_squared = chi_squared((min..).zip(histogram.iter().copied()), sum, num_buckets);
+    let p_value = chi_squared_p_value(chi_squared, num_buckets - 1);
+    let percentiles = percentiles
+        .iter()
+        .map(|percentile| {
+            let rank = percentile_rank(*percentile, num_buckets);
+            let mut seen = 0;
+            (min..).zip(histogram.iter()).find(|(_, freq)| {
+                seen += *freq;
+                seen > rank
+            }).map_or(max, |(count, _)| count)
+        })
+        .collect();
+
+    BucketAnalysis { min, max, spread, mean, median, mode, mode_count, mode_ties, mode_fraction, std_dev, variance, skewness, kurtosis, empty_buckets, occupied_fraction, spread_over_mean, max_over_mean, gini, chi_squared, p_value, percentiles }
+}
+
+#[derive(Debug)]
+pub struct AnalysisTimings {
+    pub sort_ns: f64,
+    pub streaming_ns: f64,
+}
+
+/// Nanoseconds per call of [`analyze_buckets`] and
+/// [`analyze_buckets_streaming`] over `buckets`, best of `rounds`. The sorting
+/// path is timed on a fresh copy each round; the copy is not timed.
+pub fn time_analysis(buckets: &[usize], tie_break: ModeTieBreak, rounds: usize) -> AnalysisTimings {
+    let best = |time: &mut dyn FnMut() -> f64| (0..rounds.max(1)).map(|_| time()).fold(f64::INFINITY, f64::min);
+    let sort_ns = best(&mut || {
+        let mut scratch = buckets.to_vec();
+        let start = Instant::now();
+        black_box(analyze_buckets(&mut scratch, tie_break, &DEFAULT_PERCENTILES));
+        start.elapsed().as_nanos() as f64
+    });
+    let streaming_ns = best(&mut || {
+        let start = Instant::now();
+        black_box(analyze_buckets_streaming(buckets, tie_break, &DEFAULT_PERCENTILES));
+        start.elapsed().as_nanos() as f64
+    });
+    AnalysisTimings { sort_ns, streaming_ns }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct OccupancyAnalysis {
+    pub occupied: usize,
+    pub collisions: usize,
+    pub expected_empty: f64,
+}
+
+impl fmt::Display for OccupancyAnalysis {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{}", self.occupied, self.collisions, self.expected_empty)
+    }
+}
+
+// Meaningful when there are more buckets than addresses, where most buckets
+// hold zero or one address and the load statistics above degenerate.
+pub fn analyze_occupancy(buckets: &[usize]) -> OccupancyAnalysis {
+    let num_buckets = buckets.len();
+    if num_buckets == 0 {
+        return OccupancyAnalysis::default();
+    }
+    let num_addresses = buckets.iter().sum::<usize>();
+    let occupied = buckets.iter().filter(|count| **count > 0).count();
+    let collisions = num_addresses - occupied;
+    let expected_empty = num_buckets as f64 * (1.0 - 1.0 / num_buckets as f64).powf(num_addresses as f64);
+
+    OccupancyAnalysis { occupied, collisions, expected_empty }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_buckets_no_buckets() {
+        assert_eq!(analyze_buckets(&mut [], ModeTieBreak::Lowest, &[]), BucketAnalysis::default());
+    }
+
+    #[test]
+    fn test_analyze_buckets_no_addresses() {
+        let analysis = analyze_buckets(&mut [0; 4], ModeTieBreak::Lowest, &[]);
+        assert_eq!(analysis, BucketAnalysis { mode_count: 4, mode_ties: 1, mode_fraction: 1.0, empty_buckets: 4, p_value: 1.0, ..BucketAnalysis::default() });
+    }
+
+    #[test]
+    fn test_analyze_buckets_single_bucket() {
+        let analysis = analyze_buckets(&mut [7], ModeTieBreak::Lowest, &[50.0]);
+        assert_eq!(analysis, BucketAnalysis {
+            min: 7,
+            max: 7,
+            spread: 0,
+            mean: 7,
+            median: 7,
+            mode: 7,
+            mode_count: 1,
+            mode_ties: 1,
+            mode_fraction: 1.0,
+            std_dev: 0.0,
+            variance: 0.0,
+            skewness: 0.0,
+            kurtosis: 0.0,
+            empty_buckets: 0,
+            occupied_fraction: 1.0,
+            spread_over_mean: 0.0,
+            max_over_mean: 1.0,
+            gini: 0.0,
+            chi_squared: 0.0,
+            p_value: 1.0,
+            percentiles: vec![7],
+        });
+    }
+
+    #[test]
+    fn test_moments() {
+        let analysis = analyze_buckets(&mut [4, 6, 5, 5], ModeTieBreak::Lowest, &[]);
+        assert_eq!((analysis.variance, analysis.skewness, analysis.kurtosis), (2.0 / 3.0, 0.0, -1.0));
+        assert_eq!(analysis.std_dev, (2.0f64 / 3.0).sqrt());
+        let skewed = analyze_buckets_streaming(&[0, 0, 0, 4], ModeTieBreak::Lowest, &[]);
+        assert_eq!(skewed.variance, 4.0);
+        assert!((skewed.skewness - 2.0 / 3.0f64.sqrt()).abs() < 1e-12);
+        assert!(analyze_buckets(&mut [1, 1, 1, 9, 1, 1, 1, 1], ModeTieBreak::Lowest, &[]).kurtosis > 0.0);
+    }
+
+    #[test]
+    fn test_chi_squared() {
+        let analysis = analyze_buckets(&mut [4, 6, 5, 5], ModeTieBreak::Lowest, &[]);
+        assert_eq!(analysis.chi_squared, 0.4);
+        assert_eq!(analyze_buckets_streaming(&[4, 6, 5, 5], ModeTieBreak::Lowest, &[]).chi_squared, 0.4);
+        assert!((analysis.p_value - chi_squared_p_value(0.4, 3)).abs() < 1e-15);
+        assert!(analyze_buckets(&mut [20, 0, 0, 0], ModeTieBreak::Lowest, &[]).p_value < 1e-10);
+    }
+
+    #[test]
+    fn test_chi_squared_p_value() {
+        // Two degrees of freedom have the closed form exp(-x / 2).
+        assert!((chi_squared_p_value(4.0, 2) - (-2.0f64).exp()).abs() < 1e-12);
+        // Tabulated 5% critical values.
+        assert!((chi_squared_p_value(3.841_458_820_694_124, 1) - 0.05).abs() < 1e-9);
+        assert!((chi_squared_p_value(123.225_221_453_361_8, 99) - 0.05).abs() < 1e-9);
+        assert!((chi_squared_p_value(10_232.737_266_236_884, 9_999) - 0.05).abs() < 1e-9);
+        assert_eq!(chi_squared_p_value(0.0, 99), 1.0);
+    }
+
+    #[test]
+    fn test_t_p_value() {
+        // One degree of freedom is the Cauchy distribution.
+        assert!((t_p_value(1.0, 1) - 0.25).abs() < 1e-12);
+        // Tabulated one-sided 5% critical values.
+        assert!((t_p_value(2.015_048_372_669_157, 5) - 0.05).abs() < 1e-9);
+        assert!((t_p_value(1.833_112_932_653_634, 9) - 0.05).abs() < 1e-9);
+        assert!((t_p_value(-1.833_112_932_653_634, 9) - 0.95).abs() < 1e-9);
+        assert_eq!(t_p_value(0.0, 9), 0.5);
+        assert_eq!(t_p_value(1.0, 0), 1.0);
+    }
+
+    #[test]
+    fn test_analyze_buckets_percentiles() {
+        let mut buckets = (1..=20).rev().collect::<Vec<_>>();
+        let analysis = analyze_buckets(&mut buckets, ModeTieBreak::Lowest, &[5.0, 10.0, 50.0, 99.0, 100.0]);
+        assert_eq!(analysis.percentiles, vec![1, 2, 10, 20, 20]);
+        assert!(analysis.to_string().ends_with(&format!("{},1,2,10,20,20", analysis.p_value)));
+        assert_eq!(percentile_header(&DEFAULT_PERCENTILES), "p10,p90,p99");
+        assert_eq!(percentile_header(&[99.9]), "p99.9");
+    }
+
+    #[test]
+    fn test_analyze_buckets_gini() {
+        assert_eq!(analyze_buckets(&mut [5, 5, 5, 5], ModeTieBreak::Lowest, &[]).gini, 0.0);
+        assert_eq!(analyze_buckets(&mut [0, 0, 0, 8], ModeTieBreak::Lowest, &[]).gini, 0.75);
+        // Mean absolute difference over all 16 ordered pairs, 12 / 16, over twice the mean.
+        assert!((analyze_buckets(&mut [1, 2, 2, 3], ModeTieBreak::Lowest, &[]).gini - 12.0 / 16.0 / (2.0 * 2.0)).abs() < 1e-12);
+        assert_eq!(analyze_buckets_streaming(&[0, 8, 0, 0], ModeTieBreak::Lowest, &[]).gini, 0.75);
+    }
+
+    #[test]
+    fn test_analyze_buckets_fewer_addresses_than_buckets() {
+        let analysis = analyze_buckets(&mut [0, 1, 0, 0, 1], ModeTieBreak::Lowest, &[]);
+        assert_eq!(analysis.min, 0);
+        assert_eq!(analysis.max, 1);
+        assert_eq!(analysis.mean, 0);
+        assert_eq!(analysis.mode, 0);
+        assert_eq!(analysis.mode_count, 3);
+        assert_eq!(analysis.mode_fraction, 0.6);
+        assert_eq!(analysis.empty_buckets, 3);
+        assert_eq!(analysis.occupied_fraction, 0.4);
+        assert_eq!(analysis.spread_over_mean, 2.5);
+        assert_eq!(analysis.max_over_mean, 2.5);
+    }
+
+    #[test]
+    fn test_analyze_buckets_mode_tie_break() {
+        let lowest = analyze_buckets(&mut [5, 3, 5, 9, 3, 9, 1], ModeTieBreak::Lowest, &[]);
+        assert_eq!((lowest.mode, lowest.mode_count, lowest.mode_ties), (3, 2, 3));
+        let highest = analyze_buckets(&mut [5, 3, 5, 9, 3, 9, 1], ModeTieBreak::Highest, &[]);
+        assert_eq!((highest.mode, highest.mode_count, highest.mode_ties), (9, 2, 3));
+    }
+
+    #[test]
+    fn test_analyze_buckets_normalized_independent_of_scale() {
+        let small = analyze_buckets(&mut [1, 2, 3, 6], ModeTieBreak::Lowest, &[]);
+        let large = analyze_buckets(&mut [100, 200, 300, 600], ModeTieBreak::Lowest, &[]);
+        assert_eq!(small.spread_over_mean, large.spread_over_mean);
+        assert_eq!(small.max_over_mean, large.max_over_mean);
+        assert_eq!(small.max_over_mean, 2.0);
+    }
+
+    #[test]
+    fn test_header_without_mode() {
+        let analysis = analyze_buckets(&mut [5, 3, 5, 9], ModeTieBreak::Lowest, &[]);
+        assert_eq!(analysis.to_string().split(',').count(), HEADER.split(',').count());
+        let mode_columns = ["mode", "mode_count", "mode_ties", "mode_fraction"];
+        assert_eq!(HEADER.split(',').filter(|column| !mode_columns.contains(column)).collect::<Vec<_>>().join(","), HEADER_WITHOUT_MODE);
+    }
+
+    #[test]
+    fn test_analysis_format() {
+        let analysis = analyze_buckets(&mut [5, 3, 5, 9], ModeTieBreak::Lowest, &[50.0]);
+        let metrics = analysis.metrics();
+        assert_eq!(metrics.len(), HEADER.split(',').count());
+        assert_eq!(AnalysisFormat::default().display(&analysis).to_string(), analysis.to_string());
+        let format = AnalysisFormat::new(&[50.0]).with_columns(&["p50".to_string(), "max".to_string(), "std_dev".to_string()]).unwrap().with_precision(2);
+        assert_eq!(format.header(), "p50,max,std_dev");
+        assert_eq!(format.display(&analysis).to_string(), format!("5,9,{:.2}", analysis.std_dev));
+        assert_eq!(AnalysisFormat::new(&[50.0]).header(), format!("{HEADER},p50"));
+        assert!(AnalysisFormat::new(&[50.0]).with_columns(&["p90".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_with_columns() {
+        let columns = |names: &str| names.split(',').map(str::to_string).collect::<Vec<_>>();
+        let analysis = analyze_buckets(&mut [5, 3, 5, 9], ModeTieBreak::Lowest, &[10.0, 90.0]);
+        let format = AnalysisFormat::new(&[10.0, 90.0]).with_columns(&columns("min,max,spread,chi2,p90")).unwrap();
+        assert_eq!(format.header(), "min,max,spread,chi_squared,p90");
+        assert_eq!(format.display(&analysis).to_string(), format!("3,9,6,{},9", analysis.chi_squared));
+        assert_eq!(AnalysisFormat::default().with_columns(&columns("chi2")).unwrap(), AnalysisFormat::default().with_columns(&columns("chi_squared")).unwrap());
+        let err = AnalysisFormat::new(&[10.0]).with_columns(&columns("min,chi3,max")).unwrap_err();
+        assert!(err.starts_with("unknown column chi3; expected some of min,max,"), "{err}");
+        assert!(err.ends_with(",p_value,p10"), "{err}");
+        assert!(AnalysisFormat::default().with_columns(&columns("p10")).is_err());
+    }
+
+    #[test]
+    fn test_long_rows() {
+        assert_eq!(long_rows("blake3", "epoch,min,max", "7,3,9"), "7,blake3,min,3\n7,blake3,max,9");
+        let analysis = analyze_buckets(&mut [5, 3, 5, 9], ModeTieBreak::Lowest, &[]);
+        let rows = long_rows("fnv", &format!("epoch,{HEADER}"), &format!("0,{analysis}"));
+        assert_eq!(rows.lines().count(), HEADER.split(',').count());
+        assert_eq!(rows.lines().last(), Some(format!("0,fnv,p_value,{}", analysis.p_value).as_str()));
+    }
+
+    fn assert_streaming_matches(buckets: &[usize]) {
+        for tie_break in [ModeTieBreak::Lowest, ModeTieBreak::Highest] {
+            let streaming = analyze_buckets_streaming(buckets, tie_break, &DEFAULT_PERCENTILES);
+            let sorted = analyze_buckets(&mut buckets.to_vec(), tie_break, &DEFAULT_PERCENTILES);
+            assert!((streaming.std_dev - sorted.std_dev).abs() < 1e-9, "{buckets:?}");
+            assert_eq!(BucketAnalysis { std_dev: 0.0, ..streaming }, BucketAnalysis { std_dev: 0.0, ..sorted }, "{buckets:?}");
+        }
+    }
+
+    #[test]
+    fn test_analyze_buckets_streaming_matches_sorting() {
+        assert_streaming_matches(&[]);
+        assert_streaming_matches(&[0; 4]);
+        assert_streaming_matches(&[7]);
+        assert_streaming_matches(&[0, 1, 0, 0, 1]);
+        assert_streaming_matches(&[5, 3, 5, 9, 3, 9, 1]);
+        assert_streaming_matches(&[1, 1000, 3]);
+        assert_streaming_matches(&[2, 2, 2, 2, 2, 2, 2, 2, 2, 3]);
+        let pseudo_random = (0u64..997).map(|i| (i.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 59) as usize + 40).collect::<Vec<_>>();
+        assert_streaming_matches(&pseudo_random);
+    }
+
+    #[test]
+    fn test_analyze_occupancy_sparse() {
+        let analysis = analyze_occupancy(&[0, 2, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(analysis.occupied, 2);
+        assert_eq!(analysis.collisions, 1);
+        assert!((analysis.expected_empty - 8.0 * (7.0f64 / 8.0).powi(3)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_analyze_occupancy_no_buckets() {
+        assert_eq!(analyze_occupancy(&[]), OccupancyAnalysis::default());
+    }
+}
@@ -0,0 +1,64 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    std::{ops::Range, str::FromStr},
+};
+
+pub const HEADER: &str = "epoch,bucket";
+
+/// Epochs to look an address up at: `N`, `A..B` or `A..=B`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Epochs(pub Range<u64>);
+
+impl FromStr for Epochs {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Self, String> {
+        let parse = |epoch: &str| epoch.parse::<u64>().map_err(|_| format!("expected an epoch, A..B or A..=B, got {arg:?}"));
+        let range = if let Some((start, end)) = arg.split_once("..=") {
+            parse(start)?..parse(end)?.checked_add(1).ok_or_else(|| format!("{arg:?} ends past the last epoch"))?
+        } else if let Some((start, end)) = arg.split_once("..") {
+            parse(start)?..parse(end)?
+        } else {
+            let epoch = parse(arg)?;
+            epoch..epoch.saturating_add(1)
+        };
+        if range.is_empty() {
+            return Err(format!("{arg:?} is empty"));
+        }
+        Ok(Self(range))
+    }
+}
+
+/// Records the bucket the visited hasher assigns one address.
+pub struct AssignVisitor<'a> {
+    pub buckets: usize,
+    pub reduction: Reduction,
+    pub address: &'a Pubkey2,
+    pub bucket: Option<usize>,
+}
+
+impl HasherVisitor for AssignVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, _name: &'static str, _key: &[u8], hasher: H) {
+        self.bucket = Some(address_to_bucket_with_reduction(self.buckets, self.reduction, hasher, self.address));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_epochs() {
+        assert_eq!("650".parse(), Ok(Epochs(650..651)));
+        assert_eq!("650..660".parse(), Ok(Epochs(650..660)));
+        assert_eq!("650..=660".parse(), Ok(Epochs(650..661)));
+        for bad in ["", "660..650", "650..650", "a..b", "-1", "1..=18446744073709551615"] {
+            assert!(bad.parse::<Epochs>().is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{self, AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Condvar, Mutex},
+        thread,
+    },
+};
+
+type AssignmentMap = Arc<HashMap<Pubkey, u32>>;
+
+/// One cached assignment: a registered hasher seeded for `epoch`, mixed with
+/// `nonce` as `--nonce` does.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct CacheKey {
+    pub hasher: String,
+    pub nonce: Option<u64>,
+    pub epoch: u64,
+}
+
+impl CacheKey {
+    fn seed(&self) -> u64 {
+        self.nonce.map_or(self.epoch, |nonce| hashers::nonce_seed(self.epoch, nonce))
+    }
+
+    fn with_epoch(&self, epoch: u64) -> Self {
+        Self { epoch, ..self.clone() }
+    }
+
+    fn same_keying(&self, other: &Self) -> bool {
+        self.hasher == other.hasher && self.nonce == other.nonce
+    }
+}
+
+struct BucketsVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    assigned: Vec<usize>,
+}
+
+impl HasherVisitor for BucketsVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, _name: &'static str, _key: &[u8], hasher: H) {
+        self.assigned = self.addresses.iter().map(|address| address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address)).collect();
+    }
+}
+
+#[derive(Default)]
+struct Maps {
+    ready: HashMap<CacheKey, AssignmentMap>,
+    pending: Vec<CacheKey>,
+    // The epoch each hasher and nonce was last advanced to; maps of older
+    // epochs are evicted, and dropped rather than inserted if they finish late.
+    current: Vec<CacheKey>,
+}
+
+impl Maps {
+    fn is_stale(&self, key: &CacheKey) -> bool {
+        self.current.iter().any(|current| current.same_keying(key) && key.epoch < current.epoch)
+    }
+}
+
+/// Assignment maps of a fixed address set for the current and next epoch
+/// of each hasher and nonce advanced to, computed in the background so
+/// lookups are a map hit rather than a hash per request. Addresses outside
+/// the set, or keys not cached, are hashed on demand.
+pub struct AssignmentCache {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: Arc<Vec<Pubkey2>>,
+    maps: Arc<(Mutex<Maps>, Condvar)>,
+}
+
+impl AssignmentCache {
+    pub fn new(addresses: Vec<Pubkey2>, buckets: usize, reduction: Reduction) -> Self {
+        Self { buckets, reduction, addresses: Arc::new(addresses), maps: Arc::default() }
+    }
+
+    /// Hashes `addresses` under `key` directly, or errors if its hasher isn't
+    /// registered.
+    fn assign(&self, key: &CacheKey, addresses: &[Pubkey2]) -> Result<Vec<usize>, String> {
+        let mut visitor = BucketsVisitor { buckets: self.buckets, reduction: self.reduction, addresses, assigned: Vec::new() };
+        if !hashers::visit_registered(&key.hasher, key.seed(), &mut visitor) {
+            return Err(format!("unknown hasher {}; expected one of {}", key.hasher, hashers::registered_names().join(", ")));
+        }
+        Ok(visitor.assigned)
+    }
+
+    /// Makes `key`'s epoch current for its hasher and nonce: starts
+    /// precomputing it and the epoch after in the background, and evicts
+    /// every older epoch of that hasher and nonce.
+    pub fn advance(&self, key: &CacheKey) -> Result<(), String> {
+        self.assign(key, &[])?;
+        let mut guard = self.maps.0.lock().unwrap();
+        guard.current.retain(|current| !current.same_keying(key));
+        guard.current.push(key.clone());
+        guard.ready.retain(|cached, _| !cached.same_keying(key) || cached.epoch >= key.epoch);
+        for key in [key.clone(), key.with_epoch(key.epoch + 1)] {
+            if guard.ready.contains_key(&key) || guard.pending.contains(&key) {
+                continue;
+            }
+            guard.pending.push(key.clone());
+            let cache = Self { buckets: self.buckets, reduction: self.reduction, addresses: Arc::clone(&self.addresses), maps: Arc::clone(&self.maps) };
+            thread::spawn(move || {
+                let assigned = cache.assign(&key, &cache.addresses).unwrap();
+                let map = cache.addresses.iter().zip(assigned).map(|(address, bucket)| (**address, bucket as u32)).collect::<HashMap<_, _>>();
+                let (lock, ready) = &*cache.maps;
+                let mut guard = lock.lock().unwrap();
+                guard.pending.retain(|pending| *pending != key);
+                // Checked under the lock eviction holds, so a map advanced
+                // past while computing is never cached.
+                if !guard.is_stale(&key) {
+                    guard.ready.insert(key, Arc::new(map));
+                }
+                ready.notify_all();
+            });
+        }
+        Ok(())
+    }
+
+    /// Blocks until `key`'s map is cached, if it's being computed.
+    pub fn wait_ready(&self, key: &CacheKey) -> bool {
+        let (maps, ready) = &*self.maps;
+        let guard = ready.wait_while(maps.lock().unwrap(), |maps| maps.pending.contains(key)).unwrap();
+        guard.ready.contains_key(key)
+    }
+
+    fn cached(&self, key: &CacheKey) -> Option<AssignmentMap> {
+        self.maps.0.lock().unwrap().ready.get(key).cloned()
+    }
+
+    pub fn lookup(&self, key: &CacheKey, address: &Pubkey2) -> Result<usize, String> {
+        Ok(self.lookup_batch(key, std::slice::from_ref(address))?[0])
+    }
+
+    /// Looks up a batch against one snapshot of the key's map.
+    pub fn lookup_batch(&self, key: &CacheKey, addresses: &[Pubkey2]) -> Result<Vec<usize>, String> {
+        let Some(map) = self.cached(key) else {
+            return self.assign(key, addresses);
+        };
+        let missing = addresses.iter().filter(|address| !map.contains_key(&address.0)).cloned().collect::<Vec<_>>();
+        let mut hashed = self.assign(key, &missing)?.into_iter();
+        Ok(addresses.iter().map(|address| map.get(&**address).map_or_else(|| hashed.next().unwrap(), |bucket| *bucket as usize)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher};
+
+    fn key(hasher: &str, nonce: Option<u64>, epoch: u64) -> CacheKey {
+        CacheKey { hasher: hasher.to_string(), nonce, epoch }
+    }
+
+    #[test]
+    fn test_cache_matches_hashing() {
+        let addresses = (0..100).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let cache = AssignmentCache::new(addresses.clone(), 16, Reduction::Multiply);
+        let hashed = |epoch, address| address_to_bucket_with_reduction(16, Reduction::Multiply, Blake3Hasher::new_with_seed(epoch), address);
+        assert!(!cache.wait_ready(&key("blake3", None, 7)));
+        cache.advance(&key("blake3", None, 7)).unwrap();
+        assert!(cache.wait_ready(&key("blake3", None, 7)));
+        assert!(cache.wait_ready(&key("blake3", None, 8)));
+        let outsider = Pubkey2(Pubkey::new_unique());
+        for epoch in [7, 8, 9] {
+            for address in addresses.iter().chain([&outsider]) {
+                assert_eq!(cache.lookup(&key("blake3", None, epoch), address), Ok(hashed(epoch, address)));
+            }
+        }
+        let batch = addresses.iter().chain([&outsider]).cloned().collect::<Vec<_>>();
+        assert_eq!(cache.lookup_batch(&key("blake3", None, 8), &batch).unwrap(), batch.iter().map(|address| hashed(8, address)).collect::<Vec<_>>());
+        cache.advance(&key("blake3", None, 8)).unwrap();
+        assert!(!cache.maps.0.lock().unwrap().ready.contains_key(&key("blake3", None, 7)));
+        assert!(cache.wait_ready(&key("blake3", None, 9)));
+        assert!(cache.advance(&key("nope", None, 0)).is_err());
+    }
+
+    #[test]
+    fn test_cache_keys_on_hasher_and_nonce() {
+        let addresses = (0..100).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let cache = AssignmentCache::new(addresses.clone(), 16, Reduction::Multiply);
+        let keys = [key("blake3", None, 3), key("blake3", Some(5), 3), key("xxh3", Some(5), 3)];
+        for key in &keys {
+            cache.advance(key).unwrap();
+            assert!(cache.wait_ready(key));
+        }
+        for key in &keys {
+            assert_eq!(cache.lookup_batch(key, &addresses).unwrap(), cache.assign(key, &addresses).unwrap());
+        }
+        assert_ne!(cache.lookup_batch(&keys[0], &addresses), cache.lookup_batch(&keys[1], &addresses));
+        // Advancing one nonce leaves the other's maps cached.
+        cache.advance(&key("blake3", Some(5), 4)).unwrap();
+        assert!(cache.wait_ready(&keys[0]));
+        assert!(!cache.wait_ready(&keys[1]));
+    }
+
+    #[test]
+    fn test_late_map_of_evicted_epoch_is_dropped() {
+        let mut maps = Maps::default();
+        maps.current.push(key("blake3", None, 9));
+        assert!(maps.is_stale(&key("blake3", None, 7)));
+        assert!(!maps.is_stale(&key("blake3", None, 10)));
+        assert!(!maps.is_stale(&key("blake3", Some(1), 7)));
+    }
+}
@@ -0,0 +1,340 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction, assign_each,
+        hashers::{self, AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    clap::ValueEnum,
+    std::{
+        fs::File,
+        io::{self, BufWriter, Write},
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+};
+
+pub const DUMP_HEADER: &str = "address,bucket";
+pub const ORDERED_DUMP_HEADER: &str = "address,bucket,position";
+
+/// Width of each little-endian bucket index in an exported assignment map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum IndexEncoding {
+    U8,
+    U16,
+    #[default]
+    U32,
+}
+
+impl IndexEncoding {
+    pub fn bytes(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16 => 2,
+            Self::U32 => 4,
+        }
+    }
+
+    pub fn validate(self, buckets: usize) -> Result<(), String> {
+        let limit = 1u64 << (8 * self.bytes());
+        if buckets as u64 > limit {
+            return Err(format!("{} bucket indices can't address {buckets} buckets", self.extension()));
+        }
+        Ok(())
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+        }
+    }
+
+    pub fn encode(self, bucket: usize, out: &mut impl Write) -> io::Result<()> {
+        match self {
+            Self::U8 => out.write_all(&[bucket as u8]),
+            Self::U16 => out.write_all(&(bucket as u16).to_le_bytes()),
+            Self::U32 => out.write_all(&(bucket as u32).to_le_bytes()),
+        }
+    }
+}
+
+/// Writes each visited hasher's bucket for every address, in input order and
+/// without the addresses themselves, to `<dir>/<hasher>-epoch-<epoch>.<encoding>`.
+pub struct MapVisitor<'a> {
+    pub dir: &'a Path,
+    pub epoch: u64,
+    pub buckets: usize,
+    pub reduction: Reduction,
+    pub encoding: IndexEncoding,
+    pub addresses: &'a [Pubkey2],
+    pub written: Vec<PathBuf>,
+}
+
+impl HasherVisitor for MapVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let path = self.dir.join(format!("{name}-epoch-{}.{}", self.epoch, self.encoding.extension()));
+        let mut out = BufWriter::new(File::create(&path).unwrap());
+        for address in self.addresses {
+            let bucket = address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address);
+            self.encoding.encode(bucket, &mut out).unwrap();
+        }
+        out.flush().unwrap();
+        self.written.push(path);
+    }
+}
+
+/// The epochs whose assignments `--dump-assignments` writes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DumpEpochs {
+    Epoch(u64),
+    /// Every epoch of the run, each to its own file
+    All,
+}
+
+impl FromStr for DumpEpochs {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Self, String> {
+        match arg {
+            "all" => Ok(Self::All),
+            epoch => epoch.parse().map(Self::Epoch).map_err(|_| format!("expected an epoch or \"all\", got {arg:?}")),
+        }
+    }
+}
+
+/// `out` with `-<hasher>` and `-epoch-<epoch>` inserted before the
+/// extension, for whichever of the two are given.
+pub fn dump_path(out: &Path, hasher: Option<&str>, epoch: Option<u64>) -> PathBuf {
+    let mut name = out.file_stem().unwrap_or_default().to_os_string();
+    if let Some(hasher) = hasher {
+        name.push(format!("-{hasher}"));
+    }
+    if let Some(epoch) = epoch {
+        name.push(format!("-epoch-{epoch}"));
+    }
+    if let Some(extension) = out.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    out.with_file_name(name)
+}
+
+/// A canonical order of the addresses within each bucket: ascending by a
+/// secondary hasher's hash of the address, keyed independently of the
+/// assignment, then by address.
+#[derive(Clone, Copy, Debug)]
+pub struct BucketOrder<'a> {
+    pub hasher: &'a str,
+    /// The assigning hasher's seed, from which the secondary seed is derived
+    pub seed: u64,
+}
+
+impl BucketOrder<'_> {
+    pub fn secondary_seed(&self) -> u64 {
+        let key = blake3::derive_key("hash-to-bucket bucket order", &self.seed.to_le_bytes());
+        u64::from_le_bytes(key[..8].try_into().unwrap())
+    }
+
+    /// Indices of `addresses` grouped by ascending bucket, each bucket in
+    /// this order.
+    pub fn sort(&self, addresses: &[Pubkey2], buckets: &[usize]) -> Vec<usize> {
+        let mut keys = SecondaryKeys { addresses, keys: Vec::new() };
+        assert!(hashers::visit_registered(self.hasher, self.secondary_seed(), &mut keys), "unknown hasher {}", self.hasher);
+        let mut order = (0..addresses.len()).collect::<Vec<_>>();
+        order.sort_unstable_by_key(|index| (buckets[*index], keys.keys[*index], addresses[*index].to_bytes()));
+        order
+    }
+}
+
+struct SecondaryKeys<'a> {
+    addresses: &'a [Pubkey2],
+    keys: Vec<u64>,
+}
+
+impl HasherVisitor for SecondaryKeys<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, _name: &'static str, _key: &[u8], hasher: H) {
+        self.keys = self
+            .addresses
+            .iter()
+            .map(|address| {
+                let mut hasher = hasher.clone();
+                hasher.write(address.as_ref());
+                hasher.finish()
+            })
+            .collect();
+    }
+}
+
+/// Writes each visited hasher's `address,bucket` pairs to the file `path`
+/// names for it: in input order, or with an `order`, grouped by bucket in
+/// that order along with each address's position in its bucket.
+pub struct DumpVisitor<'a, P> {
+    pub buckets: usize,
+    pub reduction: Reduction,
+    pub addresses: &'a [Pubkey2],
+    pub order: Option<BucketOrder<'a>>,
+    pub path: P,
+    pub written: Vec<PathBuf>,
+}
+
+impl<P: FnMut(&'static str) -> PathBuf> HasherVisitor for DumpVisitor<'_, P> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let path = (self.path)(name);
+        let mut out = BufWriter::new(File::create(&path).unwrap_or_else(|err| panic!("{}: {err}", path.display())));
+        let addresses = self.addresses;
+        match self.order {
+            None => {
+                writeln!(out, "{DUMP_HEADER}").unwrap();
+                assign_each(hasher, self.buckets, self.reduction, addresses, |index, bucket| writeln!(out, "{},{bucket}", *addresses[index]).unwrap());
+            }
+            Some(order) => {
+                writeln!(out, "{ORDERED_DUMP_HEADER}").unwrap();
+                let mut buckets = vec![0; addresses.len()];
+                assign_each(hasher, self.buckets, self.reduction, addresses, |index, bucket| buckets[index] = bucket);
+                let (mut previous, mut position) = (None, 0);
+                for index in order.sort(addresses, &buckets) {
+                    let bucket = buckets[index];
+                    position = if previous == Some(bucket) { position + 1 } else { 0 };
+                    previous = Some(bucket);
+                    writeln!(out, "{},{bucket},{position}", *addresses[index]).unwrap();
+                }
+            }
+        }
+        out.flush().unwrap();
+        self.written.push(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rand::seq::SliceRandom};
+
+    fn decode(encoding: IndexEncoding, bytes: &[u8]) -> Vec<usize> {
+        bytes
+            .chunks_exact(encoding.bytes())
+            .map(|index| index.iter().rev().fold(0, |bucket, byte| (bucket << 8) | *byte as usize))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(IndexEncoding::U8.validate(256), Ok(()));
+        assert!(IndexEncoding::U8.validate(257).is_err());
+        assert_eq!(IndexEncoding::U16.validate(65_536), Ok(()));
+        assert!(IndexEncoding::U16.validate(65_537).is_err());
+        assert_eq!(IndexEncoding::U32.validate(432_000), Ok(()));
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let buckets = [0, 1, 255, 7];
+        for encoding in [IndexEncoding::U8, IndexEncoding::U16, IndexEncoding::U32] {
+            let mut out = Vec::new();
+            for bucket in buckets {
+                encoding.encode(bucket, &mut out).unwrap();
+            }
+            assert_eq!(out.len(), buckets.len() * encoding.bytes());
+            assert_eq!(decode(encoding, &out), buckets);
+        }
+        let mut out = Vec::new();
+        IndexEncoding::U16.encode(0x1234, &mut out).unwrap();
+        assert_eq!(out, [0x34, 0x12]);
+    }
+
+    #[test]
+    fn test_dump() {
+        assert_eq!(dump_path(Path::new("out/assignments.csv"), None, None), Path::new("out/assignments.csv"));
+        assert_eq!(dump_path(Path::new("out/assignments.csv"), Some("xxh3"), Some(4)), Path::new("out/assignments-xxh3-epoch-4.csv"));
+        assert_eq!(dump_path(Path::new("assignments"), None, Some(4)), Path::new("assignments-epoch-4"));
+        assert_eq!("all".parse(), Ok(DumpEpochs::All));
+        assert_eq!("7".parse(), Ok(DumpEpochs::Epoch(7)));
+        assert!("-1".parse::<DumpEpochs>().is_err());
+
+        let dir = std::env::temp_dir().join(format!("hash-to-bucket-dump-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Past ten buckets, so they only order correctly as numbers.
+        let addresses = (0..40).map(|_| Pubkey2(solana_sdk::pubkey::Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = DumpVisitor { buckets: 12, reduction: Reduction::Multiply, addresses: &addresses, order: None, path: |name| dir.join(name), written: Vec::new() };
+        let hasher = crate::hashers::Blake3Hasher::new_with_seed(2);
+        visitor.visit("blake3", &[], hasher.clone());
+        let dump = std::fs::read_to_string(&visitor.written[0]).unwrap();
+        let expected = addresses.iter().map(|address| format!("{},{}\n", **address, address_to_bucket_with_reduction(12, Reduction::Multiply, hasher.clone(), address)));
+        assert_eq!(dump, format!("{DUMP_HEADER}\n{}", expected.collect::<String>()));
+
+        // Ordered, the same pairs come grouped by bucket, positions counting up in each.
+        let mut visitor = DumpVisitor { order: Some(BucketOrder { hasher: "xxh3", seed: 2 }), written: Vec::new(), ..visitor };
+        visitor.visit("blake3", &[], hasher.clone());
+        let ordered = std::fs::read_to_string(&visitor.written[0]).unwrap();
+        let rows = ordered
+            .lines()
+            .skip(1)
+            .map(|row| match row.split(',').collect::<Vec<_>>()[..] {
+                [address, bucket, position] => (address, bucket.parse::<usize>().unwrap(), position.parse::<usize>().unwrap()),
+                _ => panic!("malformed row {row}"),
+            })
+            .collect::<Vec<_>>();
+        let mut pairs = rows.iter().map(|(address, bucket, _)| format!("{address},{bucket}")).collect::<Vec<_>>();
+        let mut expected = dump.lines().skip(1).map(String::from).collect::<Vec<_>>();
+        pairs.sort();
+        expected.sort();
+        assert_eq!(pairs, expected);
+        assert_eq!(rows[0].2, 0);
+        for pair in rows.windows(2) {
+            assert!(pair[0].1 <= pair[1].1);
+            assert_eq!(pair[1].2, if pair[0].1 == pair[1].1 { pair[0].2 + 1 } else { 0 });
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_bucket_order() {
+        let addresses = (0..100).map(|_| Pubkey2(solana_sdk::pubkey::Pubkey::new_unique())).collect::<Vec<_>>();
+        let buckets = (0..100).map(|index| index % 4).collect::<Vec<_>>();
+        let order = BucketOrder { hasher: "xxh3", seed: 5 };
+        let sorted = order.sort(&addresses, &buckets);
+        assert!(sorted.windows(2).all(|pair| buckets[pair[0]] <= buckets[pair[1]]));
+        assert_ne!(sorted, BucketOrder { seed: 6, ..order }.sort(&addresses, &buckets));
+        // The order depends on the addresses alone, not their input order.
+        let reversed = order.sort(&addresses.iter().rev().cloned().collect::<Vec<_>>(), &buckets.iter().rev().copied().collect::<Vec<_>>());
+        assert_eq!(reversed.iter().map(|index| 99 - index).collect::<Vec<_>>(), sorted);
+    }
+
+    #[test]
+    fn test_bucket_order_vectors() {
+        // Pinned ordered dumps of 24 fixed addresses in 4 buckets under blake3 at
+        // seed 650, once per portable secondary hasher. Consumers building
+        // distribution transactions from the order rely on these bytes.
+        let addresses = (0u64..24).map(|index| Pubkey2(solana_sdk::pubkey::Pubkey::new_from_array(*blake3::hash(&index.to_le_bytes()).as_bytes()))).collect::<Vec<_>>();
+        assert_eq!(BucketOrder { hasher: "xxh3", seed: 650 }.secondary_seed(), 12168111098529613405);
+        let dir = std::env::temp_dir().join(format!("hash-to-bucket-vectors-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dump = |addresses: &[Pubkey2]| {
+            let mut vectors = "secondary,address,bucket,position\n".to_string();
+            for secondary in hashers::registered_names().into_iter().filter(|name| hashers::is_portable(name)) {
+                let mut visitor = DumpVisitor {
+                    buckets: 4,
+                    reduction: Reduction::Multiply,
+                    addresses,
+                    order: Some(BucketOrder { hasher: secondary, seed: 650 }),
+                    path: |name| dir.join(name),
+                    written: Vec::new(),
+                };
+                visitor.visit("blake3", &[], crate::hashers::Blake3Hasher::new_with_seed(650));
+                let rows = std::fs::read_to_string(&visitor.written[0]).unwrap();
+                vectors.extend(rows.lines().skip(1).map(|row| format!("{secondary},{row}\n")));
+            }
+            vectors
+        };
+        let vectors = dump(&addresses);
+        assert_eq!(vectors, include_str!("../tests/vectors/intra-bucket-order.csv"));
+        // Input order doesn't matter.
+        let mut shuffled = addresses.clone();
+        for seed in 0..4 {
+            shuffled.shuffle(&mut crate::rng::stream(seed, "test", 0));
+            assert_eq!(dump(&shuffled), vectors);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
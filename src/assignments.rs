@@ -0,0 +1,71 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        rng,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    rand::seq::index,
+    std::fmt,
+};
+
+pub const HEADER: &str = "epoch,hasher,pubkey,bucket";
+
+pub struct Assignment {
+    pub epoch: u64,
+    pub hasher: &'static str,
+    pub pubkey: String,
+    pub bucket: usize,
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{}", self.epoch, self.hasher, self.pubkey, self.bucket)
+    }
+}
+
+/// `k` distinct addresses drawn uniformly at random, a fresh draw per epoch,
+/// in input order.
+pub fn sample(addresses: &[Pubkey2], k: usize, rng_seed: u64, epoch: u64) -> Vec<&Pubkey2> {
+    let mut indices = index::sample(&mut rng::stream(rng_seed, "sample-assignments", epoch), addresses.len(), k.min(addresses.len())).into_vec();
+    indices.sort_unstable();
+    indices.into_iter().map(|index| &addresses[index]).collect()
+}
+
+/// Records the bucket each visited hasher assigns every sampled address.
+pub struct AssignmentVisitor<'a> {
+    pub epoch: u64,
+    pub buckets: usize,
+    pub reduction: Reduction,
+    pub sample: Vec<&'a Pubkey2>,
+    pub assignments: Vec<Assignment>,
+}
+
+impl HasherVisitor for AssignmentVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        for address in &self.sample {
+            self.assignments.push(Assignment {
+                epoch: self.epoch,
+                hasher: name,
+                pubkey: address.to_string(),
+                bucket: address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn test_sample() {
+        let addresses = (0..100).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let first = sample(&addresses, 10, 0, 3);
+        assert_eq!(first.len(), 10);
+        assert!(first.windows(2).all(|pair| pair[0].to_bytes() != pair[1].to_bytes()));
+        assert_eq!(first.iter().map(|address| address.to_bytes()).collect::<Vec<_>>(), sample(&addresses, 10, 0, 3).iter().map(|address| address.to_bytes()).collect::<Vec<_>>());
+        assert_eq!(sample(&addresses, 1000, 0, 3).len(), 100);
+    }
+}
@@ -0,0 +1,130 @@
+use {
+    crate::{
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    std::{fmt, fmt::Write},
+};
+
+pub const SEEDS_HEADER: &str = "hasher,epoch,key";
+pub const HEADER: &str = "hasher,epoch,address,key,input,digest,hash,bucket";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+        write!(hex, "{byte:02x}").unwrap();
+        hex
+    })
+}
+
+pub struct SeedRecord {
+    pub hasher: &'static str,
+    pub epoch: u64,
+    pub key: Vec<u8>,
+}
+
+impl fmt::Display for SeedRecord {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{}", self.hasher, self.epoch, hex(&self.key))
+    }
+}
+
+/// Every intermediate value of one assignment, enough for a third party to
+/// reproduce it step by step: key bytes, hashed bytes, the hasher's complete
+/// digest, the 64-bit value fed to the reduction, and the resulting bucket.
+pub struct AuditRecord {
+    pub hasher: &'static str,
+    pub epoch: u64,
+    pub address: String,
+    pub key: Vec<u8>,
+    pub input: Vec<u8>,
+    pub digest: Vec<u8>,
+    pub hash: u64,
+    pub bucket: usize,
+}
+
+impl fmt::Display for AuditRecord {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            formatter,
+            "{},{},{},{},{},{},{},{}",
+            self.hasher,
+            self.epoch,
+            self.address,
+            hex(&self.key),
+            hex(&self.input),
+            hex(&self.digest),
+            self.hash,
+            self.bucket,
+        )
+    }
+}
+
+pub struct AuditVisitor<'a> {
+    pub epoch: u64,
+    pub buckets: usize,
+    pub reduction: Reduction,
+    pub addresses: &'a [&'a Pubkey2],
+    pub seeds: Vec<SeedRecord>,
+    pub records: Vec<AuditRecord>,
+}
+
+impl HasherVisitor for AuditVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, key: &[u8], hasher: H) {
+        self.seeds.push(SeedRecord { hasher: name, epoch: self.epoch, key: key.to_vec() });
+        for address in self.addresses {
+            let mut hasher = hasher.clone();
+            let input = address.as_ref();
+            hasher.write(input);
+            let hash = hasher.finish();
+            self.records.push(AuditRecord {
+                hasher: name,
+                epoch: self.epoch,
+                address: address.to_string(),
+                key: key.to_vec(),
+                input: input.to_vec(),
+                digest: hasher.digest(),
+                hash,
+                bucket: self.reduction.reduce(self.buckets, hash),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{address_to_bucket_with_epoch_hasher, hashers::Blake3Hasher},
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[]), "");
+        assert_eq!(hex(&[0x00, 0x0f, 0xab]), "000fab");
+    }
+
+    #[test]
+    fn test_audit_visitor_matches_assignment() {
+        let address = Pubkey2(Pubkey::new_unique());
+        let addresses = [&address];
+        let mut visitor = AuditVisitor {
+            epoch: 5,
+            buckets: 100,
+            reduction: Reduction::Multiply,
+            addresses: &addresses,
+            seeds: Vec::new(),
+            records: Vec::new(),
+        };
+        visitor.visit("blake3", &Blake3Hasher::key(5), Blake3Hasher::new_with_seed(5));
+
+        assert_eq!(visitor.seeds.len(), 1);
+        assert_eq!(visitor.seeds[0].key, Blake3Hasher::key(5));
+        let record = &visitor.records[0];
+        assert_eq!(record.input, address.as_ref());
+        assert_eq!(record.digest, blake3::keyed_hash(&Blake3Hasher::key(5), address.as_ref()).as_bytes());
+        assert_eq!(record.hash.to_le_bytes(), record.digest[..8]);
+        assert_eq!(record.bucket, address_to_bucket_with_epoch_hasher(100, Blake3Hasher::new_with_seed(5), &address));
+    }
+}
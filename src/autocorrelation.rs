@@ -0,0 +1,95 @@
+use std::fmt;
+
+pub const HEADER: &str = "epoch,same_bucket,expected_same_bucket,adjacent_bucket,expected_adjacent_bucket,correlation";
+
+/// How the assignments of one epoch relate to those of the epoch before it.
+/// With independent seeds an address lands in the same bucket with
+/// probability 1/m, in a neighbouring bucket with probability ~2/m, and the
+/// bucket indices are uncorrelated. Excess in any of these points at weak
+/// seed mixing surviving the multiply-shift reduction.
+#[derive(Debug, Default, PartialEq)]
+pub struct Autocorrelation {
+    pub same_bucket: f64,
+    pub expected_same_bucket: f64,
+    pub adjacent_bucket: f64,
+    pub expected_adjacent_bucket: f64,
+    pub correlation: f64,
+}
+
+impl fmt::Display for Autocorrelation {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{}", self.same_bucket, self.expected_same_bucket, self.adjacent_bucket, self.expected_adjacent_bucket, self.correlation)
+    }
+}
+
+pub fn analyze_autocorrelation(buckets: usize, previous: &[usize], current: &[usize]) -> Autocorrelation {
+    assert_eq!(previous.len(), current.len());
+    let num_addresses = current.len();
+    if num_addresses == 0 || buckets == 0 {
+        return Autocorrelation::default();
+    }
+    let m = buckets as f64;
+    let n = num_addresses as f64;
+
+    let same = previous.iter().zip(current).filter(|(p, c)| p == c).count();
+    let adjacent = previous.iter().zip(current).filter(|(p, c)| p.abs_diff(**c) == 1).count();
+
+    let mean_previous = previous.iter().sum::<usize>() as f64 / n;
+    let mean_current = current.iter().sum::<usize>() as f64 / n;
+    let (mut covariance, mut variance_previous, mut variance_current) = (0.0, 0.0, 0.0);
+    for (p, c) in previous.iter().zip(current) {
+        let dp = *p as f64 - mean_previous;
+        let dc = *c as f64 - mean_current;
+        covariance += dp * dc;
+        variance_previous += dp * dp;
+        variance_current += dc * dc;
+    }
+    let correlation = if variance_previous == 0.0 || variance_current == 0.0 {
+        0.0
+    } else {
+        covariance / (variance_previous * variance_current).sqrt()
+    };
+
+    Autocorrelation {
+        same_bucket: same as f64 / n,
+        expected_same_bucket: 1.0 / m,
+        adjacent_bucket: adjacent as f64 / n,
+        expected_adjacent_bucket: 2.0 * (m - 1.0) / (m * m),
+        correlation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_autocorrelation_identical() {
+        let assignments = [0, 1, 2, 3];
+        let analysis = analyze_autocorrelation(4, &assignments, &assignments);
+        assert_eq!(analysis.same_bucket, 1.0);
+        assert_eq!(analysis.adjacent_bucket, 0.0);
+        assert!((analysis.correlation - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_analyze_autocorrelation_shifted() {
+        let analysis = analyze_autocorrelation(4, &[0, 1, 2, 3], &[1, 2, 3, 2]);
+        assert_eq!(analysis.same_bucket, 0.0);
+        assert_eq!(analysis.adjacent_bucket, 1.0);
+        assert_eq!(analysis.expected_same_bucket, 0.25);
+        assert_eq!(analysis.expected_adjacent_bucket, 0.375);
+    }
+
+    #[test]
+    fn test_analyze_autocorrelation_single_bucket() {
+        let analysis = analyze_autocorrelation(1, &[0, 0], &[0, 0]);
+        assert_eq!(analysis.same_bucket, 1.0);
+        assert_eq!(analysis.correlation, 0.0);
+    }
+
+    #[test]
+    fn test_analyze_autocorrelation_no_addresses() {
+        assert_eq!(analyze_autocorrelation(4, &[], &[]), Autocorrelation::default());
+    }
+}
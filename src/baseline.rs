@@ -0,0 +1,172 @@
+use {
+    crate::{analysis::BucketAnalysis, hashers},
+    serde::{Deserialize, Serialize},
+    std::{
+        borrow::Cow,
+        collections::HashMap,
+        fmt,
+        io::{self, Write},
+        time::Duration,
+    },
+};
+
+pub const HEADER: &str = "hasher,portable,spread,std_dev,max_over_mean,empty_buckets,time";
+
+const BASELINE: &str = "blake3";
+
+#[derive(Debug, Default)]
+struct Totals {
+    epochs: u64,
+    spread: f64,
+    std_dev: f64,
+    max_over_mean: f64,
+    empty_buckets: f64,
+    time: Duration,
+}
+
+impl Totals {
+    fn means(&self) -> [f64; 5] {
+        let epochs = self.epochs.max(1) as f64;
+        [self.spread / epochs, self.std_dev / epochs, self.max_over_mean / epochs, self.empty_buckets / epochs, self.time.as_secs_f64() / epochs]
+    }
+}
+
+/// A hasher's per-epoch means divided by blake3's over the same epochs.
+/// Lower is better throughout; 1 is parity.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct BaselineRatios {
+    pub hasher: Cow<'static, str>,
+    pub spread: f64,
+    pub std_dev: f64,
+    pub max_over_mean: f64,
+    pub empty_buckets: f64,
+    pub time: f64,
+}
+
+impl fmt::Display for BaselineRatios {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{},{}", self.hasher, hashers::is_portable(&self.hasher), self.spread, self.std_dev, self.max_over_mean, self.empty_buckets, self.time)
+    }
+}
+
+/// A hasher's per-epoch means over the run.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct HasherSummary {
+    pub hasher: Cow<'static, str>,
+    pub portable: bool,
+    pub epochs: u64,
+    pub spread: f64,
+    pub std_dev: f64,
+    pub max_over_mean: f64,
+    pub empty_buckets: f64,
+    pub time_us: f64,
+}
+
+fn ratio(value: f64, baseline: f64) -> f64 {
+    match (value == 0.0, baseline == 0.0) {
+        (true, true) => 1.0,
+        (false, true) => f64::INFINITY,
+        _ => value / baseline,
+    }
+}
+
+/// Accumulates every hasher's analyses so they can be expressed relative to
+/// blake3's.
+#[derive(Default)]
+pub struct BaselineComparison {
+    names: Vec<&'static str>,
+    totals: HashMap<&'static str, Totals>,
+}
+
+impl BaselineComparison {
+    pub fn record(&mut self, hasher: &'static str, analysis: &BucketAnalysis, time: Duration) {
+        let totals = self.totals.entry(hasher).or_insert_with(|| {
+            self.names.push(hasher);
+            Totals::default()
+        });
+        totals.epochs += 1;
+        totals.spread += analysis.spread as f64;
+        totals.std_dev += analysis.std_dev;
+        totals.max_over_mean += analysis.max_over_mean;
+        totals.empty_buckets += analysis.empty_buckets as f64;
+        totals.time += time;
+    }
+
+    /// Empty unless blake3 was recorded.
+    pub fn ratios(&self) -> Vec<BaselineRatios> {
+        let Some(baseline) = self.totals.get(BASELINE).map(Totals::means) else {
+            return Vec::new();
+        };
+        self.names
+            .iter()
+            .map(|name| {
+                let [spread, std_dev, max_over_mean, empty_buckets, time] = self.totals[name].means();
+                BaselineRatios {
+                    hasher: Cow::Borrowed(name),
+                    spread: ratio(spread, baseline[0]),
+                    std_dev: ratio(std_dev, baseline[1]),
+                    max_over_mean: ratio(max_over_mean, baseline[2]),
+                    empty_buckets: ratio(empty_buckets, baseline[3]),
+                    time: ratio(time, baseline[4]),
+                }
+            })
+            .collect()
+    }
+
+    /// Every hasher's per-epoch means, in the order first recorded.
+    pub fn summaries(&self) -> Vec<HasherSummary> {
+        self.names
+            .iter()
+            .map(|name| {
+                let totals = &self.totals[name];
+                let [spread, std_dev, max_over_mean, empty_buckets, time] = totals.means();
+                HasherSummary { hasher: Cow::Borrowed(name), portable: hashers::is_portable(name), epochs: totals.epochs, spread, std_dev, max_over_mean, empty_buckets, time_us: time * 1e6 }
+            })
+            .collect()
+    }
+
+    /// [`summaries`](Self::summaries) as an aligned table for reading, not
+    /// parsing.
+    pub fn write_summary(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{:<24} {:>8} {:>8} {:>12} {:>12} {:>14} {:>14} {:>12}", "hasher", "portable", "epochs", "spread", "std_dev", "max_over_mean", "empty_buckets", "time_us")?;
+        for summary in self.summaries() {
+            let portable = if summary.portable { "yes" } else { "NO" };
+            writeln!(
+                out,
+                "{:<24} {portable:>8} {:>8} {:>12.2} {:>12.4} {:>14.4} {:>14.2} {:>12.1}",
+                summary.hasher, summary.epochs, summary.spread, summary.std_dev, summary.max_over_mean, summary.empty_buckets, summary.time_us
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ratios() {
+        let analysis = |spread, std_dev, max_over_mean| BucketAnalysis { spread, std_dev, max_over_mean, ..BucketAnalysis::default() };
+        let mut comparison = BaselineComparison::default();
+        comparison.record("other", &analysis(4, 1.0, 2.0), Duration::from_micros(10));
+        assert_eq!(comparison.ratios(), vec![]);
+        comparison.record("blake3", &analysis(2, 1.0, 1.5), Duration::from_micros(20));
+        comparison.record("blake3", &analysis(4, 1.0, 1.5), Duration::from_micros(20));
+        assert_eq!(comparison.ratios(), vec![
+            BaselineRatios { hasher: "other".into(), spread: 4.0 / 3.0, std_dev: 1.0, max_over_mean: 4.0 / 3.0, empty_buckets: 1.0, time: 0.5 },
+            BaselineRatios { hasher: "blake3".into(), spread: 1.0, std_dev: 1.0, max_over_mean: 1.0, empty_buckets: 1.0, time: 1.0 },
+        ]);
+        let ratios = comparison.ratios();
+        assert_eq!(serde_json::from_str::<Vec<BaselineRatios>>(&serde_json::to_string(&ratios).unwrap()).unwrap(), ratios);
+
+        let mut summary = Vec::new();
+        comparison.write_summary(&mut summary).unwrap();
+        let summary = String::from_utf8(summary).unwrap();
+        let lines = summary.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("hasher"));
+        assert!(lines[1].starts_with("other ") && lines[1].ends_with(" 10.0"));
+        assert!(lines.iter().all(|line| line.len() == lines[0].len()));
+    }
+}
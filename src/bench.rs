@@ -0,0 +1,108 @@
+use {
+    crate::{
+        do_test_parallel,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    rayon::{ThreadPool, ThreadPoolBuilder},
+    std::{collections::HashMap, fmt, num::NonZeroUsize, time::Duration},
+};
+
+pub const HEADER: &str = "hasher,threads,addresses_per_sec,speedup,efficiency";
+
+#[derive(Debug, PartialEq)]
+pub struct ScalingResult {
+    pub hasher: &'static str,
+    pub threads: usize,
+    pub addresses_per_sec: f64,
+    pub speedup: f64,
+    pub efficiency: f64,
+}
+
+impl fmt::Display for ScalingResult {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{}", self.hasher, self.threads, self.addresses_per_sec, self.speedup, self.efficiency)
+    }
+}
+
+/// Times every visited hasher at each thread count, a rayon pool of that
+/// many threads splitting its addresses, accumulating over however many
+/// epochs it visits.
+pub struct ScalingVisitor<'a> {
+    pub buckets: usize,
+    pub reduction: Reduction,
+    pub addresses: &'a [Pubkey2],
+    pub threads: &'a [NonZeroUsize],
+    pools: Vec<ThreadPool>,
+    names: Vec<&'static str>,
+    elapsed: HashMap<(&'static str, usize), (Duration, usize)>,
+}
+
+impl<'a> ScalingVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2], threads: &'a [NonZeroUsize]) -> Self {
+        let pools = threads.iter().map(|threads| ThreadPoolBuilder::new().num_threads(threads.get()).build().unwrap()).collect();
+        Self { buckets, reduction, addresses, threads, pools, names: Vec::new(), elapsed: HashMap::new() }
+    }
+
+    pub fn results(&self) -> Vec<ScalingResult> {
+        let throughput = |hasher, threads| {
+            let (elapsed, assignments): &(Duration, usize) = &self.elapsed[&(hasher, threads)];
+            *assignments as f64 / elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+        };
+        let mut results = Vec::new();
+        for hasher in self.names.iter() {
+            let baseline_threads = self.threads[0].get();
+            let baseline = throughput(*hasher, baseline_threads) / baseline_threads as f64;
+            for threads in self.threads {
+                let addresses_per_sec = throughput(*hasher, threads.get());
+                let speedup = addresses_per_sec / baseline;
+                results.push(ScalingResult {
+                    hasher,
+                    threads: threads.get(),
+                    addresses_per_sec,
+                    speedup,
+                    efficiency: speedup / threads.get() as f64,
+                });
+            }
+        }
+        results
+    }
+}
+
+impl HasherVisitor for ScalingVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        if !self.names.contains(&name) {
+            self.names.push(name);
+        }
+        for (threads, pool) in self.threads.iter().zip(&self.pools) {
+            let mut counts = vec![0; self.buckets];
+            let time = pool.install(|| do_test_parallel(&hasher, &mut counts, self.reduction, self.addresses, None));
+            let elapsed = self.elapsed.entry((name, threads.get())).or_default();
+            elapsed.0 += time;
+            elapsed.1 += self.addresses.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher, solana_sdk::pubkey::Pubkey};
+
+    fn threads(counts: &[usize]) -> Vec<NonZeroUsize> {
+        counts.iter().map(|count| NonZeroUsize::new(*count).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_scaling_visitor() {
+        let addresses = (0..16).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let threads = threads(&[1, 2]);
+        let mut visitor = ScalingVisitor::new(4, Reduction::Multiply, &addresses, &threads);
+        visitor.visit("blake3", &[], Blake3Hasher::new_with_seed(0));
+        let results = visitor.results();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].threads, 1);
+        assert!((results[0].speedup - 1.0).abs() < 1e-9);
+        assert_eq!(results[1].threads, 2);
+    }
+}
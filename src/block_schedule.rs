@@ -0,0 +1,132 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    std::{collections::HashMap, fmt},
+};
+
+pub const HEADER: &str = "hasher,block_offset,mean_accounts,max_accounts,mean_weight,max_weight,epochs_over_limit";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct BlockLoad {
+    total_accounts: u64,
+    max_accounts: usize,
+    total_weight: u64,
+    max_weight: u64,
+    epochs_over_limit: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BlockSummary {
+    pub hasher: &'static str,
+    pub block_offset: usize,
+    pub mean_accounts: f64,
+    pub max_accounts: usize,
+    pub mean_weight: f64,
+    pub max_weight: u64,
+    pub epochs_over_limit: u64,
+}
+
+impl fmt::Display for BlockSummary {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{},{}", self.hasher, self.block_offset, self.mean_accounts, self.max_accounts, self.mean_weight, self.max_weight, self.epochs_over_limit)
+    }
+}
+
+/// Simulates the rewards-credit schedule, where partition `k` is credited in
+/// the `k`th block after the epoch boundary, accumulating each block's work
+/// across however many epochs it visits. A block's weight sums its
+/// addresses' weights, their --weight-by-count appearances, or one per
+/// address when there are none.
+pub struct BlockScheduleVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    weights: Option<&'a [usize]>,
+    account_limit: Option<usize>,
+    epochs: u64,
+    names: Vec<&'static str>,
+    loads: HashMap<&'static str, Vec<BlockLoad>>,
+}
+
+impl<'a> BlockScheduleVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2], weights: Option<&'a [usize]>, account_limit: Option<usize>) -> Self {
+        Self { buckets, reduction, addresses, weights, account_limit, epochs: 0, names: Vec::new(), loads: HashMap::new() }
+    }
+
+    /// Marks the end of an epoch's hashers.
+    pub fn end_epoch(&mut self) {
+        self.epochs += 1;
+    }
+
+    pub fn summaries(&self) -> Vec<BlockSummary> {
+        let epochs = self.epochs.max(1) as f64;
+        self.names
+            .iter()
+            .flat_map(|name| {
+                self.loads[name].iter().enumerate().map(move |(block_offset, load)| BlockSummary {
+                    hasher: name,
+                    block_offset,
+                    mean_accounts: load.total_accounts as f64 / epochs,
+                    max_accounts: load.max_accounts,
+                    mean_weight: load.total_weight as f64 / epochs,
+                    max_weight: load.max_weight,
+                    epochs_over_limit: load.epochs_over_limit,
+                })
+            })
+            .collect()
+    }
+}
+
+impl HasherVisitor for BlockScheduleVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let mut accounts = vec![0; self.buckets];
+        let mut weight = vec![0u64; self.buckets];
+        for (index, address) in self.addresses.iter().enumerate() {
+            let block = address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address);
+            accounts[block] += 1;
+            weight[block] += self.weights.map_or(1, |weights| weights[index] as u64);
+        }
+        let loads = self.loads.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            vec![BlockLoad::default(); self.buckets]
+        });
+        for ((load, accounts), weight) in loads.iter_mut().zip(accounts).zip(weight) {
+            load.total_accounts += accounts as u64;
+            load.max_accounts = load.max_accounts.max(accounts);
+            load.total_weight += weight;
+            load.max_weight = load.max_weight.max(weight);
+            if self.account_limit.is_some_and(|limit| accounts > limit) {
+                load.epochs_over_limit += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn test_block_schedule_totals() {
+        let addresses = (0..50).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let weights = vec![3; addresses.len()];
+        let mut visitor = BlockScheduleVisitor::new(4, Reduction::Multiply, &addresses, Some(&weights), Some(0));
+        for epoch in 0..2 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+            visitor.end_epoch();
+        }
+        let summaries = visitor.summaries();
+        assert_eq!(summaries.len(), 4);
+        assert_eq!(summaries.iter().map(|summary| summary.mean_accounts).sum::<f64>(), 50.0);
+        assert_eq!(summaries.iter().map(|summary| summary.mean_weight).sum::<f64>(), 150.0);
+        for summary in summaries {
+            assert!(summary.max_accounts as f64 >= summary.mean_accounts);
+            assert_eq!(summary.max_weight, 3 * summary.max_accounts as u64);
+            assert_eq!(summary.epochs_over_limit, 2);
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use {
+    crate::{
+        analysis::{analyze_buckets_streaming, BucketAnalysis, ModeTieBreak, DEFAULT_PERCENTILES},
+        do_test,
+        hashers::{self, AnalysisHasher, HasherVisitor},
+        input::{self, CsvColumn, InputFormat},
+        reduction::Reduction,
+        sweep::SeedStrategy,
+        Pubkey2,
+    },
+    std::{
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+};
+
+pub enum InputSource {
+    Addresses(Vec<Pubkey2>),
+    File(PathBuf),
+}
+
+#[derive(Debug)]
+pub struct HasherResult {
+    pub hasher: String,
+    pub analysis: BucketAnalysis,
+    pub time: Duration,
+}
+
+#[derive(Debug)]
+pub struct EpochResult {
+    pub epoch: u64,
+    pub results: Vec<HasherResult>,
+}
+
+/// Stops an [`Analysis`] between epochs once cancelled. Clones share one
+/// flag, so a clone can be cancelled from another thread or a timeout.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The epochs an [`Analysis`] completed, which are all of them unless it
+/// was cancelled.
+#[derive(Debug)]
+pub struct RunResult {
+    pub results: Vec<EpochResult>,
+    pub cancelled: bool,
+}
+
+/// Notified as an [`Analysis`] runs, so embedders can stream each epoch's
+/// results into their own systems instead of waiting for the run to end.
+pub trait Observer {
+    fn on_epoch(&mut self, result: &EpochResult);
+
+    fn on_finish(&mut self) {}
+}
+
+impl<F: FnMut(&EpochResult)> Observer for F {
+    fn on_epoch(&mut self, result: &EpochResult) {
+        self(result)
+    }
+}
+
+/// Configures an [`Analysis`] programmatically, without going through the
+/// command line. Defaults match the binary: blake3 only, seeded by epoch,
+/// 100 buckets under multiply-shift, epochs 0 to 999.
+pub struct AnalysisBuilder {
+    input: InputSource,
+    hashers: Vec<String>,
+    seed: SeedStrategy,
+    buckets: usize,
+    reduction: Reduction,
+    epochs: u64,
+    tie_break: ModeTieBreak,
+    percentiles: Vec<f64>,
+    observers: Vec<Box<dyn Observer>>,
+    cancellation: CancellationToken,
+}
+
+impl AnalysisBuilder {
+    pub fn new(input: InputSource) -> Self {
+        Self {
+            input,
+            hashers: Vec::new(),
+            seed: SeedStrategy::Epoch,
+            buckets: crate::BUCKETS,
+            reduction: Reduction::default(),
+            epochs: crate::EPOCHS,
+            tie_break: ModeTieBreak::default(),
+            percentiles: DEFAULT_PERCENTILES.to_vec(),
+            observers: Vec::new(),
+            cancellation: CancellationToken::default(),
+        }
+    }
+
+    /// Adds one of the [registered](hashers::registered_names) hashers.
+    pub fn hasher(mut self, name: &str) -> Self {
+        self.hashers.push(name.to_string());
+        self
+    }
+
+    pub fn seed_strategy(mut self, seed: SeedStrategy) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn buckets(mut self, buckets: usize) -> Self {
+        self.buckets = buckets;
+        self
+    }
+
+    pub fn reduction(mut self, reduction: Reduction) -> Self {
+        self.reduction = reduction;
+        self
+    }
+
+    pub fn epochs(mut self, epochs: u64) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    pub fn mode_tie_break(mut self, tie_break: ModeTieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// Bucket count percentiles each analysis reports, p10, p90 and p99 by
+    /// default.
+    pub fn percentiles(mut self, percentiles: &[f64]) -> Self {
+        self.percentiles = percentiles.to_vec();
+        self
+    }
+
+    /// Receives every epoch's results during [`Analysis::run`].
+    pub fn sink(self, sink: impl FnMut(&EpochResult) + 'static) -> Self {
+        self.observer(sink)
+    }
+
+    pub fn observer(mut self, observer: impl Observer + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    pub fn build(self) -> Result<Analysis, String> {
+        if self.buckets == 0 {
+            return Err("bucket count must be non-zero".to_string());
+        }
+        self.reduction.validate(self.buckets)?;
+        let mut hashers = self.hashers.iter().map(|hasher| hashers::canonical_name(hasher).to_string()).collect::<Vec<_>>();
+        if hashers.is_empty() {
+            hashers.push("blake3".to_string());
+        }
+        if let Some(percentile) = self.percentiles.iter().find(|percentile| !(**percentile > 0.0 && **percentile <= 100.0)) {
+            return Err(format!("percentile {percentile} outside (0, 100]"));
+        }
+        let registered = hashers::registered_names();
+        if let Some(hasher) = hashers.iter().find(|hasher| !registered.contains(&hasher.as_str())) {
+            return Err(format!("unknown hasher {hasher}; expected one of {}", registered.join(", ")));
+        }
+        let addresses = match self.input {
+            InputSource::Addresses(addresses) => addresses,
+            InputSource::File(path) => input::load_tagged(&path, InputFormat::detect(&path), &CsvColumn::default())?.0,
+        };
+        Ok(Analysis {
+            addresses,
+            hashers,
+            seed: self.seed,
+            buckets: self.buckets,
+            reduction: self.reduction,
+            epochs: self.epochs,
+            tie_break: self.tie_break,
+            percentiles: self.percentiles,
+            observers: self.observers,
+            cancellation: self.cancellation,
+        })
+    }
+}
+
+struct CountVisitor<'a> {
+    buckets: &'a mut [usize],
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    time: Duration,
+}
+
+impl HasherVisitor for CountVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, _name: &'static str, _key: &[u8], hasher: H) {
+        self.buckets.fill(0);
+        self.time = do_test(hasher, self.buckets, self.reduction, self.addresses, None);
+    }
+}
+
+pub struct Analysis {
+    addresses: Vec<Pubkey2>,
+    hashers: Vec<String>,
+    seed: SeedStrategy,
+    buckets: usize,
+    reduction: Reduction,
+    epochs: u64,
+    tie_break: ModeTieBreak,
+    percentiles: Vec<f64>,
+    observers: Vec<Box<dyn Observer>>,
+    cancellation: CancellationToken,
+}
+
+impl Analysis {
+    /// Every configured hasher's analysis of one epoch.
+    pub fn epoch(&self, epoch: u64) -> EpochResult {
+        let mut buckets = vec![0; self.buckets];
+        let results = self.hashers
+            .iter()
+            .map(|hasher| {
+                let mut visitor = CountVisitor { buckets: &mut buckets, reduction: self.reduction, addresses: &self.addresses, time: Duration::ZERO };
+                hashers::visit_registered(hasher, self.seed.seed(epoch), &mut visitor);
+                let time = visitor.time;
+                HasherResult { hasher: hasher.clone(), analysis: analyze_buckets_streaming(&buckets, self.tie_break, &self.percentiles), time }
+            })
+            .collect();
+        EpochResult { epoch, results }
+    }
+
+    /// The configured epochs, analyzed lazily in order, ending early once
+    /// cancelled.
+    pub fn iter(&self) -> impl Iterator<Item = EpochResult> + '_ {
+        (0..self.epochs).take_while(|_| !self.cancellation.is_cancelled()).map(|epoch| self.epoch(epoch))
+    }
+
+    /// Analyzes every epoch, handing each to the configured observers.
+    pub fn run(mut self) -> RunResult {
+        let mut observers = std::mem::take(&mut self.observers);
+        let mut observers = observers.iter_mut().map(|observer| observer.as_mut() as &mut dyn Observer).collect::<Vec<_>>();
+        self.run_with(&mut observers)
+    }
+
+    /// Analyzes every epoch, handing each to `observers` rather than the
+    /// configured ones, which needn't be `'static`.
+    pub fn run_with(&self, observers: &mut [&mut dyn Observer]) -> RunResult {
+        let mut results = Vec::new();
+        for result in self.iter() {
+            for observer in observers.iter_mut() {
+                observer.on_epoch(&result);
+            }
+            results.push(result);
+        }
+        for observer in observers.iter_mut() {
+            observer.on_finish();
+        }
+        let cancelled = results.len() as u64 != self.epochs;
+        RunResult { results, cancelled }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::pubkey::Pubkey,
+        std::{cell::RefCell, rc::Rc},
+    };
+
+    fn addresses() -> Vec<Pubkey2> {
+        (0..100).map(|_| Pubkey2(Pubkey::new_unique())).collect()
+    }
+
+    #[test]
+    fn test_build_validates() {
+        assert!(AnalysisBuilder::new(InputSource::Addresses(Vec::new())).hasher("md5").build().is_err());
+        assert!(AnalysisBuilder::new(InputSource::Addresses(Vec::new())).buckets(100).reduction(Reduction::Mask).build().is_err());
+        assert!(AnalysisBuilder::new(InputSource::Addresses(Vec::new())).buckets(0).build().is_err());
+        assert!(AnalysisBuilder::new(InputSource::Addresses(Vec::new())).percentiles(&[0.0]).build().is_err());
+        assert!(AnalysisBuilder::new(InputSource::File("does-not-exist.json".into())).build().is_err());
+    }
+
+    #[test]
+    fn test_run_matches_iter() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let sink_seen = Rc::clone(&seen);
+        let analysis = AnalysisBuilder::new(InputSource::Addresses(addresses()))
+            .hasher("blake3")
+            .hasher("murmur3-32")
+            .buckets(16)
+            .epochs(3)
+            .sink(move |result| sink_seen.borrow_mut().push((result.epoch, result.results.len(), result.results[0].analysis.mean)))
+            .build()
+            .unwrap();
+        let iterated = analysis.iter().map(|result| (result.epoch, result.results.len(), result.results[0].analysis.mean)).collect::<Vec<_>>();
+        analysis.run();
+        assert_eq!(*seen.borrow(), iterated);
+        assert_eq!(iterated, vec![(0, 2, 6), (1, 2, 6), (2, 2, 6)]);
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        epochs: u64,
+        finished: bool,
+    }
+
+    impl Observer for Counter {
+        fn on_epoch(&mut self, result: &EpochResult) {
+            assert_eq!(result.epoch, self.epochs);
+            self.epochs += 1;
+        }
+
+        fn on_finish(&mut self) {
+            self.finished = true;
+        }
+    }
+
+    #[test]
+    fn test_cancellation_returns_partial_results() {
+        let token = CancellationToken::default();
+        let observer_token = token.clone();
+        let analysis = AnalysisBuilder::new(InputSource::Addresses(addresses()))
+            .epochs(10)
+            .cancellation(token)
+            .sink(move |result| if result.epoch == 2 { observer_token.cancel() })
+            .build()
+            .unwrap();
+        let run = analysis.run();
+        assert!(run.cancelled);
+        assert_eq!(run.results.iter().map(|result| result.epoch).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_run_with_observer() {
+        let analysis = AnalysisBuilder::new(InputSource::Addresses(addresses())).epochs(4).build().unwrap();
+        let mut counter = Counter::default();
+        assert!(!analysis.run_with(&mut [&mut counter]).cancelled);
+        assert_eq!(counter.epochs, 4);
+        assert!(counter.finished);
+    }
+}
@@ -0,0 +1,162 @@
+use {
+    crate::{
+        analysis::{analyze_buckets_streaming, t_p_value, BucketAnalysis, ModeTieBreak},
+        do_test,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    serde::{Deserialize, Serialize},
+    std::{borrow::Cow, fmt},
+};
+
+pub const HEADER: &str = "metric,baseline,candidate,regression_percent,p_value,pass";
+
+type Metric = (&'static str, fn(&BucketAnalysis) -> f64);
+
+/// Metrics a hasher swap must not worsen, all lower-is-better.
+const METRICS: [Metric; 4] = [
+    ("spread", |analysis| analysis.spread as f64),
+    ("std_dev", |analysis| analysis.std_dev),
+    ("max_over_mean", |analysis| analysis.max_over_mean),
+    ("chi_squared", |analysis| analysis.chi_squared),
+];
+
+/// One metric averaged over the epochs run for both hashers, and how
+/// likely a regression this far past the threshold is from noise alone.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct MetricCheck {
+    pub metric: Cow<'static, str>,
+    pub baseline: f64,
+    pub candidate: f64,
+    /// How much worse the candidate is, as a percentage of the baseline;
+    /// negative when it's better
+    pub regression_percent: f64,
+    /// One-sided paired t-test over the epochs of the candidate regressing
+    /// by more than the threshold
+    pub p_value: f64,
+    pub pass: bool,
+}
+
+impl fmt::Display for MetricCheck {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{}", self.metric, self.baseline, self.candidate, self.regression_percent, self.p_value, self.pass)
+    }
+}
+
+/// Records [`METRICS`] for every epoch a hasher is visited for, the n-th
+/// visit being epoch n.
+pub struct PairVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    tie_break: ModeTieBreak,
+    epochs: Vec<[f64; METRICS.len()]>,
+}
+
+impl<'a> PairVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2], tie_break: ModeTieBreak) -> Self {
+        Self { buckets, reduction, addresses, tie_break, epochs: Vec::new() }
+    }
+
+    fn mean(&self, metric: usize) -> f64 {
+        self.epochs.iter().map(|values| values[metric]).sum::<f64>() / self.epochs.len().max(1) as f64
+    }
+}
+
+impl HasherVisitor for PairVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, _name: &'static str, _key: &[u8], hasher: H) {
+        let mut counts = vec![0; self.buckets];
+        do_test(hasher, &mut counts, self.reduction, self.addresses, None);
+        let analysis = analyze_buckets_streaming(&counts, self.tie_break, &[]);
+        self.epochs.push(METRICS.map(|(_, metric)| metric(&analysis)));
+    }
+}
+
+/// One-sided paired t-test p-value of `differences` having a mean above
+/// zero. Identical differences are certain either way.
+fn paired_p_value(differences: &[f64]) -> f64 {
+    let n = differences.len() as f64;
+    let mean = differences.iter().sum::<f64>() / n;
+    let variance = differences.iter().map(|difference| (difference - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    match variance.sqrt() / n.sqrt() {
+        standard_error if standard_error > 0.0 => t_p_value(mean / standard_error, differences.len() - 1),
+        _ if mean > 0.0 => 0.0,
+        _ => 1.0,
+    }
+}
+
+/// Checks each metric's candidate against the baseline epoch by epoch, both
+/// seeded alike, failing any the candidate is worse on by more than
+/// `max_regression_percent` of the baseline's mean with significance
+/// `alpha`, so noise between epochs alone doesn't fail a swap. Fails with
+/// fewer than 2 epochs, which leave the t-test no degrees of freedom.
+pub fn check(baseline: &PairVisitor, candidate: &PairVisitor, max_regression_percent: f64, alpha: f64) -> Result<Vec<MetricCheck>, String> {
+    assert_eq!(baseline.epochs.len(), candidate.epochs.len(), "hashers visited for different epochs");
+    if baseline.epochs.len() < 2 {
+        return Err(format!("checking a pair needs at least 2 epochs, got {}", baseline.epochs.len()));
+    }
+    Ok(METRICS
+        .iter()
+        .enumerate()
+        .map(|(i, (metric, _))| {
+            let (baseline_mean, candidate_mean) = (baseline.mean(i), candidate.mean(i));
+            let regression_percent = if baseline_mean == 0.0 {
+                if candidate_mean == 0.0 { 0.0 } else { f64::INFINITY }
+            } else {
+                (candidate_mean - baseline_mean) / baseline_mean * 100.0
+            };
+            let margin = baseline_mean * max_regression_percent / 100.0;
+            let differences = baseline.epochs.iter().zip(&candidate.epochs).map(|(baseline, candidate)| candidate[i] - baseline[i] - margin).collect::<Vec<_>>();
+            let p_value = paired_p_value(&differences);
+            MetricCheck { metric: Cow::Borrowed(*metric), baseline: baseline_mean, candidate: candidate_mean, regression_percent, p_value, pass: p_value >= alpha }
+        })
+        .collect())
+}
+
+/// Parses a non-negative percentage, with or without a trailing `%`.
+pub fn parse_percent(arg: &str) -> Result<f64, String> {
+    match arg.strip_suffix('%').unwrap_or(arg).parse::<f64>() {
+        Ok(percent) if percent >= 0.0 && percent.is_finite() => Ok(percent),
+        _ => Err(format!("expected a non-negative percentage such as 2%, got {arg:?}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visitor(epochs: &[[f64; 4]]) -> PairVisitor<'static> {
+        PairVisitor { epochs: epochs.to_vec(), ..PairVisitor::new(10, Reduction::Multiply, &[], ModeTieBreak::default()) }
+    }
+
+    #[test]
+    fn test_check() {
+        // std_dev is worse every epoch, spread only on average.
+        let baseline = visitor(&[[10.0, 4.0, 2.0, 0.0], [12.0, 4.0, 2.0, 0.0], [8.0, 4.0, 2.0, 0.0], [10.0, 4.0, 2.0, 0.0]]);
+        let candidate = visitor(&[[14.0, 4.4, 1.0, 0.0], [9.0, 4.3, 1.0, 0.0], [10.0, 4.5, 1.0, 0.0], [8.0, 4.4, 1.0, 0.0]]);
+        let checks = check(&baseline, &candidate, 2.0, 0.05).unwrap();
+        assert_eq!(checks.iter().map(|check| check.pass).collect::<Vec<_>>(), vec![true, false, true, true]);
+        assert_eq!(checks[0].regression_percent, 2.5);
+        assert!(checks[0].p_value > 0.4 && checks[1].p_value < 0.01, "{checks:?}");
+        assert_eq!(checks[2].regression_percent, -50.0);
+        assert_eq!(checks[2].p_value, 1.0);
+        assert_eq!(serde_json::from_str::<MetricCheck>(&serde_json::to_string(&checks[1]).unwrap()).unwrap(), checks[1]);
+        // Within the threshold every epoch passes however consistent.
+        assert!(check(&baseline, &candidate, 15.0, 0.05).unwrap()[1].pass);
+        let checks = check(&visitor(&[[0.0; 4]; 2]), &visitor(&[[0.0, 0.0, 0.0, 1.0]; 2]), 2.0, 0.05).unwrap();
+        assert_eq!(checks[3].regression_percent, f64::INFINITY);
+        assert_eq!(checks[3].p_value, 0.0);
+        assert!(!checks[3].pass && checks[0].pass);
+        assert!(check(&visitor(&[[1.0; 4]]), &visitor(&[[2.0; 4]]), 2.0, 0.05).is_err());
+        assert!(check(&visitor(&[]), &visitor(&[]), 2.0, 0.05).is_err());
+    }
+
+    #[test]
+    fn test_parse_percent() {
+        assert_eq!(parse_percent("2%"), Ok(2.0));
+        assert_eq!(parse_percent("0.5"), Ok(0.5));
+        assert!(parse_percent("-1%").is_err());
+        assert!(parse_percent("two").is_err());
+    }
+}
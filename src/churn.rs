@@ -0,0 +1,268 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{HashMap, HashSet},
+        fmt,
+    },
+};
+
+pub const SNAPSHOT_HEADER: &str = "epoch,old_addresses,new_addresses,persisted,moved,moved_fraction,expected_moved_fraction,appeared,disappeared,churned";
+pub const EPOCH_HEADER: &str = "hasher,epoch_a,epoch_b,changed,changed_fraction,expected_changed_fraction,inflow_min,inflow_mean,inflow_max,inflow_std_dev,outflow_min,outflow_mean,outflow_max,outflow_std_dev";
+
+/// Churn an operator observes between the partitions of epoch `X`, computed
+/// over the epoch `X` account set, and epoch `X + 1`, computed over the epoch
+/// `X + 1` account set. `moved` counts persisting accounts whose bucket
+/// changed with the seed; `appeared` and `disappeared` are population
+/// changes that would churn the partitions even with a fixed seed.
+#[derive(Debug, Default, PartialEq)]
+pub struct SnapshotChurn {
+    pub epoch: u64,
+    pub old_addresses: usize,
+    pub new_addresses: usize,
+    pub persisted: usize,
+    pub moved: usize,
+    pub moved_fraction: f64,
+    pub expected_moved_fraction: f64,
+    pub appeared: usize,
+    pub disappeared: usize,
+    pub churned: usize,
+}
+
+impl fmt::Display for SnapshotChurn {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            formatter,
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.epoch,
+            self.old_addresses,
+            self.new_addresses,
+            self.persisted,
+            self.moved,
+            self.moved_fraction,
+            self.expected_moved_fraction,
+            self.appeared,
+            self.disappeared,
+            self.churned,
+        )
+    }
+}
+
+pub fn analyze_snapshot_churn(
+    buckets: usize,
+    epoch: u64,
+    old: &[Pubkey2],
+    new: &[Pubkey2],
+    assign: impl Fn(u64, &Pubkey2) -> usize,
+) -> SnapshotChurn {
+    let old_buckets = old
+        .iter()
+        .map(|address| (**address, assign(epoch, address)))
+        .collect::<HashMap<Pubkey, usize>>();
+    let mut new_addresses = HashSet::with_capacity(new.len());
+    let (mut persisted, mut moved, mut appeared) = (0, 0, 0);
+    for address in new {
+        if !new_addresses.insert(**address) {
+            continue;
+        }
+        match old_buckets.get(&**address) {
+            Some(old_bucket) => {
+                persisted += 1;
+                if *old_bucket != assign(epoch + 1, address) {
+                    moved += 1;
+                }
+            }
+            None => appeared += 1,
+        }
+    }
+    let disappeared = old_buckets.len() - persisted;
+    let moved_fraction = if persisted == 0 { 0.0 } else { moved as f64 / persisted as f64 };
+
+    SnapshotChurn {
+        epoch,
+        old_addresses: old_buckets.len(),
+        new_addresses: new_addresses.len(),
+        persisted,
+        moved,
+        moved_fraction,
+        expected_moved_fraction: 1.0 - 1.0 / buckets.max(1) as f64,
+        appeared,
+        disappeared,
+        churned: moved + appeared + disappeared,
+    }
+}
+
+/// How many addresses each bucket gained or lost, summarized over buckets.
+#[derive(Debug, Default, PartialEq)]
+pub struct FlowDistribution {
+    pub min: usize,
+    pub mean: f64,
+    pub max: usize,
+    /// Population standard deviation over buckets
+    pub std_dev: f64,
+}
+
+impl FlowDistribution {
+    fn new(flows: &[usize]) -> Self {
+        if flows.is_empty() {
+            return Self::default();
+        }
+        let n = flows.len() as f64;
+        let mean = flows.iter().sum::<usize>() as f64 / n;
+        let variance = flows.iter().map(|flow| (*flow as f64 - mean).powi(2)).sum::<f64>() / n;
+        Self { min: *flows.iter().min().unwrap(), mean, max: *flows.iter().max().unwrap(), std_dev: variance.sqrt() }
+    }
+}
+
+impl fmt::Display for FlowDistribution {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{}", self.min, self.mean, self.max, self.std_dev)
+    }
+}
+
+/// Reshuffling of one fixed address set from one epoch's seed to the next:
+/// the addresses whose bucket changed, and per bucket how many moved in and
+/// out.
+#[derive(Debug, PartialEq)]
+pub struct EpochChurn {
+    pub hasher: &'static str,
+    pub epoch_a: u64,
+    pub epoch_b: u64,
+    pub changed: usize,
+    pub changed_fraction: f64,
+    /// `changed_fraction` under independent uniform assignment each epoch
+    pub expected_changed_fraction: f64,
+    pub inflow: FlowDistribution,
+    pub outflow: FlowDistribution,
+}
+
+impl fmt::Display for EpochChurn {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            formatter,
+            "{},{},{},{},{},{},{},{}",
+            self.hasher,
+            self.epoch_a,
+            self.epoch_b,
+            self.changed,
+            self.changed_fraction,
+            self.expected_changed_fraction,
+            self.inflow,
+            self.outflow,
+        )
+    }
+}
+
+/// Churn between assignments `a` of `epoch_a` and `b` of the epoch after,
+/// both indexed by address.
+pub fn analyze_epoch_churn(hasher: &'static str, epoch_a: u64, buckets: usize, a: &[u32], b: &[u32]) -> EpochChurn {
+    assert_eq!(a.len(), b.len());
+    let (mut inflow, mut outflow) = (vec![0; buckets], vec![0; buckets]);
+    for (from, to) in a.iter().zip(b).filter(|(from, to)| from != to) {
+        outflow[*from as usize] += 1;
+        inflow[*to as usize] += 1;
+    }
+    let changed = outflow.iter().sum::<usize>();
+    EpochChurn {
+        hasher,
+        epoch_a,
+        epoch_b: epoch_a + 1,
+        changed,
+        changed_fraction: if a.is_empty() { 0.0 } else { changed as f64 / a.len() as f64 },
+        expected_changed_fraction: 1.0 - 1.0 / buckets.max(1) as f64,
+        inflow: FlowDistribution::new(&inflow),
+        outflow: FlowDistribution::new(&outflow),
+    }
+}
+
+/// Measures each visited hasher's churn from one visit to the next, treating
+/// the n-th visit of a hasher as epoch n.
+pub struct EpochChurnVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    previous: HashMap<&'static str, (u64, Vec<u32>)>,
+    pub churns: Vec<EpochChurn>,
+}
+
+impl<'a> EpochChurnVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2]) -> Self {
+        Self { buckets, reduction, addresses, previous: HashMap::new(), churns: Vec::new() }
+    }
+}
+
+impl HasherVisitor for EpochChurnVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let current = self.addresses
+            .iter()
+            .map(|address| address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address) as u32)
+            .collect::<Vec<_>>();
+        let epoch = match self.previous.remove(name) {
+            Some((epoch, previous)) => {
+                self.churns.push(analyze_epoch_churn(name, epoch, self.buckets, &previous, &current));
+                epoch + 1
+            }
+            None => 0,
+        };
+        self.previous.insert(name, (epoch, current));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn byte_bucket(epoch: u64, address: &Pubkey2) -> usize {
+        (address.as_ref()[0] as u64 + epoch) as usize % 4
+    }
+
+    fn address(byte: u8) -> Pubkey2 {
+        Pubkey2(Pubkey::new_from_array([byte; 32]))
+    }
+
+    #[test]
+    fn test_analyze_snapshot_churn() {
+        let old = [address(0), address(1), address(2)];
+        let new = [address(1), address(2), address(3), address(3)];
+        let churn = analyze_snapshot_churn(4, 7, &old, &new, byte_bucket);
+        assert_eq!(churn.old_addresses, 3);
+        assert_eq!(churn.new_addresses, 3);
+        assert_eq!(churn.persisted, 2);
+        assert_eq!(churn.moved, 2);
+        assert_eq!(churn.moved_fraction, 1.0);
+        assert_eq!(churn.expected_moved_fraction, 0.75);
+        assert_eq!(churn.appeared, 1);
+        assert_eq!(churn.disappeared, 1);
+        assert_eq!(churn.churned, 4);
+    }
+
+    #[test]
+    fn test_analyze_snapshot_churn_fixed_seed() {
+        let old = [address(0), address(1)];
+        let churn = analyze_snapshot_churn(4, 0, &old, &old, |_epoch, address| byte_bucket(0, address));
+        assert_eq!(churn.moved, 0);
+        assert_eq!(churn.churned, 0);
+    }
+
+    #[test]
+    fn test_analyze_epoch_churn() {
+        let churn = analyze_epoch_churn("blake3", 3, 4, &[0, 0, 1, 2, 3], &[0, 1, 2, 2, 1]);
+        assert_eq!((churn.epoch_a, churn.epoch_b, churn.changed), (3, 4, 3));
+        assert_eq!(churn.changed_fraction, 0.6);
+        assert_eq!(churn.inflow, FlowDistribution { min: 0, mean: 0.75, max: 2, std_dev: 0.6875f64.sqrt() });
+        assert_eq!(churn.outflow, FlowDistribution { min: 0, mean: 0.75, max: 1, std_dev: 0.1875f64.sqrt() });
+        assert_eq!(churn.to_string().split(',').count(), EPOCH_HEADER.split(',').count());
+    }
+
+    #[test]
+    fn test_analyze_snapshot_churn_empty() {
+        let churn = analyze_snapshot_churn(4, 0, &[], &[], byte_bucket);
+        assert_eq!(churn.moved_fraction, 0.0);
+        assert_eq!(churn.churned, 0);
+    }
+}
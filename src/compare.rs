@@ -0,0 +1,80 @@
+use {
+    crate::results::Results,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeMap, HashMap},
+        fmt,
+    },
+};
+
+pub const HEADER: &str = "hasher,metric,epochs,mean_a,mean_b,mean_delta,max_abs_delta";
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct MetricDelta {
+    pub hasher: String,
+    pub metric: String,
+    pub epochs: usize,
+    pub mean_a: f64,
+    pub mean_b: f64,
+    pub mean_delta: f64,
+    pub max_abs_delta: f64,
+}
+
+impl fmt::Display for MetricDelta {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{},{}", self.hasher, self.metric, self.epochs, self.mean_a, self.mean_b, self.mean_delta, self.max_abs_delta)
+    }
+}
+
+/// For every hasher and metric the two results share, how `b` differs from
+/// `a` over the epochs both have. A metric counts as shared if every
+/// aligned epoch of both holds it.
+pub fn compare(a: &Results, b: &Results) -> Vec<MetricDelta> {
+    let rows_b = b.iter().map(|row| ((row.hasher.as_str(), row.epoch), row.metrics())).collect::<HashMap<_, _>>();
+    let mut aligned = BTreeMap::<&str, Vec<(Vec<(String, f64)>, &Vec<(String, f64)>)>>::new();
+    for row in a {
+        if let Some(metrics_b) = rows_b.get(&(row.hasher.as_str(), row.epoch)) {
+            aligned.entry(&row.hasher).or_default().push((row.metrics(), metrics_b));
+        }
+    }
+    let value = |metrics: &[(String, f64)], metric: &str| metrics.iter().find(|(name, _)| name == metric).map(|(_, value)| *value);
+    let mut deltas = Vec::new();
+    for (hasher, rows) in aligned {
+        let epochs = rows.len() as f64;
+        for (metric, _) in &rows[0].0 {
+            let Some(pairs) = rows.iter().map(|(a, b)| Some((value(a, metric)?, value(b, metric)?))).collect::<Option<Vec<_>>>() else {
+                continue;
+            };
+            let mean_a = pairs.iter().map(|(a, _)| a).sum::<f64>() / epochs;
+            let mean_b = pairs.iter().map(|(_, b)| b).sum::<f64>() / epochs;
+            let max_abs_delta = pairs.iter().map(|(a, b)| (b - a).abs()).fold(0.0, f64::max);
+            deltas.push(MetricDelta { hasher: hasher.to_string(), metric: metric.clone(), epochs: rows.len(), mean_a, mean_b, mean_delta: mean_b - mean_a, max_abs_delta });
+        }
+    }
+    deltas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let a = Results::parse_csv("epoch,min,max\n0,1,5\n1,3,7\n2,0,0\n", "blake3").unwrap();
+        let b = Results::parse_csv("epoch,max,std_dev\n0,6,1\n1,10,1\n", "blake3").unwrap();
+        let deltas = compare(&a, &b);
+        assert_eq!(serde_json::from_str::<Vec<MetricDelta>>(&serde_json::to_string(&deltas).unwrap()).unwrap(), deltas);
+        assert_eq!(deltas, vec![MetricDelta {
+            hasher: "blake3".to_string(),
+            metric: "max".to_string(),
+            epochs: 2,
+            mean_a: 6.0,
+            mean_b: 8.0,
+            mean_delta: 2.0,
+            max_abs_delta: 3.0,
+        }]);
+        // Long and wide results of the same run compare equal.
+        let long = Results::parse_csv("epoch,hasher,metric,value\n0,blake3,max,5\n1,blake3,max,7\n", "out").unwrap();
+        assert!(compare(&a, &long).iter().all(|delta| delta.epochs == 2 && delta.max_abs_delta == 0.0));
+    }
+}
@@ -0,0 +1,111 @@
+use {
+    crate::hashers::{AnalysisHasher, HasherVisitor},
+    std::fmt,
+};
+
+pub const HEADER: &str = "hasher,syscall,bytes_per_assignment,cu_per_assignment,cu_all_assignments,cu_per_bucket";
+
+/// Compute unit costs of the hashing syscalls, as charged by the runtime's
+/// default compute budget.
+#[derive(Clone, Copy, Debug)]
+pub struct FeeSchedule {
+    pub hash_base_cost: u64,
+    pub hash_byte_cost: u64,
+    pub mem_op_base_cost: u64,
+}
+
+pub const CURRENT_FEE_SCHEDULE: FeeSchedule = FeeSchedule {
+    hash_base_cost: 85,
+    hash_byte_cost: 1,
+    mem_op_base_cost: 10,
+};
+
+impl FeeSchedule {
+    /// Cost of one hashing syscall over `slices`: the base cost plus, per
+    /// slice, one byte cost per two bytes but never less than a memory op.
+    pub fn hash_cost(&self, slices: &[usize]) -> u64 {
+        self.hash_base_cost + slices.iter()
+            .map(|len| self.mem_op_base_cost.max(self.hash_byte_cost * (*len as u64 / 2)))
+            .sum::<u64>()
+    }
+}
+
+/// The syscall a hasher would use on chain, if there is one.
+pub fn syscall(hasher: &str) -> Option<&'static str> {
+    match hasher {
+        "blake3" => Some("sol_blake3"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CuEstimate {
+    pub hasher: &'static str,
+    pub syscall: &'static str,
+    pub bytes_per_assignment: usize,
+    pub cu_per_assignment: u64,
+    pub cu_all_assignments: u64,
+    pub cu_per_bucket: f64,
+}
+
+impl fmt::Display for CuEstimate {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{}", self.hasher, self.syscall, self.bytes_per_assignment, self.cu_per_assignment, self.cu_all_assignments, self.cu_per_bucket)
+    }
+}
+
+/// Estimates what computing every address's bucket would cost on chain for
+/// each visited hasher that has a syscall. The syscalls are unkeyed, so the
+/// key is modelled as a second slice hashed ahead of the address.
+pub struct CuVisitor {
+    pub schedule: FeeSchedule,
+    pub addresses: usize,
+    pub buckets: usize,
+    pub estimates: Vec<CuEstimate>,
+}
+
+impl HasherVisitor for CuVisitor {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, key: &[u8], _hasher: H) {
+        let Some(syscall) = syscall(name) else {
+            return;
+        };
+        let slices = [key.len(), solana_sdk::pubkey::PUBKEY_BYTES];
+        let cu_per_assignment = self.schedule.hash_cost(&slices);
+        let cu_all_assignments = cu_per_assignment * self.addresses as u64;
+        self.estimates.push(CuEstimate {
+            hasher: name,
+            syscall,
+            bytes_per_assignment: slices.iter().sum(),
+            cu_per_assignment,
+            cu_all_assignments,
+            cu_per_bucket: cu_all_assignments as f64 / self.buckets as f64,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher};
+
+    #[test]
+    fn test_hash_cost() {
+        assert_eq!(CURRENT_FEE_SCHEDULE.hash_cost(&[]), 85);
+        assert_eq!(CURRENT_FEE_SCHEDULE.hash_cost(&[4]), 95);
+        assert_eq!(CURRENT_FEE_SCHEDULE.hash_cost(&[32, 32]), 117);
+    }
+
+    #[test]
+    fn test_cu_visitor_skips_hashers_without_syscall() {
+        let mut visitor = CuVisitor { schedule: CURRENT_FEE_SCHEDULE, addresses: 1000, buckets: 100, estimates: Vec::new() };
+        visitor.visit("blake3", &Blake3Hasher::key(0), Blake3Hasher::new_with_seed(0));
+        visitor.visit("siphash13-epoch-0", &[0; 16], siphasher::sip::SipHasher13::new_with_keys(0, 0));
+        assert_eq!(visitor.estimates, vec![CuEstimate {
+            hasher: "blake3",
+            syscall: "sol_blake3",
+            bytes_per_assignment: 64,
+            cu_per_assignment: 117,
+            cu_all_assignments: 117_000,
+            cu_per_bucket: 1170.0,
+        }]);
+    }
+}
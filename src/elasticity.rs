@@ -0,0 +1,173 @@
+use {
+    crate::{
+        analysis::{analyze_buckets_streaming, ModeTieBreak},
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    std::fmt,
+};
+
+pub const HEADER: &str = "buckets,epochs,mean_spread,mean_std_dev,mean_spread_over_mean,mean_max_over_mean,mean_reassigned_fraction,minimal_reassigned_fraction";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ElasticityPoint {
+    pub buckets: usize,
+    pub epochs: u64,
+    pub mean_spread: f64,
+    pub mean_std_dev: f64,
+    pub mean_spread_over_mean: f64,
+    pub mean_max_over_mean: f64,
+    /// Fraction of addresses whose bucket differs from the previous bucket
+    /// count's, 0 at the first count
+    pub mean_reassigned_fraction: f64,
+    /// The fewest any assignment could move between the two counts, which
+    /// consistent reductions approach
+    pub minimal_reassigned_fraction: f64,
+}
+
+impl fmt::Display for ElasticityPoint {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            formatter,
+            "{},{},{},{},{},{},{},{}",
+            self.buckets,
+            self.epochs,
+            self.mean_spread,
+            self.mean_std_dev,
+            self.mean_spread_over_mean,
+            self.mean_max_over_mean,
+            self.mean_reassigned_fraction,
+            self.minimal_reassigned_fraction
+        )
+    }
+}
+
+/// Bucket counts from `from` to `to` inclusive, `step` apart, that
+/// `reduction` supports.
+pub fn bucket_counts(from: usize, to: usize, step: usize, reduction: Reduction) -> Vec<usize> {
+    (from..=to).step_by(step).filter(|buckets| reduction.validate(*buckets).is_ok()).collect()
+}
+
+/// Hashes every address once per visit and reduces the same hashes to every
+/// bucket count, so the curve costs one hashing pass per epoch however many
+/// counts it spans.
+pub struct ElasticityVisitor<'a> {
+    addresses: &'a [Pubkey2],
+    reduction: Reduction,
+    tie_break: ModeTieBreak,
+    points: Vec<ElasticityPoint>,
+}
+
+impl<'a> ElasticityVisitor<'a> {
+    pub fn new(addresses: &'a [Pubkey2], bucket_counts: &[usize], reduction: Reduction, tie_break: ModeTieBreak) -> Self {
+        let points = bucket_counts
+            .iter()
+            .scan(None, |previous, buckets| {
+                let minimal_reassigned_fraction = previous.replace(*buckets).map_or(0.0, |previous: usize| 1.0 - previous.min(*buckets) as f64 / previous.max(*buckets) as f64);
+                Some(ElasticityPoint { buckets: *buckets, minimal_reassigned_fraction, ..ElasticityPoint::default() })
+            })
+            .collect();
+        Self { addresses, reduction, tie_break, points }
+    }
+
+    /// The curve, averaged over the visited epochs.
+    pub fn points(self) -> Vec<ElasticityPoint> {
+        self.points
+            .into_iter()
+            .map(|point| {
+                let epochs = point.epochs.max(1) as f64;
+                ElasticityPoint {
+                    mean_spread: point.mean_spread / epochs,
+                    mean_std_dev: point.mean_std_dev / epochs,
+                    mean_spread_over_mean: point.mean_spread_over_mean / epochs,
+                    mean_max_over_mean: point.mean_max_over_mean / epochs,
+                    mean_reassigned_fraction: point.mean_reassigned_fraction / epochs,
+                    ..point
+                }
+            })
+            .collect()
+    }
+}
+
+impl HasherVisitor for ElasticityVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, _name: &'static str, _key: &[u8], hasher: H) {
+        let hashes = self.addresses
+            .iter()
+            .map(|address| {
+                let mut hasher = hasher.clone();
+                hasher.write(address.as_ref());
+                hasher.finish()
+            })
+            .collect::<Vec<_>>();
+        let mut previous = None::<Vec<usize>>;
+        for point in self.points.iter_mut() {
+            let assignments = hashes.iter().map(|hash| self.reduction.reduce(point.buckets, *hash)).collect::<Vec<_>>();
+            let mut buckets = vec![0; point.buckets];
+            for bucket in assignments.iter() {
+                buckets[*bucket] += 1;
+            }
+            if let Some(previous) = previous.filter(|_| !hashes.is_empty()) {
+                let reassigned = previous.iter().zip(assignments.iter()).filter(|(previous, current)| previous != current).count();
+                point.mean_reassigned_fraction += reassigned as f64 / hashes.len() as f64;
+            }
+            previous = Some(assignments);
+            let analysis = analyze_buckets_streaming(&buckets, self.tie_break, &[]);
+            point.epochs += 1;
+            point.mean_spread += analysis.spread as f64;
+            point.mean_std_dev += analysis.std_dev;
+            point.mean_spread_over_mean += analysis.spread_over_mean;
+            point.mean_max_over_mean += analysis.max_over_mean;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{address_to_bucket_with_reduction, hashers::Blake3Hasher},
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_bucket_counts() {
+        assert_eq!(bucket_counts(100, 400, 100, Reduction::Multiply), vec![100, 200, 300, 400]);
+        assert_eq!(bucket_counts(1, 16, 1, Reduction::Mask), vec![1, 2, 4, 8, 16]);
+    }
+
+    #[test]
+    fn test_elasticity_matches_per_count_hashing() {
+        let addresses = (0..1000).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = ElasticityVisitor::new(&addresses, &[10, 37], Reduction::Multiply, ModeTieBreak::Lowest);
+        for epoch in 0..3 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+        }
+        let points = visitor.points();
+        assert_eq!(points.iter().map(|point| (point.buckets, point.epochs)).collect::<Vec<_>>(), vec![(10, 3), (37, 3)]);
+        let mut spread = 0;
+        for epoch in 0..3 {
+            let mut buckets = vec![0; 37];
+            for address in addresses.iter() {
+                buckets[address_to_bucket_with_reduction(37, Reduction::Multiply, Blake3Hasher::new_with_seed(epoch), address)] += 1;
+            }
+            spread += analyze_buckets_streaming(&buckets, ModeTieBreak::Lowest, &[]).spread;
+        }
+        assert_eq!(points[1].mean_spread, spread as f64 / 3.0);
+        assert_eq!(points[0].mean_reassigned_fraction, 0.0);
+        assert_eq!(points[1].minimal_reassigned_fraction, 1.0 - 10.0 / 37.0);
+    }
+
+    #[test]
+    fn test_consistent_reassignment() {
+        let addresses = (0..2000).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let reassigned = |reduction| {
+            let mut visitor = ElasticityVisitor::new(&addresses, &[20, 21], reduction, ModeTieBreak::Lowest);
+            visitor.visit("blake3", &Blake3Hasher::key(0), Blake3Hasher::new_with_seed(0));
+            visitor.points()[1].mean_reassigned_fraction
+        };
+        assert!(reassigned(Reduction::Jump) < 0.1);
+        assert!(reassigned(Reduction::Ring) < 0.15);
+        assert!(reassigned(Reduction::Multiply) > 0.3);
+    }
+}
@@ -0,0 +1,127 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    std::{
+        collections::HashMap,
+        fmt,
+        io::{self, Write},
+    },
+};
+
+pub const HEADER: &str = "hasher,epoch_a,epoch_b,agreement,expected_agreement";
+
+#[derive(Debug, PartialEq)]
+pub struct EpochPair {
+    pub hasher: &'static str,
+    pub epoch_a: u64,
+    pub epoch_b: u64,
+    pub agreement: f64,
+    pub expected_agreement: f64,
+}
+
+impl fmt::Display for EpochPair {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{}", self.hasher, self.epoch_a, self.epoch_b, self.agreement, self.expected_agreement)
+    }
+}
+
+/// Fraction of addresses assigned the same bucket in both epochs.
+pub fn agreement(a: &[u32], b: &[u32]) -> f64 {
+    assert_eq!(a.len(), b.len());
+    if a.is_empty() {
+        return 0.0;
+    }
+    a.iter().zip(b).filter(|(a, b)| a == b).count() as f64 / a.len() as f64
+}
+
+/// Agreement between every pair of epochs, as a symmetric matrix.
+pub fn matrix(assignments: &[Vec<u32>]) -> Vec<Vec<f64>> {
+    let epochs = assignments.len();
+    let mut matrix = vec![vec![1.0; epochs]; epochs];
+    for a in 0..epochs {
+        for b in a + 1..epochs {
+            let agreement = agreement(&assignments[a], &assignments[b]);
+            matrix[a][b] = agreement;
+            matrix[b][a] = agreement;
+        }
+    }
+    matrix
+}
+
+/// Renders `matrix` as a binary PGM heatmap, one pixel per epoch pair:
+/// black at or below the agreement independent seeds would give, white at
+/// identical assignments.
+pub fn write_heatmap(matrix: &[Vec<f64>], expected_agreement: f64, out: &mut dyn Write) -> io::Result<()> {
+    let epochs = matrix.len();
+    write!(out, "P5\n{epochs} {epochs}\n255\n")?;
+    let range = (1.0 - expected_agreement).max(f64::EPSILON);
+    let pixels = matrix
+        .iter()
+        .flatten()
+        .map(|agreement| (((agreement - expected_agreement) / range).clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect::<Vec<_>>();
+    out.write_all(&pixels)
+}
+
+/// Collects every visited hasher's assignment of each address, one epoch per
+/// visit.
+pub struct EpochPairVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    names: Vec<&'static str>,
+    assignments: HashMap<&'static str, Vec<Vec<u32>>>,
+}
+
+impl<'a> EpochPairVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2]) -> Self {
+        Self { buckets, reduction, addresses, names: Vec::new(), assignments: HashMap::new() }
+    }
+
+    /// Each hasher's name and agreement matrix, in visiting order.
+    pub fn matrices(&self) -> Vec<(&'static str, Vec<Vec<f64>>)> {
+        self.names.iter().map(|name| (*name, matrix(&self.assignments[name]))).collect()
+    }
+}
+
+impl HasherVisitor for EpochPairVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let assignments = self.addresses
+            .iter()
+            .map(|address| address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address) as u32)
+            .collect();
+        self.assignments
+            .entry(name)
+            .or_insert_with(|| {
+                self.names.push(name);
+                Vec::new()
+            })
+            .push(assignments);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix() {
+        let matrix = matrix(&[vec![0, 1, 2, 3], vec![0, 1, 0, 0], vec![1, 0, 3, 2]]);
+        assert_eq!(matrix, vec![
+            vec![1.0, 0.5, 0.0],
+            vec![0.5, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+        ]);
+    }
+
+    #[test]
+    fn test_write_heatmap() {
+        let mut out = Vec::new();
+        write_heatmap(&[vec![1.0, 0.25], vec![0.75, 1.0]], 0.5, &mut out).unwrap();
+        assert_eq!(out, [b"P5\n2 2\n255\n".as_slice(), &[255, 0, 128, 255]].concat());
+    }
+}
@@ -0,0 +1,109 @@
+use {
+    crate::{
+        hashers::{AnalysisHasher, Blake3Hasher},
+        reduction::Reduction,
+    },
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::hash::Hasher,
+};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Everything needed to recompute one [`Bucketer`](crate::Bucketer) assignment by hand, so a
+/// wallet or explorer can show why an account is in its bucket and check
+/// the claim without trusting whoever made it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Explanation {
+    /// Base58
+    pub address: String,
+    pub seed: u64,
+    /// Hex blake3 key: the seed's little-endian bytes repeated four times
+    pub key: String,
+    /// Hex keyed blake3 digest of the address's 32 bytes
+    pub digest: String,
+    /// First eight digest bytes read little-endian
+    pub hash: u64,
+    pub buckets: usize,
+    pub reduction: Reduction,
+    /// The reduction applied to `hash`, e.g. `floor(100 * 1234 / 2^64) = 0`
+    pub arithmetic: String,
+    pub bucket: usize,
+}
+
+impl Explanation {
+    pub(crate) fn new(address: &Pubkey, seed: u64, buckets: usize, reduction: Reduction) -> Self {
+        let mut hasher = Blake3Hasher::new_with_seed(seed);
+        hasher.write(address.as_ref());
+        let digest = hasher.digest();
+        let hash = hasher.finish();
+        Self {
+            address: address.to_string(),
+            seed,
+            key: hex(&Blake3Hasher::key(seed)),
+            digest: hex(&digest),
+            hash,
+            buckets,
+            reduction,
+            arithmetic: reduction.arithmetic(buckets, hash),
+            bucket: reduction.reduce(buckets, hash),
+        }
+    }
+
+    /// Recomputes every step from the address, seed, bucket count and
+    /// reduction, naming the first field that doesn't follow.
+    pub fn verify(&self) -> Result<(), String> {
+        let address = self.address.parse::<Pubkey>().map_err(|err| format!("address: {err}"))?;
+        if self.buckets == 0 {
+            return Err("buckets: must be non-zero".to_string());
+        }
+        self.reduction.validate(self.buckets)?;
+        let expected = Self::new(&address, self.seed, self.buckets, self.reduction);
+        let mismatch = [
+            ("key", self.key != expected.key),
+            ("digest", self.digest != expected.digest),
+            ("hash", self.hash != expected.hash),
+            ("arithmetic", self.arithmetic != expected.arithmetic),
+            ("bucket", self.bucket != expected.bucket),
+        ];
+        match mismatch.iter().find(|(_, differs)| *differs) {
+            Some((field, _)) => Err(format!("{field} doesn't follow from the address and seed")),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::Bucketer};
+
+    #[test]
+    fn test_explain_matches_bucket() {
+        let address = Pubkey::new_unique();
+        let bucketer = Bucketer::new(100, 7);
+        let explanation = bucketer.explain(&address);
+        assert_eq!(explanation.bucket, bucketer.bucket(&address));
+        assert_eq!(explanation.key, "0700000000000000".repeat(4));
+        assert_eq!(explanation.hash, u64::from_str_radix(&explanation.digest[..16], 16).unwrap().swap_bytes());
+        assert_eq!(explanation.arithmetic, format!("floor(100 * {} / 2^64) = {}", explanation.hash, explanation.bucket));
+        assert_eq!(explanation.verify(), Ok(()));
+        let json = serde_json::to_string(&explanation).unwrap();
+        assert_eq!(serde_json::from_str::<Explanation>(&json).unwrap(), explanation);
+
+        let masked = Bucketer::new(16, 7).with_reduction(Reduction::Mask).unwrap().explain(&address);
+        assert_eq!(masked.arithmetic, format!("{} & 15 = {}", masked.hash, masked.bucket));
+        assert_eq!(masked.digest, explanation.digest);
+    }
+
+    #[test]
+    fn test_verify_rejects_tampering() {
+        let explanation = Bucketer::new(100, 7).explain(&Pubkey::new_unique());
+        let tampered = Explanation { bucket: (explanation.bucket + 1) % 100, ..explanation.clone() };
+        assert_eq!(tampered.verify(), Err("bucket doesn't follow from the address and seed".to_string()));
+        let tampered = Explanation { seed: 8, ..explanation.clone() };
+        assert_eq!(tampered.verify(), Err("key doesn't follow from the address and seed".to_string()));
+        assert!(Explanation { address: "nope".to_string(), ..explanation }.verify().is_err());
+    }
+}
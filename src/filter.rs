@@ -0,0 +1,74 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::io::{self, BufRead, Write},
+};
+
+/// Reads one base58 address per line and writes `pubkey,bucket` for each as
+/// it arrives, under the one hasher it visits. Blank lines are skipped; the
+/// first line that isn't an address stops the filter with an error.
+pub struct FilterVisitor<R, W> {
+    pub buckets: usize,
+    pub reduction: Reduction,
+    pub input: R,
+    pub output: W,
+    pub result: io::Result<usize>,
+}
+
+impl<R: BufRead, W: Write> FilterVisitor<R, W> {
+    fn filter<H: AnalysisHasher>(&mut self, hasher: H) -> io::Result<usize> {
+        let mut assigned = 0;
+        let mut line = String::new();
+        for number in 1.. {
+            line.clear();
+            if self.input.read_line(&mut line)? == 0 {
+                break;
+            }
+            let address = line.trim();
+            if address.is_empty() {
+                continue;
+            }
+            let pubkey = address
+                .parse::<Pubkey>()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("line {number}: {err}: {address:?}")))?;
+            let bucket = address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), &Pubkey2(pubkey));
+            writeln!(self.output, "{address},{bucket}")?;
+            assigned += 1;
+        }
+        self.output.flush()?;
+        Ok(assigned)
+    }
+}
+
+impl<R: BufRead, W: Write> HasherVisitor for FilterVisitor<R, W> {
+    fn visit<H: AnalysisHasher>(&mut self, _name: &'static str, _key: &[u8], hasher: H) {
+        self.result = self.filter(hasher);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher};
+
+    #[test]
+    fn test_filter() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let input = format!("{a}\n\n  {b}\n");
+        let mut visitor = FilterVisitor { buckets: 10, reduction: Reduction::Multiply, input: input.as_bytes(), output: Vec::new(), result: Ok(0) };
+        visitor.visit("blake3", &Blake3Hasher::key(4), Blake3Hasher::new_with_seed(4));
+        assert_eq!(visitor.result.unwrap(), 2);
+        let bucket = |address| address_to_bucket_with_reduction(10, Reduction::Multiply, Blake3Hasher::new_with_seed(4), &Pubkey2(address));
+        assert_eq!(String::from_utf8(visitor.output).unwrap(), format!("{a},{}\n{b},{}\n", bucket(a), bucket(b)));
+
+        let input = format!("{a}\nnope\n{b}\n");
+        let mut visitor = FilterVisitor { buckets: 10, reduction: Reduction::Multiply, input: input.as_bytes(), output: Vec::new(), result: Ok(0) };
+        visitor.visit("blake3", &Blake3Hasher::key(4), Blake3Hasher::new_with_seed(4));
+        assert!(visitor.result.unwrap_err().to_string().starts_with("line 2: "));
+        assert_eq!(String::from_utf8(visitor.output).unwrap().lines().count(), 1);
+    }
+}
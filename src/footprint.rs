@@ -0,0 +1,85 @@
+use {
+    crate::{
+        alloc_counter,
+        hashers::{AnalysisHasher, HasherVisitor},
+        Pubkey2,
+    },
+    std::fmt,
+};
+
+pub const HEADER: &str = "hasher,state_bytes,allocations_per_call,allocated_bytes_per_call";
+
+#[derive(Debug, PartialEq)]
+pub struct Footprint {
+    pub hasher: &'static str,
+    pub state_bytes: usize,
+    pub allocations_per_call: f64,
+    pub allocated_bytes_per_call: f64,
+}
+
+impl fmt::Display for Footprint {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{}", self.hasher, self.state_bytes, self.allocations_per_call, self.allocated_bytes_per_call)
+    }
+}
+
+/// Measures each visited hasher's inline state size and the allocations made
+/// while hashing `addresses`. Allocation counts are process wide, so this
+/// must run while no other thread is working.
+pub struct FootprintVisitor<'a> {
+    pub addresses: &'a [Pubkey2],
+    pub footprints: Vec<Footprint>,
+}
+
+impl HasherVisitor for FootprintVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let mut digests = 0u64;
+        let (allocations_before, bytes_before) = alloc_counter::allocations();
+        for address in self.addresses {
+            let mut hasher = hasher.clone();
+            hasher.write(address.as_ref());
+            digests ^= hasher.finish();
+        }
+        let (allocations_after, bytes_after) = alloc_counter::allocations();
+        std::hint::black_box(digests);
+        let calls = self.addresses.len().max(1) as f64;
+        self.footprints.push(Footprint {
+            hasher: name,
+            state_bytes: std::mem::size_of::<H>(),
+            allocations_per_call: (allocations_after - allocations_before) as f64 / calls,
+            allocated_bytes_per_call: (bytes_after - bytes_before) as f64 / calls,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::pubkey::Pubkey, std::hash::Hasher};
+
+    // Leaks a fresh allocation on every write.
+    #[derive(Clone, Default)]
+    struct AllocatingHasher(u64);
+
+    impl Hasher for AllocatingHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0 = Box::leak(bytes.to_vec().into_boxed_slice()).len() as u64;
+        }
+    }
+
+    impl AnalysisHasher for AllocatingHasher {}
+
+    #[test]
+    fn test_footprint_visitor() {
+        let addresses = (0..8).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = FootprintVisitor { addresses: &addresses, footprints: Vec::new() };
+        visitor.visit("allocating", &[], AllocatingHasher::default());
+        let footprint = &visitor.footprints[0];
+        assert_eq!(footprint.state_bytes, 8);
+        assert!(footprint.allocations_per_call >= 1.0);
+        assert!(footprint.allocated_bytes_per_call >= 32.0);
+    }
+}
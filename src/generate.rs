@@ -0,0 +1,104 @@
+use {
+    crate::{grinding::random_key, Pubkey2},
+    clap::ValueEnum,
+    rand::RngCore,
+    solana_sdk::pubkey::Pubkey,
+};
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Synthetic address sets, from the uniform case to inputs an adversary or a
+/// careless program could produce.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Pattern {
+    /// Uniformly random keys
+    Random,
+    /// Keys whose base58 form starts with --prefix, ground from random keys as vanity key tools do
+    Vanity,
+    /// Program addresses of --program derived from the seeds 0, 1, 2, ... as little-endian u64s
+    Pda,
+    /// Runs of keys differing from a random key in exactly one byte
+    SingleByte,
+}
+
+/// Parses a vanity prefix, which must be base58.
+pub fn parse_prefix(arg: &str) -> Result<String, String> {
+    match arg.chars().find(|c| !BASE58_ALPHABET.contains(*c)) {
+        _ if arg.is_empty() => Err("expected a base58 prefix".to_string()),
+        Some(c) => Err(format!("{c:?} isn't a base58 character")),
+        None => Ok(arg.to_string()),
+    }
+}
+
+pub fn random(count: usize, rng: &mut impl RngCore) -> Vec<Pubkey2> {
+    (0..count).map(|_| random_key(rng)).collect()
+}
+
+/// Grinds `count` keys starting with `prefix`, each taking about
+/// `58^prefix.len()` draws.
+pub fn vanity(count: usize, prefix: &str, rng: &mut impl RngCore) -> Vec<Pubkey2> {
+    (0..count)
+        .map(|_| loop {
+            let key = random_key(rng);
+            if key.to_string().starts_with(prefix) {
+                break key;
+            }
+        })
+        .collect()
+}
+
+pub fn pdas(count: usize, program: &Pubkey) -> Vec<Pubkey2> {
+    (0..count as u64).map(|seed| Pubkey2(Pubkey::find_program_address(&[&seed.to_le_bytes()], program).0)).collect()
+}
+
+/// A random key followed by every key one byte away from it, in byte order,
+/// then another random key and so on.
+pub fn single_byte(count: usize, rng: &mut impl RngCore) -> Vec<Pubkey2> {
+    const RUN: usize = 1 + 32 * 255;
+    let mut keys = Vec::with_capacity(count);
+    let mut base = [0; 32];
+    for index in 0..count {
+        let mut key = base;
+        match index % RUN {
+            0 => {
+                rng.fill_bytes(&mut base);
+                key = base;
+            }
+            offset => {
+                let (byte, delta) = ((offset - 1) / 255, (offset - 1) % 255 + 1);
+                key[byte] = key[byte].wrapping_add(delta as u8);
+            }
+        }
+        keys.push(Pubkey2(Pubkey::new_from_array(key)));
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::rng, std::collections::HashSet};
+
+    #[test]
+    fn test_patterns() {
+        let vanity = vanity(20, "Ab", &mut rng::stream(0, "test", 0));
+        assert!(vanity.iter().all(|key| key.to_string().starts_with("Ab")));
+        assert_eq!(parse_prefix("Ab"), Ok("Ab".to_string()));
+        assert!(parse_prefix("A0").is_err() && parse_prefix("").is_err());
+
+        let program = Pubkey::new_unique();
+        let pdas = pdas(3, &program);
+        assert_eq!(*pdas[2], Pubkey::find_program_address(&[&2u64.to_le_bytes()], &program).0);
+        assert!(pdas.iter().all(|pda| !pda.is_on_curve()));
+
+        let keys = single_byte(2 * (1 + 32 * 255), &mut rng::stream(0, "test", 0));
+        assert_eq!(keys.iter().map(|key| **key).collect::<HashSet<_>>().len(), keys.len());
+        for run in keys.chunks(1 + 32 * 255) {
+            for key in &run[1..] {
+                assert_eq!(run[0].as_ref().iter().zip(key.as_ref()).filter(|(a, b)| a != b).count(), 1);
+            }
+        }
+        let random = |seed| random(4, &mut rng::stream(seed, "test", 0)).iter().map(|key| **key).collect::<Vec<_>>();
+        assert_eq!(random(0), random(0));
+        assert_ne!(random(0), random(1));
+    }
+}
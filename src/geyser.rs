@@ -0,0 +1,89 @@
+use {
+    crate::live_set::LiveAccountSet,
+    futures::StreamExt,
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::HashMap, time::Duration},
+    yellowstone_grpc_client::GeyserGrpcClient,
+    yellowstone_grpc_proto::{
+        prelude::{
+            subscribe_request_filter_accounts_filter::Filter, subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+            SubscribeRequestAccountsDataSlice, SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+        },
+        tonic::transport::ClientTlsConfig,
+    },
+};
+
+pub struct GeyserConfig {
+    /// Yellowstone gRPC endpoint, e.g. `https://host:10000`
+    pub endpoint: String,
+    pub x_token: Option<String>,
+    /// Program owning the accounts tracked
+    pub program: Pubkey,
+    pub data_size: Option<u64>,
+}
+
+fn subscribe_request(config: &GeyserConfig) -> SubscribeRequest {
+    let filters = config
+        .data_size
+        .map(|size| SubscribeRequestFilterAccountsFilter { filter: Some(Filter::Datasize(size)) })
+        .into_iter()
+        .collect();
+    SubscribeRequest {
+        accounts: HashMap::from([(
+            "hash-to-bucket".to_string(),
+            SubscribeRequestFilterAccounts { account: Vec::new(), owner: vec![config.program.to_string()], filters },
+        )]),
+        commitment: Some(CommitmentLevel::Confirmed as i32),
+        // Only keys and lamports are needed, so skip the account data.
+        accounts_data_slice: vec![SubscribeRequestAccountsDataSlice { offset: 0, length: 0 }],
+        ..SubscribeRequest::default()
+    }
+}
+
+/// Applies `config.program`'s account updates to `live` as they stream in,
+/// calling `analyze` every `interval` until the stream fails or ends.
+pub fn stream(config: &GeyserConfig, live: &mut LiveAccountSet, interval: Duration, mut analyze: impl FnMut(&LiveAccountSet)) -> Result<(), String> {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|err| err.to_string())?;
+    runtime.block_on(async {
+        let mut builder = GeyserGrpcClient::build_from_shared(config.endpoint.clone())
+            .and_then(|builder| builder.x_token(config.x_token.clone()))
+            .map_err(|err| err.to_string())?;
+        if config.endpoint.starts_with("https") {
+            builder = builder.tls_config(ClientTlsConfig::new()).map_err(|err| err.to_string())?;
+        }
+        let mut client = builder.connect().await.map_err(|err| err.to_string())?;
+        let mut updates = client.subscribe_once(subscribe_request(config)).await.map_err(|err| err.to_string())?;
+        let mut ticks = tokio::time::interval(interval);
+        ticks.tick().await;
+        loop {
+            tokio::select! {
+                update = updates.next() => match update {
+                    Some(Ok(update)) => if let Some(UpdateOneof::Account(update)) = update.update_oneof {
+                        let Some(account) = update.account else { continue };
+                        let address = Pubkey::try_from(account.pubkey.as_slice()).map_err(|_| "malformed account pubkey".to_string())?;
+                        live.update(update.slot, address, account.lamports);
+                    },
+                    Some(Err(status)) => return Err(status.to_string()),
+                    None => return Err("account update stream ended".to_string()),
+                },
+                _ = ticks.tick() => analyze(live),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribe_request() {
+        let program = Pubkey::new_unique();
+        let config = GeyserConfig { endpoint: String::new(), x_token: None, program, data_size: Some(3762) };
+        let request = subscribe_request(&config);
+        let accounts = &request.accounts["hash-to-bucket"];
+        assert_eq!(accounts.owner, vec![program.to_string()]);
+        assert_eq!(accounts.filters[0].filter, Some(Filter::Datasize(3762)));
+        assert_eq!(request.accounts_data_slice[0].length, 0);
+    }
+}
@@ -0,0 +1,197 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        rng, Pubkey2,
+    },
+    rand::RngCore,
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::HashMap, fmt},
+};
+
+pub const HEADER: &str = "hasher,epochs,trials,target_share,expected_attempts,measured_attempts,exhausted_trials";
+
+/// Keys drawn per bucket before [`grind`] gives up on a target, far beyond
+/// the `buckets` a target holding its fair share of the key space takes.
+pub const MAX_ATTEMPTS_PER_BUCKET: u64 = 1000;
+
+/// Expected hash attempts for an attacker grinding keypairs to land a new
+/// account in one chosen bucket, a geometric trial whose success
+/// probability is the bucket's share of the address set. This is the cost of
+/// the cheapest target, the most loaded bucket, so lower is weaker. With few
+/// addresses per bucket the most loaded bucket's share overstates its share
+/// of the key space, so this is a lower bound on what grinding measures.
+pub fn expected_attempts(buckets: &[usize]) -> f64 {
+    let total = buckets.iter().sum::<usize>();
+    buckets.iter().max().filter(|max| **max > 0).map_or(0.0, |max| total as f64 / *max as f64)
+}
+
+pub fn random_key(rng: &mut impl RngCore) -> Pubkey2 {
+    let mut key = [0; 32];
+    rng.fill_bytes(&mut key);
+    Pubkey2(Pubkey::new_from_array(key))
+}
+
+/// Draws random keys until one lands in `target`, returning it and the
+/// number of keys drawn, or no key if none did within
+/// [`MAX_ATTEMPTS_PER_BUCKET`] per bucket.
+pub fn grind<H: AnalysisHasher>(buckets: usize, reduction: Reduction, hasher: &H, target: usize, rng: &mut impl RngCore) -> (Option<Pubkey2>, u64) {
+    let max_attempts = buckets as u64 * MAX_ATTEMPTS_PER_BUCKET;
+    for attempts in 1..=max_attempts {
+        let key = random_key(rng);
+        if address_to_bucket_with_reduction(buckets, reduction, hasher.clone(), &key) == target {
+            return (Some(key), attempts);
+        }
+    }
+    (None, max_attempts)
+}
+
+/// A hasher's grinding resistance averaged over the epochs run: the analytic
+/// expected attempts to hit each epoch's most loaded bucket, and the attempts
+/// random keys actually took. Trials that gave up count the attempts they
+/// made, so with any exhausted the measured attempts are a lower bound.
+#[derive(Debug, PartialEq)]
+pub struct GrindingScore {
+    pub hasher: &'static str,
+    pub epochs: u64,
+    /// Keys ground per epoch
+    pub trials: u64,
+    pub target_share: f64,
+    pub expected_attempts: f64,
+    pub measured_attempts: f64,
+    /// Trials over every epoch that drew no key into the target
+    pub exhausted_trials: u64,
+}
+
+impl fmt::Display for GrindingScore {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            formatter,
+            "{},{},{},{},{},{},{}",
+            self.hasher, self.epochs, self.trials, self.target_share, self.expected_attempts, self.measured_attempts, self.exhausted_trials
+        )
+    }
+}
+
+#[derive(Default)]
+struct Totals {
+    epochs: u64,
+    target_share: f64,
+    expected_attempts: f64,
+    measured_attempts: f64,
+    exhausted_trials: u64,
+}
+
+/// Grinds `trials` random keys into each visited hasher's most loaded
+/// bucket, treating the n-th visit of a hasher as epoch n. Every hasher
+/// draws the same candidate keys in a given epoch.
+pub struct GrindingVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    trials: u64,
+    rng_seed: u64,
+    names: Vec<&'static str>,
+    totals: HashMap<&'static str, Totals>,
+}
+
+impl<'a> GrindingVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2], trials: u64, rng_seed: u64) -> Self {
+        Self { buckets, reduction, addresses, trials, rng_seed, names: Vec::new(), totals: HashMap::new() }
+    }
+
+    /// Each hasher's score, in visiting order.
+    pub fn scores(&self) -> Vec<GrindingScore> {
+        self.names
+            .iter()
+            .map(|name| {
+                let totals = &self.totals[name];
+                let epochs = totals.epochs.max(1) as f64;
+                GrindingScore {
+                    hasher: name,
+                    epochs: totals.epochs,
+                    trials: self.trials,
+                    target_share: totals.target_share / epochs,
+                    expected_attempts: totals.expected_attempts / epochs,
+                    measured_attempts: totals.measured_attempts / epochs,
+                    exhausted_trials: totals.exhausted_trials,
+                }
+            })
+            .collect()
+    }
+}
+
+impl HasherVisitor for GrindingVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let totals = self.totals.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            Totals::default()
+        });
+        let mut counts = vec![0; self.buckets];
+        for address in self.addresses {
+            counts[address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address)] += 1;
+        }
+        let Some((target, max)) = counts.iter().copied().enumerate().max_by_key(|(bucket, count)| (*count, std::cmp::Reverse(*bucket))) else {
+            return;
+        };
+        if max == 0 {
+            return;
+        }
+        let epoch = totals.epochs;
+        totals.epochs += 1;
+        totals.target_share += max as f64 / self.addresses.len() as f64;
+        totals.expected_attempts += expected_attempts(&counts);
+        let mut rng = rng::stream(self.rng_seed, "grinding", epoch);
+        let mut attempts = 0;
+        for _ in 0..self.trials {
+            let (key, drawn) = grind(self.buckets, self.reduction, &hasher, target, &mut rng);
+            attempts += drawn;
+            totals.exhausted_trials += u64::from(key.is_none());
+        }
+        totals.measured_attempts += attempts as f64 / self.trials.max(1) as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{hashers::Blake3Hasher, test_hashers::ConstantHasher},
+    };
+
+    #[test]
+    fn test_expected_attempts() {
+        assert_eq!(expected_attempts(&[5, 5, 5, 5]), 4.0);
+        assert_eq!(expected_attempts(&[2, 8, 0, 0]), 1.25);
+        assert_eq!(expected_attempts(&[0, 0]), 0.0);
+    }
+
+    #[test]
+    fn test_measured_matches_expected() {
+        let addresses = (0..20_000).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = GrindingVisitor::new(10, Reduction::Multiply, &addresses, 500, 0);
+        for epoch in 0..4 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+        }
+        let scores = visitor.scores();
+        assert_eq!((scores[0].hasher, scores[0].epochs, scores[0].trials), ("blake3", 4, 500));
+        assert!(scores[0].expected_attempts > 8.0 && scores[0].expected_attempts < 10.0);
+        assert!((scores[0].measured_attempts / scores[0].expected_attempts - 1.0).abs() < 0.1, "{:?}", scores[0]);
+        assert_eq!(scores[0].exhausted_trials, 0);
+    }
+
+    #[test]
+    fn test_grinding_gives_up_and_skips_empty_epochs() {
+        // Every key lands in bucket 0, so no key ever reaches bucket 1.
+        let mut rng = rng::stream(0, "grinding", 0);
+        let (key, attempts) = grind(4, Reduction::Multiply, &ConstantHasher, 1, &mut rng);
+        assert!(key.is_none());
+        assert_eq!(attempts, 4 * MAX_ATTEMPTS_PER_BUCKET);
+        assert_eq!(grind(4, Reduction::Multiply, &ConstantHasher, 0, &mut rng).1, 1);
+
+        let mut visitor = GrindingVisitor::new(4, Reduction::Multiply, &[], 3, 0);
+        visitor.visit("blake3", &Blake3Hasher::key(0), Blake3Hasher::new_with_seed(0));
+        assert_eq!(visitor.scores()[0].epochs, 0);
+    }
+}
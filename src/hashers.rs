@@ -0,0 +1,862 @@
+use {
+    clap::builder::{PossibleValue, PossibleValuesParser, TypedValueParser},
+    std::{
+        fmt,
+        hash::{BuildHasher, Hasher},
+        marker::PhantomData,
+        str::FromStr,
+    },
+};
+
+pub const SIPHASH_VARIANTS: &[(&str, &str, SipHashKeying)] = &[
+    ("siphash13-epoch-0", "siphash24-epoch-0", SipHashKeying::EpochZero),
+    ("siphash13-epoch-epoch", "siphash24-epoch-epoch", SipHashKeying::EpochEpoch),
+    ("siphash13-blake3-key", "siphash24-blake3-key", SipHashKeying::Blake3Derived),
+];
+
+/// Hashers whose output isn't specified, so may change across library
+/// versions or platforms.
+pub const NON_PORTABLE: &[&str] = &["ahash"];
+
+/// Whether the hasher called `name` has output specified independently of
+/// platform and library version, as consensus-critical partitioning needs.
+/// Blake3, SipHash, Murmur3, xxh3, wyhash, FNV-1a, HighwayHash, SHA-256 and
+/// Keccak-256 are fixed algorithms; ahash is not.
+pub fn is_portable(name: &str) -> bool {
+    !NON_PORTABLE.contains(&name)
+}
+
+/// Forwards only the hashers that [`is_portable`] accepts.
+pub struct PortableOnly<'a, V>(pub &'a mut V);
+
+impl<V: HasherVisitor> HasherVisitor for PortableOnly<'_, V> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, key: &[u8], hasher: H) {
+        if is_portable(name) {
+            self.0.visit(name, key, hasher);
+        }
+    }
+}
+
+/// A hasher the analysis can run: cloned fresh per address, shareable
+/// across threads, and able to report its complete output for audits.
+pub trait AnalysisHasher: Hasher + Clone + Send + Sync + 'static {
+    /// The hasher's full digest. Defaults to the `finish` value, for hashers
+    /// whose output is no wider than that.
+    fn digest(&self) -> Vec<u8> {
+        self.finish().to_le_bytes().to_vec()
+    }
+}
+
+/// A hasher built from a 64-bit seed alone, which lets the registry
+/// construct it by name for any epoch.
+pub trait SeedableHasher: AnalysisHasher {
+    fn with_seed(seed: u64) -> Self;
+
+    /// The key bytes `with_seed` derives from `seed`.
+    fn seed_key(seed: u64) -> Vec<u8>;
+}
+
+/// Constructs hashers on whichever thread needs one, so only the factory is
+/// shared between threads and each thread hashes with state of its own.
+/// Hashers are `Send + Sync` too, through [`AnalysisHasher`], though some are
+/// large enough (xxh3 buffers a stripe, HighwayHash holds SIMD lanes) that
+/// building them per thread beats cloning one across.
+pub trait HasherFactory: Send + Sync {
+    type Hasher: AnalysisHasher;
+
+    fn build(&self) -> Self::Hasher;
+}
+
+/// Builds a registered hasher from a seed.
+pub struct SeededFactory<H> {
+    seed: u64,
+    hasher: PhantomData<fn() -> H>,
+}
+
+impl<H> SeededFactory<H> {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, hasher: PhantomData }
+    }
+}
+
+/// A hasher builds clones of itself.
+impl<H: AnalysisHasher> HasherFactory for H {
+    type Hasher = H;
+
+    fn build(&self) -> H {
+        self.clone()
+    }
+}
+
+impl<H: SeedableHasher> HasherFactory for SeededFactory<H> {
+    type Hasher = H;
+
+    fn build(&self) -> H {
+        H::with_seed(self.seed)
+    }
+}
+
+/// Names hashers went by before the registry, each with the registered
+/// hasher it now stands for.
+pub const ALIASES: &[(&str, &str)] = &[("sip13", "siphash13"), ("sip24", "siphash24"), ("murmur3", "murmur3-128")];
+
+/// The registered name `name` stands for, resolving [`ALIASES`].
+pub fn canonical_name(name: &str) -> &str {
+    ALIASES.iter().find(|(alias, _)| *alias == name).map_or(name, |(_, canonical)| canonical)
+}
+
+/// Parses one of `names` or an alias of one, yielding the registered name.
+pub fn name_parser(names: Vec<&'static str>) -> impl TypedValueParser<Value = String> {
+    let values = names.into_iter().map(|name| PossibleValue::new(name).aliases(ALIASES.iter().filter(move |(_, canonical)| *canonical == name).map(|(alias, _)| *alias)));
+    PossibleValuesParser::new(values).map(|name| canonical_name(&name).to_string())
+}
+
+/// Receives the type and name of every registered hasher.
+pub trait RegistryVisitor {
+    fn register<H: SeedableHasher>(&mut self, name: &'static str);
+}
+
+/// The hasher registry: registering a [`SeedableHasher`] here makes it
+/// selectable by name everywhere hashers are.
+pub fn for_each_registered(visitor: &mut impl RegistryVisitor) {
+    visitor.register::<Blake3Hasher>("blake3");
+    visitor.register::<siphasher::sip::SipHasher13>("siphash13");
+    visitor.register::<siphasher::sip::SipHasher24>("siphash24");
+    visitor.register::<mur3::Hasher128>("murmur3-128");
+    visitor.register::<Murmur3x32Hasher>("murmur3-32");
+    visitor.register::<ahash::AHasher>("ahash");
+    visitor.register::<xxhash_rust::xxh3::Xxh3>("xxh3");
+    visitor.register::<wyhash::WyHash>("wyhash");
+    visitor.register::<FnvHasher>("fnv");
+    visitor.register::<highway::HighwayHasher>("highwayhash");
+    visitor.register::<Sha256Hasher>("sha256");
+    visitor.register::<Keccak256Hasher>("keccak256");
+}
+
+/// Names of the registered hashers, in registration order.
+pub fn registered_names() -> Vec<&'static str> {
+    struct Names(Vec<&'static str>);
+
+    impl RegistryVisitor for Names {
+        fn register<H: SeedableHasher>(&mut self, name: &'static str) {
+            self.0.push(name);
+        }
+    }
+
+    let mut names = Names(Vec::new());
+    for_each_registered(&mut names);
+    names.0
+}
+
+/// Visits the registered hasher called `name`, or aliased `name`, seeded with
+/// `seed`, returning false if there is no such hasher.
+pub fn visit_registered(name: &str, seed: u64, visitor: &mut impl HasherVisitor) -> bool {
+    struct Seeded<'a, V> {
+        name: &'a str,
+        seed: u64,
+        visitor: &'a mut V,
+        found: bool,
+    }
+
+    impl<V: HasherVisitor> RegistryVisitor for Seeded<'_, V> {
+        fn register<H: SeedableHasher>(&mut self, name: &'static str) {
+            if name == self.name {
+                self.visitor.visit(name, &H::seed_key(self.seed), H::with_seed(self.seed));
+                self.found = true;
+            }
+        }
+    }
+
+    let mut seeded = Seeded { name: canonical_name(name), seed, visitor, found: false };
+    for_each_registered(&mut seeded);
+    seeded.found
+}
+
+/// Receives every hasher selected for a run, seeded for one epoch, along
+/// with the exact key bytes it was constructed from.
+pub trait HasherVisitor {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, key: &[u8], hasher: H);
+}
+
+#[derive(Clone)]
+pub struct Blake3Hasher(blake3::Hasher);
+
+impl Hasher for Blake3Hasher {
+    fn finish(&self) -> u64 {
+        let hash = self.0.finalize();
+        u64::from_le_bytes(hash.as_bytes()[..8].try_into().unwrap())
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+}
+
+impl AnalysisHasher for Blake3Hasher {
+    fn digest(&self) -> Vec<u8> {
+        self.0.finalize().as_bytes().to_vec()
+    }
+}
+
+impl Blake3Hasher {
+    pub fn key(seed: u64) -> [u8; 32] {
+        let seed_bytes = seed.to_le_bytes();
+        let mut key = [0u8; 32];
+        for chunk in key.chunks_mut(8) {
+            chunk.copy_from_slice(&seed_bytes);
+        }
+        key
+    }
+
+    pub fn new_with_seed(seed: u64) -> Self {
+        Self::new_with_key(&Self::key(seed))
+    }
+
+    pub fn new_with_key(key: &[u8; 32]) -> Self {
+        Self(blake3::Hasher::new_keyed(key))
+    }
+}
+
+// SHA-256 and Keccak-256 as Solana programs compute them through the
+// `sol_sha256` and `sol_keccak256` syscalls. Neither has a keyed mode, so the
+// seed is hashed ahead of the address instead.
+#[derive(Clone, Default)]
+pub struct Sha256Hasher(solana_sdk::hash::Hasher);
+
+impl Hasher for Sha256Hasher {
+    fn finish(&self) -> u64 {
+        u64::from_le_bytes(self.0.clone().result().to_bytes()[..8].try_into().unwrap())
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.hash(bytes);
+    }
+}
+
+impl AnalysisHasher for Sha256Hasher {
+    fn digest(&self) -> Vec<u8> {
+        self.0.clone().result().to_bytes().to_vec()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Keccak256Hasher(solana_sdk::keccak::Hasher);
+
+impl Hasher for Keccak256Hasher {
+    fn finish(&self) -> u64 {
+        u64::from_le_bytes(self.0.clone().result().to_bytes()[..8].try_into().unwrap())
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.hash(bytes);
+    }
+}
+
+impl AnalysisHasher for Keccak256Hasher {
+    fn digest(&self) -> Vec<u8> {
+        self.0.clone().result().to_bytes().to_vec()
+    }
+}
+
+// `mur3::Hasher32::finish` zero-extends the 32-bit digest, which would put
+// every address in bucket 0 under the multiply-shift reduction. Repeat it in
+// both halves instead, so reductions reading the high bits and those reading
+// the low bits (mask, modulo, fastrange32) all see the full (narrower) output.
+#[derive(Clone)]
+pub struct Murmur3x32Hasher(pub mur3::Hasher32);
+
+impl Hasher for Murmur3x32Hasher {
+    fn finish(&self) -> u64 {
+        let digest = self.0.finish32() as u64;
+        digest << 32 | digest
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes);
+    }
+}
+
+impl AnalysisHasher for Murmur3x32Hasher {
+    fn digest(&self) -> Vec<u8> {
+        self.0.finish32().to_le_bytes().to_vec()
+    }
+}
+
+impl AnalysisHasher for mur3::Hasher128 {
+    fn digest(&self) -> Vec<u8> {
+        let (h1, h2) = self.finish128();
+        [h1.to_le_bytes(), h2.to_le_bytes()].concat()
+    }
+}
+
+impl AnalysisHasher for siphasher::sip::SipHasher13 {}
+
+impl AnalysisHasher for siphasher::sip::SipHasher24 {}
+
+impl AnalysisHasher for ahash::AHasher {}
+
+impl AnalysisHasher for xxhash_rust::xxh3::Xxh3 {}
+
+impl AnalysisHasher for wyhash::WyHash {}
+
+impl AnalysisHasher for highway::HighwayHasher {}
+
+// `fnv::FnvHasher` isn't `Clone`, but its state is exactly its `finish` value,
+// which `with_key` restores.
+pub struct FnvHasher(pub fnv::FnvHasher);
+
+impl Clone for FnvHasher {
+    fn clone(&self) -> Self {
+        Self(fnv::FnvHasher::with_key(self.0.finish()))
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes);
+    }
+}
+
+impl AnalysisHasher for FnvHasher {}
+
+impl SeedableHasher for Blake3Hasher {
+    fn with_seed(seed: u64) -> Self {
+        Self::new_with_seed(seed)
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        Self::key(seed).to_vec()
+    }
+}
+
+impl SeedableHasher for siphasher::sip::SipHasher13 {
+    fn with_seed(seed: u64) -> Self {
+        Self::new_with_keys(seed, seed)
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        [seed.to_le_bytes(); 2].concat()
+    }
+}
+
+impl SeedableHasher for siphasher::sip::SipHasher24 {
+    fn with_seed(seed: u64) -> Self {
+        Self::new_with_keys(seed, seed)
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        [seed.to_le_bytes(); 2].concat()
+    }
+}
+
+impl SeedableHasher for mur3::Hasher128 {
+    fn with_seed(seed: u64) -> Self {
+        Self::with_seed(seed as u32)
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        (seed as u32).to_le_bytes().to_vec()
+    }
+}
+
+impl SeedableHasher for Murmur3x32Hasher {
+    fn with_seed(seed: u64) -> Self {
+        Self(mur3::Hasher32::with_seed(seed as u32))
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        (seed as u32).to_le_bytes().to_vec()
+    }
+}
+
+impl SeedableHasher for ahash::AHasher {
+    fn with_seed(seed: u64) -> Self {
+        ahash::random_state::RandomState::with_seeds(seed, seed, seed, seed).build_hasher()
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        [seed.to_le_bytes(); 4].concat()
+    }
+}
+
+impl SeedableHasher for xxhash_rust::xxh3::Xxh3 {
+    fn with_seed(seed: u64) -> Self {
+        Self::with_seed(seed)
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        seed.to_le_bytes().to_vec()
+    }
+}
+
+impl SeedableHasher for wyhash::WyHash {
+    fn with_seed(seed: u64) -> Self {
+        Self::with_seed(seed)
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        seed.to_le_bytes().to_vec()
+    }
+}
+
+// FNV has no key; the seed replaces the offset basis.
+impl SeedableHasher for FnvHasher {
+    fn with_seed(seed: u64) -> Self {
+        Self(fnv::FnvHasher::with_key(seed))
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        seed.to_le_bytes().to_vec()
+    }
+}
+
+impl SeedableHasher for highway::HighwayHasher {
+    fn with_seed(seed: u64) -> Self {
+        Self::new(highway::Key([seed; 4]))
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        [seed.to_le_bytes(); 4].concat()
+    }
+}
+
+impl SeedableHasher for Sha256Hasher {
+    fn with_seed(seed: u64) -> Self {
+        let mut hasher = Self::default();
+        hasher.write(&Self::seed_key(seed));
+        hasher
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        seed.to_le_bytes().to_vec()
+    }
+}
+
+impl SeedableHasher for Keccak256Hasher {
+    fn with_seed(seed: u64) -> Self {
+        let mut hasher = Self::default();
+        hasher.write(&Self::seed_key(seed));
+        hasher
+    }
+
+    fn seed_key(seed: u64) -> Vec<u8> {
+        seed.to_le_bytes().to_vec()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum SipHashKeying {
+    EpochZero,
+    EpochEpoch,
+    Blake3Derived,
+}
+
+impl SipHashKeying {
+    pub fn keys(self, epoch: u64) -> (u64, u64) {
+        match self {
+            Self::EpochZero => (epoch, 0),
+            Self::EpochEpoch => (epoch, epoch),
+            Self::Blake3Derived => {
+                let key = blake3::derive_key("hash-to-bucket siphash key", &epoch.to_le_bytes());
+                (
+                    u64::from_le_bytes(key[..8].try_into().unwrap()),
+                    u64::from_le_bytes(key[8..16].try_into().unwrap()),
+                )
+            }
+        }
+    }
+}
+
+/// SipHash with any number of compression and finalization rounds, for
+/// the counts siphasher doesn't provide. SipHash-1-3 and SipHash-2-4 match
+/// siphasher's.
+#[derive(Clone)]
+pub struct SipHasherCd {
+    compression: usize,
+    finalization: usize,
+    state: [u64; 4],
+    tail: [u8; 8],
+    length: usize,
+}
+
+impl SipHasherCd {
+    pub fn new_with_keys(compression: usize, finalization: usize, key0: u64, key1: u64) -> Self {
+        let state = [key0 ^ 0x736f6d6570736575, key1 ^ 0x646f72616e646f6d, key0 ^ 0x6c7967656e657261, key1 ^ 0x7465646279746573];
+        Self { compression, finalization, state, tail: [0; 8], length: 0 }
+    }
+
+    fn rounds(state: &mut [u64; 4], rounds: usize) {
+        let [v0, v1, v2, v3] = state;
+        for _ in 0..rounds {
+            *v0 = v0.wrapping_add(*v1);
+            *v1 = v1.rotate_left(13) ^ *v0;
+            *v0 = v0.rotate_left(32);
+            *v2 = v2.wrapping_add(*v3);
+            *v3 = v3.rotate_left(16) ^ *v2;
+            *v0 = v0.wrapping_add(*v3);
+            *v3 = v3.rotate_left(21) ^ *v0;
+            *v2 = v2.wrapping_add(*v1);
+            *v1 = v1.rotate_left(17) ^ *v2;
+            *v2 = v2.rotate_left(32);
+        }
+    }
+
+    fn compress(state: &mut [u64; 4], rounds: usize, word: u64) {
+        state[3] ^= word;
+        Self::rounds(state, rounds);
+        state[0] ^= word;
+    }
+}
+
+impl Hasher for SipHasherCd {
+    fn finish(&self) -> u64 {
+        let mut state = self.state;
+        let mut last = self.tail;
+        last[7] = self.length as u8;
+        Self::compress(&mut state, self.compression, u64::from_le_bytes(last));
+        state[2] ^= 0xff;
+        Self::rounds(&mut state, self.finalization);
+        state.iter().fold(0, |hash, word| hash ^ word)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.tail[self.length % 8] = *byte;
+            self.length += 1;
+            if self.length.is_multiple_of(8) {
+                Self::compress(&mut self.state, self.compression, u64::from_le_bytes(self.tail));
+                self.tail = [0; 8];
+            }
+        }
+    }
+}
+
+impl AnalysisHasher for SipHasherCd {}
+
+/// How a HighwayHash key's four words are derived from the seed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HighwayKeySchedule {
+    /// Every word the seed, as the registered highwayhash is keyed
+    Repeat,
+    /// The seed followed by three zero words
+    ZeroPadded,
+    /// Four words derived from the seed with blake3
+    Blake3,
+}
+
+impl HighwayKeySchedule {
+    pub fn key(self, seed: u64) -> [u64; 4] {
+        match self {
+            Self::Repeat => [seed; 4],
+            Self::ZeroPadded => [seed, 0, 0, 0],
+            Self::Blake3 => {
+                let key = blake3::derive_key("hash-to-bucket highwayhash key", &seed.to_le_bytes());
+                [0, 1, 2, 3].map(|word| u64::from_le_bytes(key[word * 8..][..8].try_into().unwrap()))
+            }
+        }
+    }
+}
+
+/// Length of an xxh3 secret.
+const XXH3_SECRET_SIZE: usize = 192;
+
+/// The secret xxh3 hashes with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Xxh3Secret {
+    /// xxh3's default secret customized by the seed, as the registered xxh3 is keyed
+    Seeded,
+    /// A secret derived from the seed with blake3, with no seed
+    Blake3,
+}
+
+impl Xxh3Secret {
+    fn blake3_secret(seed: u64) -> [u8; XXH3_SECRET_SIZE] {
+        let mut secret = [0; XXH3_SECRET_SIZE];
+        blake3::Hasher::new_derive_key("hash-to-bucket xxh3 secret").update(&seed.to_le_bytes()).finalize_xof().fill(&mut secret);
+        secret
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tunable {
+    SipHashRounds { compression: usize, finalization: usize },
+    HighwayKey(HighwayKeySchedule),
+    Xxh3Secret(Xxh3Secret),
+}
+
+impl Tunable {
+    /// The hasher tuned.
+    pub fn hasher(self) -> &'static str {
+        match self {
+            Self::SipHashRounds { .. } => "siphash",
+            Self::HighwayKey(_) => "highwayhash",
+            Self::Xxh3Secret(_) => "xxh3",
+        }
+    }
+
+    // The key and value, as `hasher.key=value` gives them.
+    fn setting(self) -> (&'static str, String) {
+        match self {
+            Self::SipHashRounds { compression, finalization } => ("rounds", format!("{compression}-{finalization}")),
+            Self::HighwayKey(HighwayKeySchedule::Repeat) => ("key", "repeat".to_string()),
+            Self::HighwayKey(HighwayKeySchedule::ZeroPadded) => ("key", "zero-padded".to_string()),
+            Self::HighwayKey(HighwayKeySchedule::Blake3) => ("key", "blake3".to_string()),
+            Self::Xxh3Secret(Xxh3Secret::Seeded) => ("secret", "seeded".to_string()),
+            Self::Xxh3Secret(Xxh3Secret::Blake3) => ("secret", "blake3".to_string()),
+        }
+    }
+}
+
+/// Writes the option as parsed, e.g. `siphash.rounds=1-3`.
+impl fmt::Display for Tunable {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let (key, value) = self.setting();
+        write!(formatter, "{}.{key}={value}", self.hasher())
+    }
+}
+
+/// A registered hasher with one tunable changed, parsed from
+/// `hasher.key=value`:
+///
+/// - `siphash.rounds=C-D`: SipHash with C compression and D finalization rounds
+/// - `highwayhash.key=repeat|zero-padded|blake3`: the HighwayHash key schedule
+/// - `xxh3.secret=seeded|blake3`: the xxh3 secret
+///
+/// It runs as `hasher-key-value`, e.g. `siphash-rounds-1-3`, a name safe in
+/// file names.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HasherOpt {
+    pub name: &'static str,
+    pub tunable: Tunable,
+}
+
+impl FromStr for HasherOpt {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Self, String> {
+        let tunable = match arg.split_once('=') {
+            Some(("siphash.rounds", rounds)) => match rounds.split_once('-').map(|(c, d)| (c.parse(), d.parse())) {
+                Some((Ok(compression), Ok(finalization))) if compression > 0 && finalization > 0 => Tunable::SipHashRounds { compression, finalization },
+                _ => return Err(format!("expected siphash rounds as C-D, such as 2-4, got {rounds:?}")),
+            },
+            Some(("highwayhash.key", "repeat")) => Tunable::HighwayKey(HighwayKeySchedule::Repeat),
+            Some(("highwayhash.key", "zero-padded")) => Tunable::HighwayKey(HighwayKeySchedule::ZeroPadded),
+            Some(("highwayhash.key", "blake3")) => Tunable::HighwayKey(HighwayKeySchedule::Blake3),
+            Some(("xxh3.secret", "seeded")) => Tunable::Xxh3Secret(Xxh3Secret::Seeded),
+            Some(("xxh3.secret", "blake3")) => Tunable::Xxh3Secret(Xxh3Secret::Blake3),
+            Some((key @ ("highwayhash.key" | "xxh3.secret"), value)) => return Err(format!("unknown {key} {value:?}")),
+            _ => return Err(format!("expected siphash.rounds=C-D, highwayhash.key=SCHEDULE or xxh3.secret=SECRET, got {arg:?}")),
+        };
+        let (key, value) = tunable.setting();
+        // Parsed once per command line argument, so leaking the name is bounded.
+        Ok(Self { name: Box::leak(format!("{}-{key}-{value}", tunable.hasher()).into_boxed_str()), tunable })
+    }
+}
+
+impl HasherOpt {
+    /// Visits the tuned hasher seeded with `seed`.
+    pub fn visit(&self, seed: u64, visitor: &mut impl HasherVisitor) {
+        match self.tunable {
+            Tunable::SipHashRounds { compression, finalization } => {
+                visitor.visit(self.name, &[seed.to_le_bytes(); 2].concat(), SipHasherCd::new_with_keys(compression, finalization, seed, seed));
+            }
+            Tunable::HighwayKey(schedule) => {
+                let key = schedule.key(seed);
+                visitor.visit(self.name, &key.map(u64::to_le_bytes).concat(), highway::HighwayHasher::new(highway::Key(key)));
+            }
+            Tunable::Xxh3Secret(Xxh3Secret::Seeded) => visitor.visit(self.name, &seed.to_le_bytes(), xxhash_rust::xxh3::Xxh3::with_seed(seed)),
+            Tunable::Xxh3Secret(Xxh3Secret::Blake3) => {
+                let secret = Xxh3Secret::blake3_secret(seed);
+                visitor.visit(self.name, &secret, xxhash_rust::xxh3::Xxh3::with_secret(secret));
+            }
+        }
+    }
+}
+
+/// The seed for `epoch` in a run with `nonce`: a blake3-derived mix of both,
+/// so runs with different nonces key every hasher differently.
+pub fn nonce_seed(epoch: u64, nonce: u64) -> u64 {
+    let key = blake3::derive_key("hash-to-bucket nonce seed", &[epoch.to_le_bytes(), nonce.to_le_bytes()].concat());
+    u64::from_le_bytes(key[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn test_siphash_keying() {
+        assert_eq!(SipHashKeying::EpochZero.keys(7), (7, 0));
+        assert_eq!(SipHashKeying::EpochEpoch.keys(7), (7, 7));
+        let (key0, key1) = SipHashKeying::Blake3Derived.keys(7);
+        assert_ne!(key0, key1);
+        assert_ne!((key0, key1), SipHashKeying::Blake3Derived.keys(8));
+    }
+
+    #[test]
+    fn test_nonce_seed() {
+        assert_eq!(nonce_seed(7, 1), nonce_seed(7, 1));
+        assert_ne!(nonce_seed(7, 1), nonce_seed(7, 2));
+        assert_ne!(nonce_seed(7, 1), nonce_seed(8, 1));
+        assert_ne!(nonce_seed(7, 1), 7);
+    }
+
+    struct Names(Vec<&'static str>);
+
+    impl HasherVisitor for Names {
+        fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], _hasher: H) {
+            self.0.push(name);
+        }
+    }
+
+    #[test]
+    fn test_visit_registered() {
+        let mut names = Names(Vec::new());
+        for name in registered_names() {
+            assert!(visit_registered(name, 7, &mut names));
+        }
+        assert_eq!(names.0, registered_names());
+        assert!(!visit_registered("md5", 7, &mut names));
+        let mut names = Names(Vec::new());
+        for (alias, _) in ALIASES {
+            assert!(visit_registered(alias, 7, &mut names));
+        }
+        assert_eq!(names.0, vec!["siphash13", "siphash24", "murmur3-128"]);
+    }
+
+    #[test]
+    fn test_registered_hashers_are_seeded() {
+        struct Digests(Vec<u64>);
+
+        impl HasherVisitor for Digests {
+            fn visit<H: AnalysisHasher>(&mut self, _name: &'static str, _key: &[u8], mut hasher: H) {
+                hasher.write(b"address");
+                self.0.push(hasher.finish());
+            }
+        }
+
+        for name in registered_names() {
+            let mut digests = Digests(Vec::new());
+            for seed in [1, 1, 2] {
+                visit_registered(name, seed, &mut digests);
+            }
+            assert_eq!(digests.0[0], digests.0[1], "{name}");
+            assert_ne!(digests.0[0], digests.0[2], "{name}");
+        }
+    }
+
+    #[test]
+    fn test_portable_only() {
+        let mut names = Names(Vec::new());
+        let mut portable = PortableOnly(&mut names);
+        portable.visit("blake3", &[], Blake3Hasher::new_with_seed(0));
+        portable.visit("ahash", &[], ahash::AHasher::default());
+        assert_eq!(names.0, vec!["blake3"]);
+        assert!(is_portable("siphash13-epoch-0"));
+    }
+
+    #[test]
+    fn test_murmur3_x32_fills_both_halves() {
+        let address = Pubkey::new_unique();
+        let mut hasher = Murmur3x32Hasher(mur3::Hasher32::with_seed(0));
+        hasher.write(address.as_ref());
+        let expected = mur3::murmurhash3_x86_32(address.as_ref(), 0);
+        assert_eq!(hasher.finish(), (expected as u64) << 32 | expected as u64);
+        assert_eq!(hasher.digest(), expected.to_le_bytes());
+    }
+
+    #[test]
+    fn test_fnv_clone_keeps_state() {
+        let mut hasher = FnvHasher::with_seed(5);
+        hasher.write(b"prefix");
+        let mut clone = hasher.clone();
+        hasher.write(b"suffix");
+        clone.write(b"suffix");
+        assert_eq!(hasher.finish(), clone.finish());
+    }
+
+    #[test]
+    fn test_syscall_hashers_match_solana() {
+        let address = Pubkey::new_unique();
+        let mut sha256 = Sha256Hasher::with_seed(9);
+        sha256.write(address.as_ref());
+        assert_eq!(sha256.digest(), solana_sdk::hash::hashv(&[&9u64.to_le_bytes(), address.as_ref()]).to_bytes());
+        assert_eq!(sha256.digest()[..8], sha256.finish().to_le_bytes());
+        let mut keccak256 = Keccak256Hasher::with_seed(9);
+        keccak256.write(address.as_ref());
+        assert_eq!(keccak256.digest(), solana_sdk::keccak::hashv(&[&9u64.to_le_bytes(), address.as_ref()]).to_bytes());
+    }
+
+    #[test]
+    fn test_blake3_digest_prefixes_finish() {
+        let mut hasher = Blake3Hasher::new_with_seed(3);
+        hasher.write(Pubkey::new_unique().as_ref());
+        let digest = hasher.digest();
+        assert_eq!(digest.len(), 32);
+        assert_eq!(digest[..8], hasher.finish().to_le_bytes());
+    }
+
+    #[test]
+    fn test_siphash_cd_matches_siphasher() {
+        let bytes = (0..40).collect::<Vec<u8>>();
+        for length in 0..bytes.len() {
+            let (head, tail) = bytes[..length].split_at(length / 3);
+            let digest = |compression, finalization| {
+                let mut hasher = SipHasherCd::new_with_keys(compression, finalization, 3, 5);
+                hasher.write(head);
+                hasher.write(tail);
+                hasher.finish()
+            };
+            let mut sip13 = siphasher::sip::SipHasher13::new_with_keys(3, 5);
+            sip13.write(&bytes[..length]);
+            let mut sip24 = siphasher::sip::SipHasher24::new_with_keys(3, 5);
+            sip24.write(&bytes[..length]);
+            assert_eq!(digest(1, 3), sip13.finish(), "{length}");
+            assert_eq!(digest(2, 4), sip24.finish(), "{length}");
+            assert_ne!(digest(4, 8), sip24.finish(), "{length}");
+        }
+    }
+
+    #[test]
+    fn test_hasher_opts() {
+        struct Digests(Vec<(&'static str, u64)>);
+
+        impl HasherVisitor for Digests {
+            fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], mut hasher: H) {
+                hasher.write(b"address");
+                self.0.push((name, hasher.finish()));
+            }
+        }
+
+        let digest = |opt: &str, seed| {
+            let mut digests = Digests(Vec::new());
+            match opt.parse::<HasherOpt>() {
+                Ok(opt) => opt.visit(seed, &mut digests),
+                Err(_) => assert!(visit_registered(opt, seed, &mut digests)),
+            }
+            digests.0[0]
+        };
+        assert_eq!(digest("siphash.rounds=1-3", 7).1, digest("siphash13", 7).1);
+        assert_eq!(digest("highwayhash.key=repeat", 7).1, digest("highwayhash", 7).1);
+        assert_eq!(digest("xxh3.secret=seeded", 7).1, digest("xxh3", 7).1);
+        for (opt, name) in [
+            ("siphash.rounds=3-5", "siphash-rounds-3-5"),
+            ("highwayhash.key=zero-padded", "highwayhash-key-zero-padded"),
+            ("highwayhash.key=blake3", "highwayhash-key-blake3"),
+            ("xxh3.secret=blake3", "xxh3-secret-blake3"),
+        ] {
+            assert_eq!(digest(opt, 7), digest(opt, 7));
+            assert_eq!(digest(opt, 7).0, name);
+            assert_eq!(opt.parse::<HasherOpt>().unwrap().tunable.to_string(), opt);
+            assert_ne!(digest(opt, 7).1, digest(opt, 8).1, "{opt}");
+        }
+        assert_ne!(digest("xxh3.secret=blake3", 7).1, digest("xxh3", 7).1);
+        for bad in ["siphash.rounds=0-4", "siphash.rounds=2", "xxh3.secret=zero", "blake3.rounds=7", "xxh3"] {
+            assert!(bad.parse::<HasherOpt>().is_err(), "{bad}");
+        }
+    }
+}
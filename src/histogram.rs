@@ -0,0 +1,78 @@
+use std::io::{self, Write};
+
+const WIDTH: usize = 50;
+const MAX_ROWS: usize = 20;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Draws how many buckets hold each load as rows of `#`, one per range of
+/// loads, the ranges equally wide and at most [`MAX_ROWS`] of them.
+pub fn write_histogram(counts: &[usize], out: &mut impl Write) -> io::Result<()> {
+    let (Some(&min), Some(&max)) = (counts.iter().min(), counts.iter().max()) else {
+        return Ok(());
+    };
+    let width = (max - min + 1).div_ceil(MAX_ROWS);
+    let mut rows = vec![0; (max - min) / width + 1];
+    for count in counts {
+        rows[(count - min) / width] += 1;
+    }
+    let tallest = rows.iter().copied().max().unwrap_or(0).max(1);
+    let label = |row: usize| match (min + row * width, min + row * width + width - 1) {
+        (low, high) if low == high => low.to_string(),
+        (low, high) => format!("{low}-{high}"),
+    };
+    let label_width = (0..rows.len()).map(|row| label(row).len()).max().unwrap_or(0);
+    for (row, buckets) in rows.iter().enumerate() {
+        let bar = if *buckets == 0 { 0 } else { (buckets * WIDTH).div_ceil(tallest) };
+        writeln!(out, "{:>label_width$} |{:<WIDTH$} {buckets}", label(row), "#".repeat(bar))?;
+    }
+    Ok(())
+}
+
+/// One block character per value, from lowest to highest of `values`.
+pub fn sparkline(values: &[f64]) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|value| match max - min {
+            range if range > 0.0 => SPARKS[((value - min) / range * (SPARKS.len() - 1) as f64).round() as usize],
+            _ => SPARKS[0],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut out = Vec::new();
+        write_histogram(&[2, 3, 3, 5, 3], &mut out).unwrap();
+        let lines = String::from_utf8(out).unwrap().lines().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], format!("2 |{:<WIDTH$} 1", "#".repeat(17)));
+        assert_eq!(lines[1], format!("3 |{} 3", "#".repeat(WIDTH)));
+        assert_eq!(lines[2], format!("4 |{} 0", " ".repeat(WIDTH)));
+
+        let mut out = Vec::new();
+        write_histogram(&(0..100).collect::<Vec<_>>(), &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), MAX_ROWS);
+        assert!(text.starts_with("  0-4 |") && text.ends_with(&format!("95-99 |{} 5\n", "#".repeat(WIDTH))));
+
+        // 21 distinct loads don't fit one per row.
+        let mut out = Vec::new();
+        write_histogram(&(0..=20).collect::<Vec<_>>(), &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), 11);
+        assert!(text.starts_with("  0-1 |") && text.ends_with(&format!("20-21 |{:<WIDTH$} 1\n", "#".repeat(WIDTH / 2))));
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[1.0, 8.0, 4.5, 1.0]), "▁█▅▁");
+        assert_eq!(sparkline(&[3.0, 3.0]), "▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
@@ -0,0 +1,218 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    std::fmt,
+    xxhash_rust::xxh3::xxh3_64,
+};
+
+pub const HEADER: &str = "epoch,hasher,entries,distinct,min_distinct,mean_distinct,max_distinct,distinct_spread";
+
+/// Smallest and largest supported precision, the log2 of the register count.
+pub const PRECISIONS: std::ops::RangeInclusive<u64> = 4..=16;
+
+/// Distinct-item count estimate in `2^precision` bytes (Flajolet et al.,
+/// 2007), with linear counting below `2.5 * 2^precision`. The standard error
+/// is about `1.04 / sqrt(2^precision)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HyperLogLog {
+    precision: u32,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub fn new(precision: u32) -> Self {
+        assert!(PRECISIONS.contains(&(precision as u64)), "precision must be in {PRECISIONS:?}");
+        Self { precision, registers: vec![0; 1 << precision] }
+    }
+
+    /// Counts an item by a uniformly distributed hash of it.
+    pub fn insert_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rank = ((hash << self.precision).leading_zeros() + 1).min(64 - self.precision + 1) as u8;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /// Makes this the sketch of the union of both sketches' items.
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(self.precision, other.precision);
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum = self.registers.iter().map(|register| 2f64.powi(-(*register as i32))).sum::<f64>();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|register| **register == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        }
+    }
+}
+
+/// One hasher's epoch: exact entry counts per bucket, but distinct addresses
+/// only estimated, from a sketch per bucket.
+#[derive(Debug, PartialEq)]
+pub struct DistinctEstimate {
+    pub epoch: u64,
+    pub hasher: &'static str,
+    pub entries: usize,
+    /// Estimated distinct addresses over all buckets
+    pub distinct: f64,
+    pub min_distinct: f64,
+    pub mean_distinct: f64,
+    pub max_distinct: f64,
+}
+
+impl fmt::Display for DistinctEstimate {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            formatter,
+            "{},{},{},{},{},{},{},{}",
+            self.epoch,
+            self.hasher,
+            self.entries,
+            self.distinct,
+            self.min_distinct,
+            self.mean_distinct,
+            self.max_distinct,
+            self.max_distinct - self.min_distinct
+        )
+    }
+}
+
+/// Sketch bytes, one register per byte, [`DistinctSketches`] may hold in
+/// all.
+pub const MAX_SKETCH_BYTES: u64 = 1 << 30;
+
+/// Bytes `sketches` sketches of 2^`precision` registers take.
+pub fn sketch_bytes(sketches: u64, precision: u32) -> u64 {
+    sketches.saturating_mul(1 << precision)
+}
+
+/// One epoch's sketches of each visited hasher's distinct addresses per
+/// bucket, accumulated over as many chunks of addresses as are visited, so
+/// memory is independent of the input. Sketches are fed an unkeyed xxh3 of
+/// the address rather than the bucketing hash, which the bucket itself
+/// would bias.
+pub struct DistinctSketches {
+    epoch: u64,
+    buckets: usize,
+    reduction: Reduction,
+    precision: u32,
+    // Each hasher's entries and bucket sketches, in visiting order.
+    hashers: Vec<(&'static str, usize, Vec<HyperLogLog>)>,
+}
+
+impl DistinctSketches {
+    pub fn new(epoch: u64, buckets: usize, reduction: Reduction, precision: u32) -> Self {
+        Self { epoch, buckets, reduction, precision, hashers: Vec::new() }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// A visitor adding `addresses` to each hasher it visits.
+    pub fn add<'a>(&'a mut self, addresses: &'a [Pubkey2]) -> DistinctVisitor<'a> {
+        DistinctVisitor { sketches: self, addresses }
+    }
+
+    /// Each hasher's estimates, in visiting order.
+    pub fn estimates(&self) -> Vec<DistinctEstimate> {
+        self.hashers
+            .iter()
+            .map(|(name, entries, sketches)| {
+                let per_bucket = sketches.iter().map(HyperLogLog::estimate).collect::<Vec<_>>();
+                let mut union = HyperLogLog::new(self.precision);
+                for sketch in sketches {
+                    union.merge(sketch);
+                }
+                DistinctEstimate {
+                    epoch: self.epoch,
+                    hasher: name,
+                    entries: *entries,
+                    distinct: union.estimate(),
+                    min_distinct: per_bucket.iter().copied().fold(f64::INFINITY, f64::min),
+                    mean_distinct: per_bucket.iter().sum::<f64>() / self.buckets as f64,
+                    max_distinct: per_bucket.iter().copied().fold(0.0, f64::max),
+                }
+            })
+            .collect()
+    }
+}
+
+pub struct DistinctVisitor<'a> {
+    sketches: &'a mut DistinctSketches,
+    addresses: &'a [Pubkey2],
+}
+
+impl HasherVisitor for DistinctVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let DistinctSketches { buckets, reduction, precision, hashers, .. } = &mut *self.sketches;
+        let index = hashers.iter().position(|(hashed, ..)| *hashed == name).unwrap_or_else(|| {
+            hashers.push((name, 0, vec![HyperLogLog::new(*precision); *buckets]));
+            hashers.len() - 1
+        });
+        let (_, entries, sketches) = &mut hashers[index];
+        *entries += self.addresses.len();
+        for address in self.addresses {
+            let bucket = address_to_bucket_with_reduction(*buckets, *reduction, hasher.clone(), address);
+            sketches[bucket].insert_hash(xxh3_64(address.as_ref()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn test_estimate() {
+        for (precision, items) in [(4, 10), (10, 100), (10, 100_000), (14, 1_000_000)] {
+            let mut sketch = HyperLogLog::new(precision);
+            for item in 0u64..items {
+                // Inserting twice doesn't count twice.
+                sketch.insert_hash(xxh3_64(&item.to_le_bytes()));
+                sketch.insert_hash(xxh3_64(&item.to_le_bytes()));
+            }
+            let error = 1.04 / ((1u64 << precision) as f64).sqrt();
+            let relative = (sketch.estimate() / items as f64 - 1.0).abs();
+            assert!(relative < 4.0 * error, "p={precision} n={items}: {}", sketch.estimate());
+        }
+    }
+
+    #[test]
+    fn test_distinct_ignores_duplicates() {
+        let distinct = (0..1000).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let addresses = distinct.iter().chain(&distinct).cloned().collect::<Vec<_>>();
+        let mut sketches = DistinctSketches::new(0, 4, Reduction::Multiply, 12);
+        sketches.add(&addresses).visit("blake3", &Blake3Hasher::key(0), Blake3Hasher::new_with_seed(0));
+        let estimates = sketches.estimates();
+        let estimate = &estimates[0];
+        assert_eq!(estimate.entries, 2000);
+        assert!((estimate.distinct - 1000.0).abs() < 50.0, "{estimate:?}");
+        assert!((estimate.mean_distinct - 250.0).abs() < 15.0, "{estimate:?}");
+        assert!(estimate.min_distinct <= estimate.mean_distinct && estimate.mean_distinct <= estimate.max_distinct);
+
+        // Fed in chunks, the sketches match one pass.
+        let mut chunked = DistinctSketches::new(0, 4, Reduction::Multiply, 12);
+        for chunk in addresses.chunks(300) {
+            chunked.add(chunk).visit("blake3", &Blake3Hasher::key(0), Blake3Hasher::new_with_seed(0));
+        }
+        assert_eq!(chunked.estimates(), estimates);
+    }
+}
@@ -0,0 +1,267 @@
+use {
+    crate::{
+        tags::{self, InputEntry},
+        Pubkey2,
+    },
+    clap::ValueEnum,
+    serde::de::{Deserializer as _, SeqAccess, Visitor},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        fmt,
+        fs::File,
+        io::{BufRead, BufReader, ErrorKind},
+        num::NonZeroUsize,
+        path::Path,
+        str::FromStr,
+    },
+};
+
+/// How an address file lays out its addresses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum InputFormat {
+    /// A JSON array of base58 addresses, each optionally tagged
+    #[default]
+    Json,
+    /// One JSON entry per line, as in the array
+    Jsonl,
+    /// Comma-separated with a header row, addresses in one column
+    Csv,
+    /// One base58 address per line; blank lines and `#` comments skipped
+    Txt,
+    /// Packed raw 32-byte addresses
+    Bin,
+}
+
+impl InputFormat {
+    /// The format `path`'s extension names, if any.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            "txt" => Some(Self::Txt),
+            "bin" => Some(Self::Bin),
+            _ => None,
+        }
+    }
+
+    /// [`from_extension`](Self::from_extension), else JSON.
+    pub fn detect(path: &Path) -> Self {
+        Self::from_extension(path).unwrap_or_default()
+    }
+}
+
+/// The CSV column holding addresses: a header name, or a zero-based index.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CsvColumn {
+    Index(usize),
+    Name(String),
+}
+
+impl Default for CsvColumn {
+    fn default() -> Self {
+        Self::Index(0)
+    }
+}
+
+impl FromStr for CsvColumn {
+    type Err = String;
+
+    fn from_str(column: &str) -> Result<Self, String> {
+        match column.parse() {
+            Ok(index) => Ok(Self::Index(index)),
+            Err(_) if !column.is_empty() => Ok(Self::Name(column.to_string())),
+            Err(_) => Err("expected a column name or index".to_string()),
+        }
+    }
+}
+
+struct Entries<F>(F);
+
+impl<'de, F: FnMut(InputEntry)> Visitor<'de> for Entries<F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of addresses")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(entry) = seq.next_element()? {
+            (self.0)(entry);
+        }
+        Ok(())
+    }
+}
+
+fn parse_address(field: &str, line: usize) -> Result<InputEntry, String> {
+    let field = field.trim().trim_matches('"');
+    field.parse::<Pubkey>().map(|address| InputEntry::Address(Pubkey2(address))).map_err(|err| format!("line {line}: {err}: {field:?}"))
+}
+
+/// Hands `f` each entry of `reader` in order. JSON input that doesn't open
+/// with `[` is read as JSON Lines. Only JSON entries carry tags.
+pub fn read_entries(mut reader: impl BufRead, format: InputFormat, column: &CsvColumn, mut f: impl FnMut(InputEntry)) -> Result<(), String> {
+    let io_error = |err: std::io::Error| err.to_string();
+    match format {
+        InputFormat::Json | InputFormat::Jsonl => {
+            if first_byte(&mut reader).map_err(io_error)? == Some(b'[') {
+                let mut deserializer = serde_json::Deserializer::from_reader(reader);
+                deserializer.deserialize_seq(Entries(f)).map_err(|err| err.to_string())?;
+                deserializer.end().map_err(|err| err.to_string())?;
+            } else {
+                for entry in serde_json::Deserializer::from_reader(reader).into_iter::<InputEntry>() {
+                    f(entry.map_err(|err| err.to_string())?);
+                }
+            }
+        }
+        InputFormat::Txt => {
+            for (number, line) in reader.lines().enumerate() {
+                let line = line.map_err(io_error)?;
+                if !line.trim().is_empty() && !line.trim_start().starts_with('#') {
+                    f(parse_address(&line, number + 1)?);
+                }
+            }
+        }
+        InputFormat::Csv => {
+            let mut lines = reader.lines();
+            let Some(header) = lines.next().transpose().map_err(io_error)? else {
+                return Ok(());
+            };
+            let index = match column {
+                CsvColumn::Index(index) => *index,
+                CsvColumn::Name(name) => header
+                    .split(',')
+                    .position(|field| field.trim().trim_matches('"') == name)
+                    .ok_or_else(|| format!("no column {name:?} in header {header:?}"))?,
+            };
+            for (number, line) in lines.enumerate() {
+                let line = line.map_err(io_error)?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let field = line.split(',').nth(index).ok_or_else(|| format!("line {}: no column {index}", number + 2))?;
+                f(parse_address(field, number + 2)?);
+            }
+        }
+        InputFormat::Bin => {
+            let mut address = [0; 32];
+            for count in 0.. {
+                let mut filled = 0;
+                while filled < address.len() {
+                    match reader.read(&mut address[filled..]) {
+                        Ok(0) => break,
+                        Ok(read) => filled += read,
+                        Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                        Err(err) => return Err(io_error(err)),
+                    }
+                }
+                match filled {
+                    0 => break,
+                    32 => f(InputEntry::Address(Pubkey2(Pubkey::new_from_array(address)))),
+                    _ => return Err(format!("{filled} trailing bytes after {count} 32-byte addresses")),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads an address file and each tagged address's tag, as
+/// [`load_tagged_addresses`](crate::load_tagged_addresses) does for JSON.
+pub fn load_tagged(path: &Path, format: InputFormat, column: &CsvColumn) -> Result<(Vec<Pubkey2>, HashMap<Pubkey, String>), String> {
+    let file = File::open(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let mut entries = Vec::new();
+    read_entries(BufReader::new(file), format, column, |entry| entries.push(entry)).map_err(|err| format!("{}: {err}", path.display()))?;
+    Ok(tags::split_entries(entries))
+}
+
+/// Streams an address file's entries, handing `f` their addresses
+/// `chunk_size` at a time so memory is bounded by the chunk rather than the
+/// file. Tags are dropped. Returns the number of entries read.
+pub fn for_each_chunk(path: &Path, format: InputFormat, column: &CsvColumn, chunk_size: NonZeroUsize, f: impl FnMut(&[Pubkey2])) -> Result<usize, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    read_chunks(BufReader::new(file), format, column, chunk_size, f).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// [`for_each_chunk`] over any buffered reader.
+pub fn read_chunks(reader: impl BufRead, format: InputFormat, column: &CsvColumn, chunk_size: NonZeroUsize, mut f: impl FnMut(&[Pubkey2])) -> Result<usize, String> {
+    let mut chunk = Vec::with_capacity(chunk_size.get());
+    let mut entries = 0;
+    read_entries(reader, format, column, |entry| {
+        chunk.push(entry.into_address());
+        entries += 1;
+        if chunk.len() == chunk_size.get() {
+            f(&chunk);
+            chunk.clear();
+        }
+    })?;
+    if !chunk.is_empty() {
+        f(&chunk);
+    }
+    Ok(entries)
+}
+
+// The first byte that isn't JSON whitespace, left unconsumed.
+fn first_byte(reader: &mut impl BufRead) -> std::io::Result<Option<u8>> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        let Some(&byte) = buffer.first() else {
+            return Ok(None);
+        };
+        if !byte.is_ascii_whitespace() {
+            return Ok(Some(byte));
+        }
+        reader.consume(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(bytes: &[u8], format: InputFormat, column: &str) -> Result<Vec<Pubkey>, String> {
+        let mut addresses = Vec::new();
+        read_entries(bytes, format, &column.parse().unwrap(), |entry| addresses.push(*entry.into_address()))?;
+        Ok(addresses)
+    }
+
+    fn chunks(text: &str, chunk_size: usize) -> (Vec<Vec<Pubkey>>, usize) {
+        let mut chunks = Vec::new();
+        let entries = read_chunks(text.as_bytes(), InputFormat::Json, &CsvColumn::default(), NonZeroUsize::new(chunk_size).unwrap(), |chunk| {
+            chunks.push(chunk.iter().map(|address| **address).collect());
+        })
+        .unwrap();
+        (chunks, entries)
+    }
+
+    #[test]
+    fn test_array_and_lines_match() {
+        let keys = (0..5).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let array = format!(r#" ["{}", "{}", {{"pubkey": "{}", "tag": "vote"}}, "{}", "{}"]"#, keys[0], keys[1], keys[2], keys[3], keys[4]);
+        let lines = keys.iter().map(|key| format!("\"{key}\"\n")).collect::<String>();
+        let expected = vec![keys[..2].to_vec(), keys[2..4].to_vec(), keys[4..].to_vec()];
+        assert_eq!(chunks(&array, 2), (expected.clone(), 5));
+        assert_eq!(chunks(&lines, 2), (expected, 5));
+        assert_eq!(chunks("[]", 2), (Vec::new(), 0));
+        assert!(read(br#"["nope"]"#, InputFormat::Json, "0").is_err());
+        assert!(read(format!(r#"["{}"] 1"#, keys[0]).as_bytes(), InputFormat::Json, "0").is_err());
+    }
+
+    #[test]
+    fn test_formats() {
+        let keys = (0..3).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let txt = format!("# accounts\n{}\n\n  {}\n{}\n", keys[0], keys[1], keys[2]);
+        assert_eq!(read(txt.as_bytes(), InputFormat::Txt, "0"), Ok(keys.clone()));
+        let csv = format!("lamports,pubkey\n1,{}\n2,\"{}\"\n3,{}\n", keys[0], keys[1], keys[2]);
+        assert_eq!(read(csv.as_bytes(), InputFormat::Csv, "pubkey"), Ok(keys.clone()));
+        assert_eq!(read(csv.as_bytes(), InputFormat::Csv, "1"), Ok(keys.clone()));
+        assert!(read(csv.as_bytes(), InputFormat::Csv, "owner").is_err());
+        assert_eq!(read(csv.as_bytes(), InputFormat::Csv, "0"), Err("line 2: String is the wrong size: \"1\"".to_string()));
+        let bin = keys.iter().flat_map(|key| key.to_bytes()).collect::<Vec<_>>();
+        assert_eq!(read(&bin, InputFormat::Bin, "0"), Ok(keys.clone()));
+        assert!(read(&bin[..40], InputFormat::Bin, "0").is_err());
+        assert_eq!(InputFormat::detect(Path::new("a.CSV")), InputFormat::Csv);
+        assert_eq!(InputFormat::detect(Path::new("accounts")), InputFormat::Json);
+    }
+}
@@ -0,0 +1,98 @@
+use {
+    crate::{
+        hashers::{AnalysisHasher, HasherVisitor},
+        Pubkey2,
+    },
+    hdrhistogram::Histogram,
+    std::{collections::HashMap, fmt, hint::black_box, time::Instant},
+};
+
+pub const HEADER: &str = "hasher,samples,p50_ns,p99_ns,p999_ns,max_ns";
+
+// Nanoseconds, three significant figures.
+const SIGNIFICANT_FIGURES: u8 = 3;
+
+#[derive(Debug, PartialEq)]
+pub struct LatencySummary {
+    pub hasher: &'static str,
+    pub samples: u64,
+    pub p50_ns: u64,
+    pub p99_ns: u64,
+    pub p999_ns: u64,
+    pub max_ns: u64,
+}
+
+impl fmt::Display for LatencySummary {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{}", self.hasher, self.samples, self.p50_ns, self.p99_ns, self.p999_ns, self.max_ns)
+    }
+}
+
+/// Times individual assignments (clone, write, finish) of every
+/// `sample_rate`th address into a per-hasher HDR histogram, accumulating
+/// across however many epochs it visits.
+pub struct LatencyVisitor<'a> {
+    addresses: &'a [Pubkey2],
+    sample_rate: usize,
+    names: Vec<&'static str>,
+    histograms: HashMap<&'static str, Histogram<u64>>,
+}
+
+impl<'a> LatencyVisitor<'a> {
+    pub fn new(addresses: &'a [Pubkey2], sample_rate: usize) -> Self {
+        Self { addresses, sample_rate: sample_rate.max(1), names: Vec::new(), histograms: HashMap::new() }
+    }
+
+    pub fn summaries(&self) -> Vec<LatencySummary> {
+        self.names
+            .iter()
+            .map(|name| {
+                let histogram = &self.histograms[name];
+                LatencySummary {
+                    hasher: name,
+                    samples: histogram.len(),
+                    p50_ns: histogram.value_at_quantile(0.5),
+                    p99_ns: histogram.value_at_quantile(0.99),
+                    p999_ns: histogram.value_at_quantile(0.999),
+                    max_ns: histogram.max(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl HasherVisitor for LatencyVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let histogram = self.histograms.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            Histogram::new(SIGNIFICANT_FIGURES).unwrap()
+        });
+        for address in self.addresses.iter().step_by(self.sample_rate) {
+            let start = Instant::now();
+            let mut hasher = hasher.clone();
+            hasher.write(address.as_ref());
+            black_box(hasher.finish());
+            histogram.saturating_record(start.elapsed().as_nanos() as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn test_latency_visitor() {
+        let addresses = (0..10).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = LatencyVisitor::new(&addresses, 3);
+        for epoch in 0..2 {
+            visitor.visit("blake3", &[], Blake3Hasher::new_with_seed(epoch));
+        }
+        let summaries = visitor.summaries();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].hasher, "blake3");
+        assert_eq!(summaries[0].samples, 8);
+        assert!(summaries[0].p50_ns <= summaries[0].p99_ns);
+        assert!(summaries[0].p999_ns <= summaries[0].max_ns);
+    }
+}
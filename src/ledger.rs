@@ -0,0 +1,130 @@
+#[cfg(feature = "ledger")]
+use {
+    solana_ledger::blockstore::Blockstore,
+    solana_transaction_status::VersionedTransactionWithStatusMeta,
+    std::path::PathBuf,
+};
+use {
+    solana_sdk::{
+        clock::{Epoch, Slot},
+        epoch_schedule::EpochSchedule,
+        pubkey::Pubkey,
+    },
+    solana_storage_bigtable::LedgerStorage,
+    solana_transaction_status::TransactionWithStatusMeta,
+    std::{collections::HashSet, ops::RangeInclusive},
+};
+
+// Slots requested per Bigtable listing.
+const BIGTABLE_PAGE: usize = 1000;
+
+pub enum LedgerSource {
+    /// A validator's RocksDB ledger directory, or an archive of one
+    #[cfg(feature = "ledger")]
+    Rocksdb(PathBuf),
+    /// The warehouse Bigtable, authenticated with this credential file or
+    /// `GOOGLE_APPLICATION_CREDENTIALS`
+    Bigtable { credential_path: Option<String> },
+}
+
+/// The slots of `epoch` under mainnet's schedule, which has no warmup.
+pub fn epoch_slots(epoch: Epoch) -> RangeInclusive<Slot> {
+    let schedule = EpochSchedule::without_warmup();
+    schedule.get_first_slot_in_epoch(epoch)..=schedule.get_last_slot_in_epoch(epoch)
+}
+
+/// Every account key referenced by a transaction, loaded from lookup tables
+/// included, in order of first reference.
+#[derive(Default)]
+pub struct AddressCollector {
+    seen: HashSet<Pubkey>,
+    pub addresses: Vec<Pubkey>,
+    pub blocks: usize,
+    pub transactions: usize,
+}
+
+impl AddressCollector {
+    fn add(&mut self, keys: impl Iterator<Item = Pubkey>) {
+        self.transactions += 1;
+        for key in keys {
+            if self.seen.insert(key) {
+                self.addresses.push(key);
+            }
+        }
+    }
+
+    #[cfg(feature = "ledger")]
+    pub fn add_versioned(&mut self, transaction: &VersionedTransactionWithStatusMeta) {
+        self.add(transaction.account_keys().iter().copied());
+    }
+
+    pub fn add_stored(&mut self, transaction: &TransactionWithStatusMeta) {
+        self.add(transaction.account_keys().iter().copied());
+    }
+}
+
+/// Collects the account keys of every transaction in the rooted blocks of
+/// `slots` held by `source`. Skipped slots are simply absent.
+pub fn extract_addresses(source: &LedgerSource, slots: RangeInclusive<Slot>) -> Result<AddressCollector, String> {
+    let mut collector = AddressCollector::default();
+    match source {
+        #[cfg(feature = "ledger")]
+        LedgerSource::Rocksdb(path) => {
+            let blockstore = Blockstore::open(path).map_err(|err| err.to_string())?;
+            let rooted = blockstore.rooted_slot_iterator(*slots.start()).map_err(|err| err.to_string())?;
+            for slot in rooted.take_while(|slot| slots.contains(slot)) {
+                let block = blockstore.get_rooted_block(slot, false).map_err(|err| format!("slot {slot}: {err}"))?;
+                collector.blocks += 1;
+                block.transactions.iter().for_each(|transaction| collector.add_versioned(transaction));
+            }
+        }
+        LedgerSource::Bigtable { credential_path } => {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|err| err.to_string())?;
+            runtime.block_on(async {
+                let storage = LedgerStorage::new(true, None, credential_path.clone()).await.map_err(|err| err.to_string())?;
+                let mut start = *slots.start();
+                loop {
+                    let page = storage.get_confirmed_blocks(start, BIGTABLE_PAGE).await.map_err(|err| err.to_string())?;
+                    for slot in page.iter().copied().take_while(|slot| slots.contains(slot)) {
+                        let block = storage.get_confirmed_block(slot).await.map_err(|err| format!("slot {slot}: {err}"))?;
+                        collector.blocks += 1;
+                        block.transactions.iter().for_each(|transaction| collector.add_stored(transaction));
+                    }
+                    match page.last() {
+                        Some(last) if page.len() == BIGTABLE_PAGE && last < slots.end() => start = last + 1,
+                        _ => return Ok::<_, String>(()),
+                    }
+                }
+            })?;
+        }
+    }
+    Ok(collector)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{message::Message, transaction::Transaction},
+    };
+
+    #[test]
+    fn test_epoch_slots() {
+        assert_eq!(epoch_slots(0), 0..=431_999);
+        assert_eq!(epoch_slots(600), 259_200_000..=259_631_999);
+    }
+
+    #[test]
+    fn test_address_collector() {
+        let (payer, program, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let transaction = |keys: Vec<Pubkey>| {
+            let message = Message { account_keys: keys, ..Message::default() };
+            TransactionWithStatusMeta::MissingMetadata(Transaction::new_unsigned(message))
+        };
+        let mut collector = AddressCollector::default();
+        collector.add_stored(&transaction(vec![payer, program]));
+        collector.add_stored(&transaction(vec![other, payer]));
+        assert_eq!(collector.addresses, vec![payer, program, other]);
+        assert_eq!(collector.transactions, 2);
+    }
+}
@@ -0,0 +1,261 @@
+//! Assignment of addresses to buckets by keyed hashing, and analysis of how
+//! evenly a hasher spreads a set of addresses.
+//!
+//! [`Bucketer`] assigns single addresses the way the analysis does by
+//! default. [`address_to_bucket_with_epoch_hasher`] and
+//! [`address_to_bucket_with_reduction`] take any [`Hasher`] for other
+//! schemes, and [`analysis::analyze_buckets`] summarizes the resulting bucket
+//! counts. [`builder::AnalysisBuilder`] runs whole multi-epoch analyses, and
+//! [`Bucketer::explain`] shows the working behind a single assignment.
+//! [`results::Results`] loads the results a run wrote for post-processing.
+
+use {
+    hashers::{Blake3Hasher, HasherFactory},
+    rayon::prelude::*,
+    reduction::Reduction,
+    serde::{de, Deserialize, Deserializer},
+    serde_json::Value as JsonValue,
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        hash::{BuildHasher, Hasher},
+        ops::Deref,
+        path::Path,
+        time::{Duration, Instant},
+    },
+};
+
+pub mod analysis;
+pub mod assignment_cache;
+pub mod baseline;
+pub mod builder;
+pub mod explain;
+pub mod hashers;
+pub mod input;
+pub mod records;
+pub mod reduction;
+pub mod results;
+pub mod sink;
+pub mod sweep;
+pub mod tags;
+
+pub use analysis::{analyze_buckets, BucketAnalysis};
+
+/// Bucket count the analysis uses unless told otherwise.
+pub const BUCKETS: usize = 100;
+/// Number of epochs, starting at 0, the analysis covers unless told otherwise.
+pub const EPOCHS: u64 = 1000;
+
+fn de_stringified_pubkey<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+    match JsonValue::deserialize(deserializer)? {
+        JsonValue::String(s) => s.parse().map_err(de::Error::custom),
+        _ => Err(de::Error::custom("wrong type")),
+    }
+}
+
+/// A [`Pubkey`] that deserializes from its base58 string, as input files
+/// list addresses.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Pubkey2(
+    #[serde(deserialize_with = "de_stringified_pubkey")]
+    pub Pubkey
+);
+
+impl Deref for Pubkey2 {
+    type Target = Pubkey;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Assigns addresses to one of `buckets` buckets for one seed, with blake3
+/// keyed by the seed and reduced by multiply-shift unless another
+/// [`Reduction`] is chosen.
+///
+/// ```
+/// use {hash_to_bucket::Bucketer, solana_sdk::pubkey::Pubkey};
+///
+/// let bucketer = Bucketer::new(100, 7);
+/// let address = Pubkey::new_unique();
+/// assert!(bucketer.bucket(&address) < 100);
+/// assert_eq!(bucketer.bucket(&address), Bucketer::new(100, 7).bucket(&address));
+/// ```
+#[derive(Clone)]
+pub struct Bucketer {
+    buckets: usize,
+    seed: u64,
+    reduction: Reduction,
+    hasher: Blake3Hasher,
+}
+
+impl Bucketer {
+    /// Panics if `buckets` is zero.
+    pub fn new(buckets: usize, seed: u64) -> Self {
+        assert!(buckets > 0, "bucket count must be non-zero");
+        Self { buckets, seed, reduction: Reduction::default(), hasher: Blake3Hasher::new_with_seed(seed) }
+    }
+
+    /// Fails if `reduction` doesn't support the bucket count.
+    pub fn with_reduction(mut self, reduction: Reduction) -> Result<Self, String> {
+        reduction.validate(self.buckets)?;
+        self.reduction = reduction;
+        Ok(self)
+    }
+
+    pub fn buckets(&self) -> usize {
+        self.buckets
+    }
+
+    pub fn bucket(&self, address: &Pubkey) -> usize {
+        let mut hasher = self.hasher.clone();
+        hasher.write(address.as_ref());
+        self.reduction.reduce(self.buckets, hasher.finish())
+    }
+
+    /// The assignment of `address` with its working shown.
+    pub fn explain(&self, address: &Pubkey) -> explain::Explanation {
+        explain::Explanation::new(address, self.seed, self.buckets, self.reduction)
+    }
+}
+
+#[allow(dead_code)]
+fn address_to_bucket(buckets: usize, epoch: u64, address: &Pubkey2) -> usize {
+    let state = ahash::random_state::RandomState::with_seeds(epoch, epoch, epoch, epoch);
+    let hasher = state.build_hasher();
+    address_to_bucket_with_epoch_hasher(buckets, hasher, address)
+}
+
+/// The bucket `hasher`, already keyed for the epoch, assigns `address` under
+/// multiply-shift reduction.
+pub fn address_to_bucket_with_epoch_hasher<H: Hasher>(buckets: usize, hasher: H, address: &Pubkey2) -> usize {
+    address_to_bucket_with_reduction(buckets, Reduction::Multiply, hasher, address)
+}
+
+/// The bucket `hasher` assigns `address` under `reduction`. Panics if
+/// `buckets` is zero.
+pub fn address_to_bucket_with_reduction<H: Hasher>(buckets: usize, reduction: Reduction, mut hasher: H, address: &Pubkey2) -> usize {
+    assert!(buckets > 0, "bucket count must be non-zero");
+    hasher.write(address.as_ref());
+    reduction.reduce(buckets, hasher.finish())
+}
+
+/// Hands `f` the index of each address in `addresses` and the bucket
+/// `hasher` assigns it, in input order. Every analysis that counts or
+/// exports assignments goes through here.
+pub fn assign_each<H: Hasher + Clone>(hasher: H, buckets: usize, reduction: Reduction, addresses: &[Pubkey2], mut f: impl FnMut(usize, usize)) {
+    for (index, address) in addresses.iter().enumerate() {
+        f(index, address_to_bucket_with_reduction(buckets, reduction, hasher.clone(), address));
+    }
+}
+
+/// Adds each address's weight to its bucket in `buckets`, which the caller
+/// zeroes, returning the time spent hashing.
+pub fn do_test<H: Hasher + Clone>(hasher: H, buckets: &mut [usize], reduction: Reduction, addresses: &[Pubkey2], weights: Option<&[usize]>) -> Duration {
+    let num_buckets = buckets.len();
+    let start = std::time::Instant::now();
+    match weights {
+        None => assign_each(hasher, num_buckets, reduction, addresses, |_, bucket| buckets[bucket] += 1),
+        Some(weights) => assign_each(hasher, num_buckets, reduction, addresses, |index, bucket| buckets[bucket] += weights[index]),
+    }
+    std::time::Instant::now().duration_since(start)
+}
+
+/// [`do_test`] split across the current rayon pool, each task counting a
+/// contiguous share of `addresses` with a hasher it builds from `factory`,
+/// which may be a hasher to clone. The shares' counts are summed, so the
+/// result matches a serial run exactly.
+pub fn do_test_parallel<F: HasherFactory>(factory: &F, buckets: &mut [usize], reduction: Reduction, addresses: &[Pubkey2], weights: Option<&[usize]>) -> Duration {
+    let num_buckets = buckets.len();
+    let share = addresses.len().div_ceil(rayon::current_num_threads()).max(1);
+    let start = Instant::now();
+    let counts = addresses
+        .par_chunks(share)
+        .enumerate()
+        .map(|(i, addresses)| {
+            let weights = weights.map(|weights| &weights[i * share..i * share + addresses.len()]);
+            let mut counts = vec![0; num_buckets];
+            do_test(factory.build(), &mut counts, reduction, addresses, weights);
+            counts
+        })
+        .collect::<Vec<_>>();
+    for share in counts {
+        for (bucket, count) in buckets.iter_mut().zip(share) {
+            *bucket += count;
+        }
+    }
+    start.elapsed()
+}
+
+/// Reads an input file in the [`input::InputFormat`] its extension names,
+/// by default a JSON array of addresses, each optionally tagged. Panics if
+/// the file can't be read or parsed.
+pub fn load_addresses(path: &Path) -> Vec<Pubkey2> {
+    load_tagged_addresses(path).0
+}
+
+/// [`load_addresses`], also returning each tagged address's tag.
+pub fn load_tagged_addresses(path: &Path) -> (Vec<Pubkey2>, HashMap<Pubkey, String>) {
+    input::load_tagged(path, input::InputFormat::detect(path), &input::CsvColumn::default()).unwrap_or_else(|err| panic!("{err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        hashers::{RegistryVisitor, SeedableHasher, SeededFactory},
+    };
+
+    #[test]
+    fn test_do_test_parallel_matches_serial() {
+        struct Check {
+            addresses: Vec<Pubkey2>,
+            weights: Vec<usize>,
+        }
+
+        impl RegistryVisitor for Check {
+            fn register<H: SeedableHasher>(&mut self, name: &'static str) {
+                for weights in [None, Some(self.weights.as_slice())] {
+                    let mut serial = vec![0; 10];
+                    do_test(H::with_seed(7), &mut serial, Reduction::Multiply, &self.addresses, weights);
+                    for threads in [1, 3, 8] {
+                        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+                        let mut parallel = vec![0; 10];
+                        pool.install(|| do_test_parallel(&SeededFactory::<H>::new(7), &mut parallel, Reduction::Multiply, &self.addresses, weights));
+                        assert_eq!(parallel, serial, "{name} on {threads} threads");
+                        let mut parallel = vec![0; 10];
+                        pool.install(|| do_test_parallel(&H::with_seed(7), &mut parallel, Reduction::Multiply, &self.addresses, weights));
+                        assert_eq!(parallel, serial, "{name} cloned on {threads} threads");
+                    }
+                }
+            }
+        }
+
+        let addresses = (0..1000).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let weights = (0..addresses.len()).map(|i| i % 5).collect();
+        hashers::for_each_registered(&mut Check { addresses, weights });
+    }
+
+    #[test]
+    fn test_address_to_bucket_single_bucket() {
+        let address = Pubkey2(Pubkey::new_unique());
+        for epoch in 0..16 {
+            assert_eq!(address_to_bucket(1, epoch, &address), 0);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "bucket count must be non-zero")]
+    fn test_address_to_bucket_no_buckets() {
+        address_to_bucket(0, 0, &Pubkey2(Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_bucketer_matches_analysis_assignment() {
+        let address = Pubkey2(Pubkey::new_unique());
+        let expected = address_to_bucket_with_epoch_hasher(10, Blake3Hasher::new_with_seed(3), &address);
+        assert_eq!(Bucketer::new(10, 3).bucket(&address), expected);
+        let masked = Bucketer::new(16, 3).with_reduction(Reduction::Mask).unwrap();
+        assert_eq!(masked.bucket(&address), address_to_bucket_with_reduction(16, Reduction::Mask, Blake3Hasher::new_with_seed(3), &address));
+        assert!(Bucketer::new(10, 3).with_reduction(Reduction::Mask).is_err());
+    }
+}
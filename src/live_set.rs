@@ -0,0 +1,83 @@
+use {
+    crate::Pubkey2,
+    solana_sdk::pubkey::Pubkey,
+    std::collections::HashMap,
+};
+
+/// The account set a stream of account updates currently describes. An
+/// update with lamports adds its account; one draining it to zero lamports
+/// closes it. Addresses are kept dense so the analysis reads them like a
+/// loaded input file.
+#[derive(Default)]
+pub struct LiveAccountSet {
+    addresses: Vec<Pubkey2>,
+    index: HashMap<Pubkey, usize>,
+    slot: u64,
+}
+
+impl LiveAccountSet {
+    pub fn new(slot: u64, addresses: impl IntoIterator<Item = Pubkey>) -> Self {
+        let mut set = Self { slot, ..Self::default() };
+        for address in addresses {
+            set.update(slot, address, 1);
+        }
+        set
+    }
+
+    /// Applies one update, returning whether the set changed.
+    pub fn update(&mut self, slot: u64, address: Pubkey, lamports: u64) -> bool {
+        self.slot = self.slot.max(slot);
+        if lamports > 0 {
+            if self.index.contains_key(&address) {
+                return false;
+            }
+            self.index.insert(address, self.addresses.len());
+            self.addresses.push(Pubkey2(address));
+            true
+        } else {
+            let Some(i) = self.index.remove(&address) else {
+                return false;
+            };
+            self.addresses.swap_remove(i);
+            if let Some(moved) = self.addresses.get(i) {
+                self.index.insert(**moved, i);
+            }
+            true
+        }
+    }
+
+    pub fn addresses(&self) -> &[Pubkey2] {
+        &self.addresses
+    }
+
+    /// Latest slot any update came from.
+    pub fn slot(&self) -> u64 {
+        self.slot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_live_account_set() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut set = LiveAccountSet::new(10, [a, b]);
+        assert!(!set.update(11, a, 5));
+        assert!(set.update(12, c, 5));
+        assert!(set.update(13, a, 0));
+        assert!(!set.update(14, a, 0));
+        assert_eq!(set.slot(), 14);
+        let mut live = set.addresses().iter().map(|address| **address).collect::<Vec<_>>();
+        live.sort();
+        let mut expected = vec![b, c];
+        expected.sort();
+        assert_eq!(live, expected);
+        assert!(set.update(15, c, 0));
+        assert!(set.update(15, b, 0));
+        assert!(set.addresses().is_empty());
+        assert!(set.update(16, a, 1));
+        assert_eq!(set.addresses().len(), 1);
+    }
+}
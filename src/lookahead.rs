@@ -0,0 +1,86 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    std::collections::HashMap,
+};
+
+/// Column names for `epochs` epochs from `start`: `address,epoch_<n>,...`.
+pub fn header(start: u64, epochs: u64) -> String {
+    let mut header = "address".to_string();
+    for epoch in start..start + epochs {
+        header.push_str(&format!(",epoch_{epoch}"));
+    }
+    header
+}
+
+/// Collects every visited hasher's bucket for each address, one epoch per
+/// visit, into a table with a row per address and a column per epoch.
+pub struct LookaheadVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    names: Vec<&'static str>,
+    columns: HashMap<&'static str, Vec<Vec<u32>>>,
+}
+
+impl<'a> LookaheadVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2]) -> Self {
+        Self { buckets, reduction, addresses, names: Vec::new(), columns: HashMap::new() }
+    }
+
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+
+    /// `name`'s table rows, in input order, matching [`header`].
+    pub fn rows<'s>(&'s self, name: &str) -> impl Iterator<Item = String> + 's {
+        let columns = &self.columns[name];
+        self.addresses.iter().enumerate().map(move |(i, address)| {
+            let mut row = address.to_string();
+            for column in columns {
+                row.push_str(&format!(",{}", column[i]));
+            }
+            row
+        })
+    }
+}
+
+impl HasherVisitor for LookaheadVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let column = self.addresses
+            .iter()
+            .map(|address| address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address) as u32)
+            .collect();
+        self.columns
+            .entry(name)
+            .or_insert_with(|| {
+                self.names.push(name);
+                Vec::new()
+            })
+            .push(column);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn test_lookahead_table() {
+        assert_eq!(header(7, 2), "address,epoch_7,epoch_8");
+        let addresses = (0..3).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = LookaheadVisitor::new(10, Reduction::Multiply, &addresses);
+        for epoch in 7..9 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+        }
+        assert_eq!(visitor.names(), ["blake3"]);
+        let rows = visitor.rows("blake3").collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        let bucket = |epoch| address_to_bucket_with_reduction(10, Reduction::Multiply, Blake3Hasher::new_with_seed(epoch), &addresses[1]);
+        assert_eq!(rows[1], format!("{},{},{}", *addresses[1], bucket(7), bucket(8)));
+    }
+}
@@ -1,167 +1,1944 @@
 use {
-    serde::{de, Deserialize, Deserializer},
-    serde_json::Value as JsonValue,
+    analysis::{analyze_buckets_streaming, analyze_occupancy, AnalysisFormat, BucketAnalysis, ModeTieBreak, OccupancyAnalysis},
+    clap::{builder::RangedU64ValueParser, CommandFactory, Parser, Subcommand},
+    hash_to_bucket::{
+        address_to_bucket_with_epoch_hasher, address_to_bucket_with_reduction, analysis, assign_each, baseline, do_test, do_test_parallel, hashers,
+        input, load_addresses, records, reduction, results, sink, sweep, tags, Bucketer, Pubkey2, BUCKETS, EPOCHS,
+    },
+    hashers::{AnalysisHasher, Blake3Hasher, HasherVisitor, SIPHASH_VARIANTS},
+    pipeline::BufferPool,
+    reduction::Reduction,
+    run_dir::{Manifest, ResultsOutput, RunDir},
+    sink::OutputSink,
     solana_sdk::pubkey::Pubkey,
     std::{
-        collections::HashMap,
-        hash::{BuildHasher, Hasher},
-        ops::Deref,
+        borrow::Cow,
+        collections::{HashMap, HashSet},
+        fmt::Write as _,
+        hash::Hasher,
+        io::Write,
+        num::{NonZeroU64, NonZeroUsize},
+        path::PathBuf,
+        time::Duration,
     },
 };
 
-const BUCKETS: usize = 100;
-const EPOCHS: u64 = 1000;
+mod account_cache;
+mod aggregate;
+mod ahash_conformance;
+#[cfg(feature = "memory-report")]
+mod alloc_counter;
+mod assign;
+mod assignment_map;
+mod assignments;
+mod audit;
+mod autocorrelation;
+mod bench;
+mod block_schedule;
+mod check_pair;
+mod churn;
+mod compare;
+mod elasticity;
+mod epoch_pairs;
+mod cu_model;
+mod filter;
+#[cfg(feature = "memory-report")]
+mod footprint;
+mod generate;
+mod grinding;
+#[cfg(feature = "geyser")]
+mod geyser;
+mod histogram;
+mod hll;
+mod latency;
+#[cfg(feature = "bigtable")]
+mod ledger;
+mod lookahead;
+#[cfg(feature = "geyser")]
+mod live_set;
+mod pipeline;
+mod reduction_comparison;
+mod reference;
+mod replay;
+mod rewards;
+mod rng;
+mod rpc;
+mod run_dir;
+mod scaling;
+mod set_ops;
+mod stability;
+mod stake;
+mod sybil;
+#[cfg(test)]
+mod test_hashers;
+mod top_buckets;
+mod verify;
+mod worst_case;
+
+
+#[cfg(feature = "memory-report")]
+#[global_allocator]
+static ALLOCATOR: alloc_counter::CountingAllocator = alloc_counter::CountingAllocator;
+
+#[derive(Debug, Parser)]
+// Options that need the whole address set in memory, which --stream-chunk avoids.
+#[command(group(clap::ArgGroup::new("in_memory").multiple(true).args([
+    "rpc_url",
+    "reference_cmd",
+    "verify_order",
+    "audit",
+    "latency_sample",
+    "weight_by_count",
+    "cu_model",
+    "block_schedule",
+    "exclude",
+    "stake_meta",
+    "reward_amounts",
+    "rewards_from_stake",
+    "sample_assignments",
+    "top_buckets",
+    "epoch_pairs",
+    "group_by_tag",
+    "stability",
+    "scaling",
+    "worst_case",
+    "grinding",
+    "compare_reductions",
+    "sybil",
+    "churn",
+    "export_map",
+    "dump_assignments",
+    "lookahead",
+    "autocorrelation",
+])))]
+#[command(after_help = "Examples:\n  hash-to-bucket --input accounts.json --epochs 100\n  hash-to-bucket --input accounts.csv --csv-column pubkey --compare --vs-blake3\n  hash-to-bucket --input accounts.bin --stream-chunk 1000000 --hasher xxh3 --hasher blake3\n  hash-to-bucket --hasher siphash24 --hasher-opt siphash.rounds=1-2 --hasher-opt siphash.rounds=4-8 --vs-blake3\n  hash-to-bucket --run-name nightly --sink sqlite=results.db --aggregate\n  hash-to-bucket completions bash > /etc/bash_completion.d/hash-to-bucket")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Number of buckets addresses are assigned to
+    #[arg(long, global = true, default_value_t = BUCKETS, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    buckets: usize,
+
+    /// Number of epochs, starting at 0, to analyze
+    #[arg(long, default_value_t = EPOCHS, value_parser = RangedU64ValueParser::<u64>::new().range(1..))]
+    epochs: u64,
+
+    /// Address file to analyze: a JSON array of base58 addresses, each optionally tagged, or any other --input-format
+    #[arg(long, global = true, default_value = "./addresses.json")]
+    input: PathBuf,
+
+    /// Layout of --input; by default named by its extension, else json
+    #[arg(long, global = true, value_enum)]
+    input_format: Option<input::InputFormat>,
+
+    /// Column of a csv --input holding addresses, by header name or zero-based index
+    #[arg(long, global = true, default_value = "0")]
+    csv_column: input::CsvColumn,
+
+    /// Stream the input, a JSON array or JSON Lines, N addresses at a time, hashing each chunk for every epoch; memory grows with epochs, hashers and buckets rather than addresses
+    #[arg(long, value_name = "N", conflicts_with = "in_memory")]
+    stream_chunk: Option<NonZeroUsize>,
+
+    /// Registered hashers to run in place of blake3; repeatable. The "Also run" flags add to these
+    #[arg(long = "hasher", value_name = "HASHER", global = true, value_parser = hashers::name_parser(hashers::registered_names()))]
+    hashers: Vec<String>,
+
+    /// Also run a registered hasher with one tunable changed: siphash.rounds=C-D, highwayhash.key=repeat|zero-padded|blake3 or xxh3.secret=seeded|blake3, named like siphash-rounds-C-D; repeatable
+    #[arg(long = "hasher-opt", value_name = "HASHER.KEY=VALUE")]
+    hasher_opts: Vec<hashers::HasherOpt>,
+
+    /// How 64-bit hashes are reduced to a bucket index, directly or by consistent hashing
+    #[arg(long, visible_alias = "strategy", global = true, value_enum, default_value_t)]
+    reduction: Reduction,
+
+    /// Seed for every randomized feature (sampling, shuffling), which otherwise repeat identically
+    #[arg(long, global = true, default_value_t = 0)]
+    rng_seed: u64,
+
+    /// Mix this nonce into every epoch's seed, so the run tests different keys than epochs 0..1000 alone give
+    #[arg(long, conflicts_with = "reference_cmd")]
+    nonce: Option<u64>,
+
+    /// Mix a random nonce, recorded in the run manifest, into every epoch's seed
+    #[arg(long, conflicts_with_all = ["nonce", "reference_cmd"])]
+    random_nonce: bool,
+
+    /// Write results into a run directory named after this run instead of stdout
+    #[arg(long)]
+    run_name: Option<String>,
+
+    /// Parent directory for run directories
+    #[arg(long, default_value = "runs", requires = "run_name")]
+    output_dir: PathBuf,
+
+    /// Write per-epoch results and reports to this file instead of stdout
+    #[arg(long, value_name = "PATH", conflicts_with = "run_name")]
+    stats_out: Option<PathBuf>,
+
+    /// Write the per-hasher summary of timings and spread to this file instead of stderr
+    #[arg(long, value_name = "PATH")]
+    timings_out: Option<PathBuf>,
+
+    /// Report no progress and leave the summary off stderr; warnings and errors are still printed
+    #[arg(long)]
+    quiet: bool,
+
+    /// Before analyzing, check bucket assignments against a reference implementation run via `sh -c`
+    #[arg(long)]
+    reference_cmd: Option<String>,
+
+    /// Number of addresses to check against the reference implementation
+    #[arg(long, default_value_t = 1000, requires = "reference_cmd")]
+    reference_sample: usize,
+
+    /// Number of epochs, starting at 0, to check against the reference implementation
+    #[arg(long, default_value_t = 4, requires = "reference_cmd")]
+    reference_epochs: u64,
+
+    /// Also report how each epoch's assignments correlate with the previous epoch's
+    #[arg(long)]
+    autocorrelation: bool,
+
+    /// Also run SipHash-1-3 and SipHash-2-4 under each of the key derivations
+    #[arg(long, global = true)]
+    siphash: bool,
+
+    /// Also run x64 128-bit Murmur3, reducing the low 64 bits of its output
+    #[arg(long, global = true)]
+    murmur3_128: bool,
+
+    /// Also run x86 32-bit Murmur3
+    #[arg(long, global = true)]
+    murmur3_32: bool,
+
+    /// Also run ahash, whose output isn't stable across ahash versions or platforms
+    #[arg(long, global = true)]
+    ahash: bool,
+
+    /// Run every hasher, writing each per-epoch row keyed by hasher name
+    #[arg(long)]
+    compare: bool,
+
+    /// Run only hashers whose output is specified across platforms and library versions, dropping e.g. ahash
+    #[arg(long, global = true)]
+    portable_only: bool,
+
+    /// Leave hashers with non-portable output, such as ahash, out of the --vs-blake3 comparison and summary
+    #[arg(long)]
+    portable_candidates: bool,
+
+    /// Threads hashing epochs
+    #[arg(long, default_value = "1", conflicts_with = "threads")]
+    hash_threads: NonZeroUsize,
+
+    /// Threads hashing epochs and each epoch's addresses, in one rayon pool; output matches a single-threaded run
+    #[arg(long)]
+    threads: Option<NonZeroUsize>,
+
+    /// Threads analyzing and formatting hashed epochs
+    #[arg(long, default_value = "1")]
+    io_threads: NonZeroUsize,
+
+    /// Before analyzing, check the first N epochs give identical bucket counts over shuffled input
+    #[arg(long, value_name = "EPOCHS")]
+    verify_order: Option<u64>,
+
+    /// Also report each hasher's state size and allocations per call
+    #[cfg(feature = "memory-report")]
+    #[arg(long, group = "in_memory")]
+    memory_report: bool,
+
+    /// Also report every epoch's hasher keys, and every intermediate value for N sampled addresses
+    #[arg(long, value_name = "N")]
+    audit: Option<usize>,
+
+    /// Number of epochs, starting at 0, for which sampled addresses are audited
+    #[arg(long, default_value_t = 1, requires = "audit")]
+    audit_epochs: u64,
+
+    /// Also report per-assignment latency percentiles, timing every Nth address in every epoch
+    #[arg(long, value_name = "N")]
+    latency_sample: Option<NonZeroUsize>,
+
+    /// Hash each distinct address once, counting it as many times as it appears in the input
+    #[arg(long)]
+    weight_by_count: bool,
+
+    /// Which bucket count to report as the mode when several occur equally often
+    #[arg(long, value_enum, default_value_t)]
+    mode_tie_break: ModeTieBreak,
+
+    /// Leave the mode, mode_count, mode_ties and mode_fraction columns out of per-epoch results
+    #[arg(long)]
+    no_mode_columns: bool,
+
+    /// Write only these per-epoch result columns, in this order, e.g. min,max,spread,chi2,p99; chi2 names chi_squared
+    #[arg(long, value_name = "COLUMN", value_delimiter = ',', conflicts_with = "no_mode_columns")]
+    columns: Option<Vec<String>>,
+
+    /// Write fractional per-epoch results with this many decimal places instead of in full
+    #[arg(long, value_name = "DIGITS")]
+    precision: Option<usize>,
+
+    /// Flag epochs whose chi-squared test rejects uniform bucket counts at this significance level
+    #[arg(long, value_name = "ALPHA", default_value_t = 0.001, value_parser = parse_significance)]
+    significance: f64,
+
+    /// Also draw an ASCII histogram of bucket loads at every epoch, or only at EPOCH, and a sparkline of each hasher's spread over the epochs, on stderr or into the run directory's plots
+    #[arg(long, value_name = "EPOCH", num_args = 0..=1)]
+    histogram: Option<Option<u64>>,
+
+    /// Bucket count percentiles to report after the fixed per-epoch columns, nearest-rank
+    #[arg(long, value_name = "P", value_delimiter = ',', default_values_t = analysis::DEFAULT_PERCENTILES, value_parser = parse_percentile)]
+    percentiles: Vec<f64>,
+
+    /// Write per-epoch results as wide rows, or as long rows of epoch, hasher, metric and value
+    #[arg(long, value_enum, default_value_t)]
+    format: analysis::Format,
+
+    /// Write per-epoch results and the final summary as CSV, or as JSON records carrying the hasher and run configuration, every metric at full precision; other reports stay CSV
+    #[arg(long, value_enum, default_value_t, conflicts_with_all = ["format", "columns", "precision", "no_mode_columns"])]
+    output_format: records::OutputFormat,
+
+    /// Flush results and sinks every N epochs, syncing files to disk, instead of only at the end of the run
+    #[arg(long, value_name = "N")]
+    flush_every: Option<NonZeroU64>,
+
+    /// Also stream per-epoch results, of the analysis or a sweep, to a sink: stdout, csv=PATH, json=PATH, sqlite=PATH or prometheus=PATH; repeatable
+    #[arg(long = "sink", value_name = "SINK", global = true)]
+    sinks: Vec<sink::SinkSpec>,
+
+    /// Also report estimated compute units per assignment for hashers with an on-chain syscall
+    #[arg(long)]
+    cu_model: bool,
+
+    /// Also report per-block work when partition k is credited k blocks after the epoch boundary
+    #[arg(long)]
+    block_schedule: bool,
+
+    /// Count the epochs in which a block credits more than this many accounts
+    #[arg(long, value_name = "ACCOUNTS", requires = "block_schedule")]
+    block_account_limit: Option<usize>,
+
+    /// Address file of accounts to drop from the analyzed set, e.g. those rewarded through another path
+    #[arg(long, value_name = "PATH")]
+    exclude: Option<PathBuf>,
+
+    /// JSON array of `{pubkey, activation_epoch, deactivation_epoch}` describing the input's stake accounts
+    #[arg(long, value_name = "PATH")]
+    stake_meta: Option<PathBuf>,
+
+    /// Analyze only stake actively delegated during this epoch, per --stake-meta
+    #[arg(long, value_name = "EPOCH", requires = "stake_meta")]
+    active_at: Option<u64>,
+
+    /// JSON array of `{pubkey, lamports}` rewards; also report the balance of per-bucket reward totals
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["weight_by_count", "rewards_from_stake"])]
+    reward_amounts: Option<PathBuf>,
+
+    /// Split this many lamports of rewards in proportion to --stake-meta stake and report as --reward-amounts does
+    #[arg(long, value_name = "LAMPORTS", requires = "stake_meta", conflicts_with = "weight_by_count")]
+    rewards_from_stake: Option<u64>,
+
+    /// Also report every hasher's mean quality metrics and hashing time as ratios of blake3's
+    #[arg(long)]
+    vs_blake3: bool,
+
+    /// Also write each hasher's bucket for K addresses sampled afresh every epoch, for spot checks
+    #[arg(long, value_name = "K")]
+    sample_assignments: Option<usize>,
+
+    /// Also export example member addresses of each hasher's N most loaded buckets every epoch
+    #[arg(long, value_name = "N")]
+    top_buckets: Option<usize>,
+
+    /// Member addresses sampled per exported bucket, in memory bounded by K per bucket
+    #[arg(long, value_name = "K", default_value_t = 10, requires = "top_buckets")]
+    bucket_examples: usize,
+
+    /// Also estimate each bucket's distinct addresses every epoch with a HyperLogLog sketch of 2^P bytes; with --stream-chunk, in memory independent of the input but holding every epoch's sketches at once
+    #[arg(long, value_name = "P", value_parser = RangedU64ValueParser::<u32>::new().range(hll::PRECISIONS))]
+    approximate_distinct: Option<u32>,
+
+    /// Also report how often every pair among the first N epochs assigns an address the same bucket
+    #[arg(long, value_name = "N")]
+    epoch_pairs: Option<u64>,
+
+    /// Also report per-epoch results separately for each input tag
+    #[arg(long)]
+    group_by_tag: bool,
+
+    /// Also report, per hasher and input tag, the mean number of distinct buckets an address visits across epochs
+    #[arg(long)]
+    stability: bool,
+
+    /// Also report each hasher's bucket count deviation at growing prefixes of the input (1k, 10k, ...), averaged over the first N epochs
+    #[arg(long, value_name = "N")]
+    scaling: Option<u64>,
+
+    /// Also report each hasher's worst observed bucket overload and epoch-to-epoch churn over all epochs
+    #[arg(long)]
+    worst_case: bool,
+
+    /// Also report each hasher's grinding resistance: the expected attempts to place a key in its most loaded bucket, checked by grinding N random keys into it per epoch
+    #[arg(long, value_name = "N")]
+    grinding: Option<u64>,
+
+    /// Also report each hasher across all epochs: worst spread, mean and std_dev of std_dev, worst chi-squared and hashing time
+    #[arg(long)]
+    aggregate: bool,
+
+    /// Also report each hasher's distribution under every reduction valid for the bucket count, reducing the same hashes each time
+    #[arg(long)]
+    compare_reductions: bool,
+
+    /// Also simulate one entity adding K accounts, as random keypairs and as keypairs ground into the most loaded bucket, reporting each hasher's resulting worst-bucket overload
+    #[arg(long, value_name = "K")]
+    sybil: Option<usize>,
+
+    /// Also report, for each pair of consecutive epochs, the fraction of addresses changing bucket and the spread of per-bucket inflow and outflow
+    #[arg(long)]
+    churn: bool,
+
+    /// Also export every hasher's bucket for each address, in input order, at this epoch
+    #[arg(long, value_name = "EPOCH")]
+    export_map: Option<u64>,
+
+    /// Directory assignment maps are exported to
+    #[arg(long, default_value = ".", requires = "export_map")]
+    map_dir: PathBuf,
+
+    /// Bucket index width in exported assignment maps
+    #[arg(long, value_enum, default_value_t, requires = "export_map")]
+    map_index: assignment_map::IndexEncoding,
+
+    /// Also write every hasher's `address,bucket` pairs, in input order, at this epoch, or at every epoch with "all"
+    #[arg(long, value_name = "EPOCH")]
+    dump_assignments: Option<assignment_map::DumpEpochs>,
+
+    /// Write --dump-assignments grouped by bucket, ordering each bucket's addresses by this hasher's hash of them, keyed by a seed derived from the epoch's, with a position column. Only portable hashers, so the order is the same on every platform
+    #[arg(long, value_name = "HASHER", requires = "dump_assignments", value_parser = hashers::name_parser(hashers::registered_names().into_iter().filter(|name| hashers::is_portable(name)).collect()))]
+    intra_bucket_order: Option<String>,
+
+    /// File --dump-assignments writes; with several hashers or epochs, each gets its own, named after this one
+    #[arg(long, default_value = "assignments.csv", requires = "dump_assignments")]
+    out: PathBuf,
+
+    /// Also export a table of every hasher's bucket for each address over the E epochs from --lookahead-start
+    #[arg(long, value_name = "E")]
+    lookahead: Option<u64>,
+
+    /// First epoch of the --lookahead table
+    #[arg(long, value_name = "EPOCH", default_value_t = 0, requires = "lookahead")]
+    lookahead_start: u64,
+
+    /// Analyze the accounts a program owns, fetched from this RPC endpoint, instead of the input file
+    #[arg(long, value_name = "URL", requires = "rpc_program")]
+    rpc_url: Option<String>,
+
+    /// Program whose accounts are fetched from --rpc-url
+    #[arg(long, value_name = "PUBKEY", requires = "rpc_url")]
+    rpc_program: Option<Pubkey>,
+
+    /// Fetch only program accounts with exactly this many bytes of data
+    #[arg(long, value_name = "BYTES", requires = "rpc_url")]
+    rpc_data_size: Option<u64>,
+
+    /// Fetch program accounts in 256 pages, split on the value of the data byte at this offset
+    #[arg(long, value_name = "OFFSET", requires = "rpc_url")]
+    rpc_page_offset: Option<usize>,
+
+    /// Retries for RPC requests failing in transport or with HTTP 429 or 5xx
+    #[arg(long, default_value_t = 5, requires = "rpc_url")]
+    rpc_retries: u32,
+
+    /// Delay before the first RPC retry in milliseconds, doubling for each one after
+    #[arg(long, default_value_t = 500, requires = "rpc_url")]
+    rpc_backoff_ms: u64,
+
+    /// Maximum RPC requests per second
+    #[arg(long, value_name = "REQUESTS", requires = "rpc_url", value_parser = parse_rpc_rate)]
+    rpc_rate: Option<f64>,
 
-#[derive(Clone)]
-struct Blake3Hasher(blake3::Hasher);
+    /// Reuse account sets fetched from --rpc-url within this many seconds instead of fetching again
+    #[arg(long, value_name = "SECONDS", requires = "rpc_url")]
+    max_age: Option<u64>,
 
-impl Hasher for Blake3Hasher {
-    fn finish(&self) -> u64 {
-        let hash = self.0.finalize();
-        u64::from_le_bytes(hash.as_bytes()[..8].try_into().unwrap())
+    /// Directory fetched account sets are cached in
+    #[arg(long, default_value = "rpc-cache", requires = "max_age")]
+    rpc_cache_dir: PathBuf,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Report realized blake3 partition churn between account sets of consecutive epochs
+    #[command(after_help = "Examples:\n  hash-to-bucket snapshot-churn accounts-500.json accounts-501.json --epoch 500")]
+    SnapshotChurn {
+        /// Addresses present at the snapshot epoch
+        old: PathBuf,
+
+        /// Addresses present at the epoch after
+        new: PathBuf,
+
+        /// Epoch of the `old` snapshot
+        #[arg(long)]
+        epoch: u64,
+    },
+    /// Write a shell completion script to stdout
+    #[command(after_help = "Examples:\n  hash-to-bucket completions bash > /etc/bash_completion.d/hash-to-bucket\n  hash-to-bucket completions zsh > ~/.zfunc/_hash-to-bucket\n  hash-to-bucket completions fish > ~/.config/fish/completions/hash-to-bucket.fish")]
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Fetch an address set from an RPC endpoint, writing it as a JSON address file
+    #[command(after_help = "Examples:\n  hash-to-bucket fetch vote --url https://api.mainnet-beta.solana.com --out vote.json\n  hash-to-bucket fetch program --url $RPC --program Stake11111111111111111111111111111111111111 --page-offset 44")]
+    Fetch {
+        /// Accounts to fetch
+        #[arg(value_enum)]
+        set: rpc::AccountSet,
+
+        /// RPC endpoint to fetch from
+        #[arg(long, value_name = "URL")]
+        url: String,
+
+        /// Owner of the accounts, for the program set
+        #[arg(long, value_name = "PUBKEY", required_if_eq("set", "program"))]
+        program: Option<Pubkey>,
+
+        /// Fetch only program accounts with exactly this many bytes of data
+        #[arg(long, value_name = "BYTES")]
+        data_size: Option<u64>,
+
+        /// Fetch program or stake accounts in 256 pages by the data byte at this offset
+        #[arg(long, value_name = "OFFSET")]
+        page_offset: Option<usize>,
+
+        /// Write the addresses here instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Write a synthetic address set, uniform or patterned to provoke clustering, as a JSON address file
+    #[command(after_help = "Examples:\n  hash-to-bucket generate random --count 1000000 --out random.json\n  hash-to-bucket generate vanity --count 10000 --prefix So1 --out vanity.json\n  hash-to-bucket generate pda --count 100000 --program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA --out pdas.json")]
+    Generate {
+        #[arg(value_enum)]
+        pattern: generate::Pattern,
+
+        /// Number of addresses
+        #[arg(long, default_value_t = 100_000)]
+        count: usize,
+
+        /// Base58 prefix of vanity addresses; each additional character multiplies generation time by 58
+        #[arg(long, required_if_eq("pattern", "vanity"), value_parser = generate::parse_prefix)]
+        prefix: Option<String>,
+
+        /// Program deriving the PDAs
+        #[arg(long, value_name = "PUBKEY", required_if_eq("pattern", "pda"))]
+        program: Option<Pubkey>,
+
+        /// Write the addresses here instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Print the bucket one --hasher assigns an address at an epoch, or at each epoch of a range
+    #[command(after_help = "Examples:\n  hash-to-bucket assign Vote111111111111111111111111111111111111111 --epoch 650 --buckets 432000\n  hash-to-bucket assign Vote111111111111111111111111111111111111111 --epoch 650..=660 --hasher xxh3")]
+    Assign {
+        address: Pubkey,
+
+        /// Epoch, or range of epochs as A..B or A..=B
+        #[arg(long)]
+        epoch: assign::Epochs,
+    },
+    /// Read base58 addresses from stdin, one per line, writing `pubkey,bucket` under one --hasher to stdout as each arrives
+    #[command(after_help = "Examples:\n  tail -f new-accounts.txt | hash-to-bucket filter --epoch 500 --buckets 432000\n  hash-to-bucket filter --hasher xxh3 < accounts.txt > assignments.csv")]
+    Filter {
+        /// Epoch whose seed keys the hasher
+        #[arg(long, default_value_t = 0)]
+        epoch: u64,
+    },
+    /// Combine two address files, writing the resulting address file
+    #[command(after_help = "Examples:\n  hash-to-bucket set union vote.json stake.json --out accounts.json")]
+    Set {
+        #[arg(value_enum)]
+        op: set_ops::SetOp,
+
+        left: PathBuf,
+
+        right: PathBuf,
+
+        /// Write the resulting addresses here instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Show the seed, digest and reduction arithmetic behind one address's blake3 bucket, as JSON
+    #[command(after_help = "Examples:\n  hash-to-bucket explain Vote111111111111111111111111111111111111111 --seed 500")]
+    Explain {
+        address: Pubkey,
+
+        /// Seed blake3 is keyed with, the epoch unless the run has a nonce
+        #[arg(long)]
+        seed: u64,
+    },
+    /// Measure hashing throughput per hasher at several thread counts
+    #[command(after_help = "Examples:\n  hash-to-bucket bench --threads 1,4,16 --epochs 3")]
+    Bench {
+        /// Comma-separated thread counts; scaling is relative to the first
+        #[arg(long, value_delimiter = ',', default_value = "1,2,4,8,16")]
+        threads: Vec<NonZeroUsize>,
+
+        /// Number of epochs, starting at 0, to time
+        #[arg(long, default_value_t = 10)]
+        epochs: u64,
+    },
+    /// Time the runtime bucket reduction against the compile-time bucket count fast path
+    #[command(after_help = "Examples:\n  hash-to-bucket bench-reduction --rounds 20")]
+    BenchReduction {
+        /// Timing rounds; the fastest is reported
+        #[arg(long, default_value_t = 10)]
+        rounds: usize,
+    },
+    /// Run every combination in a JSON experiment matrix of hashers, bucket counts, seed strategies and reductions
+    #[command(after_help = "Examples:\n  hash-to-bucket sweep matrix.json --out sweep.csv --threads 8")]
+    Sweep {
+        /// `{"hashers": [..], "buckets": [..], "seeds": ["epoch", "blake3-derived"], "reductions": ["multiply", "mask"], "epochs": N}`
+        config: PathBuf,
+
+        /// Results table; combinations already completed in it, as listed in `<OUT>.done`, are skipped
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Combinations run concurrently
+        #[arg(long, default_value = "1")]
+        threads: NonZeroUsize,
+    },
+    /// Align two results files or run directories by hasher and epoch and report how each metric moved
+    #[command(after_help = "Examples:\n  hash-to-bucket compare-runs runs/before runs/after")]
+    CompareRuns {
+        a: PathBuf,
+
+        b: PathBuf,
+    },
+    /// Time sorting bucket analysis against the single-pass streaming analysis at several bucket counts
+    #[command(after_help = "Examples:\n  hash-to-bucket bench-analysis --sizes 1000,1000000 --load 4")]
+    BenchAnalysis {
+        /// Comma-separated bucket counts
+        #[arg(long, value_delimiter = ',', default_value = "100,10000,1000000")]
+        sizes: Vec<NonZeroUsize>,
+
+        /// Mean number of addresses per bucket in the synthetic bucket counts
+        #[arg(long, default_value_t = 16)]
+        load: usize,
+
+        /// Timing rounds; the fastest is reported
+        #[arg(long, default_value_t = 10)]
+        rounds: usize,
+    },
+    /// Report one --hasher's mean spread and std_dev over a range of bucket counts, for the same seeds
+    #[command(after_help = "Examples:\n  hash-to-bucket elasticity --hasher xxh3 --from 1000 --to 2000 --step 100")]
+    Elasticity {
+        /// Smallest bucket count
+        #[arg(long)]
+        from: NonZeroUsize,
+
+        /// Largest bucket count
+        #[arg(long)]
+        to: usize,
+
+        /// Distance between consecutive bucket counts
+        #[arg(long, default_value = "1")]
+        step: NonZeroUsize,
+
+        /// Number of epochs, starting at 0, seeding the hasher
+        #[arg(long, default_value_t = 10)]
+        epochs: u64,
+    },
+    /// Run two hashers over the same epochs and fail if the candidate's spread, std_dev, max_over_mean or chi-squared is significantly worse than the baseline's beyond a threshold, by a paired t-test over the epochs
+    #[command(after_help = "Examples:\n  hash-to-bucket check-pair blake3 xxh3 --max-quality-regression 2% --epochs 20")]
+    CheckPair {
+        /// Hasher currently in use
+        #[arg(value_parser = hashers::name_parser(hashers::registered_names()))]
+        baseline: String,
+
+        /// Proposed replacement
+        #[arg(value_parser = hashers::name_parser(hashers::registered_names()))]
+        candidate: String,
+
+        /// Largest tolerated regression per metric, as a percentage of the baseline, e.g. 2%
+        #[arg(long, value_name = "PERCENT", default_value = "0%", value_parser = check_pair::parse_percent)]
+        max_quality_regression: f64,
+
+        /// Fail a metric only if a regression past --max-quality-regression is significant at this level
+        #[arg(long, value_name = "ALPHA", default_value_t = 0.05, value_parser = parse_significance)]
+        significance: f64,
+
+        /// Number of epochs, starting at 0, seeding both hashers; at least 2 for the t-test
+        #[arg(long, default_value_t = 10, value_parser = RangedU64ValueParser::<u64>::new().range(2..))]
+        epochs: u64,
+    },
+    /// Record ahash's build configuration and output fingerprint, or check the current build against a recording
+    #[command(group(clap::ArgGroup::new("mode").required(true).args(["record", "check"])))]
+    #[command(after_help = "Examples:\n  hash-to-bucket ahash-conformance --record ahash.json\n  hash-to-bucket ahash-conformance --check ahash.json")]
+    AhashConformance {
+        /// Write the current configuration here
+        #[arg(long, value_name = "PATH")]
+        record: Option<PathBuf>,
+
+        /// Compare the current configuration against this recording, failing if ahash's output changed
+        #[arg(long, value_name = "PATH")]
+        check: Option<PathBuf>,
+    },
+    /// Replay the real seeds of past epochs, their boundary blockhashes, against the current account set
+    #[command(group(clap::ArgGroup::new("seeds").required(true).args(["blockhashes", "seed_rpc_url"])))]
+    #[command(after_help = "Examples:\n  hash-to-bucket replay --seed-rpc-url https://api.mainnet-beta.solana.com --epochs 5\n  hash-to-bucket replay --blockhashes blockhashes.json")]
+    Replay {
+        /// JSON array of `{epoch, blockhash}` giving each epoch's seed
+        #[arg(long, value_name = "PATH")]
+        blockhashes: Option<PathBuf>,
+
+        /// Look up the seeds of the latest epochs from this RPC endpoint
+        #[arg(long, value_name = "URL")]
+        seed_rpc_url: Option<String>,
+
+        /// Number of epochs, up to and including the current one, to look up from --seed-rpc-url
+        #[arg(long, default_value_t = 10, requires = "seed_rpc_url")]
+        epochs: u64,
+    },
+    /// Write the account keys of every transaction in a range of rooted slots, from a RocksDB ledger or the warehouse Bigtable, as an address file
+    #[cfg(feature = "bigtable")]
+    #[command(group(clap::ArgGroup::new("source").required(true)))]
+    #[command(group(clap::ArgGroup::new("slots").required(true).args(["epoch", "start_slot"])))]
+    #[command(after_help = "Examples:\n  hash-to-bucket extract-addresses --ledger ledger --epoch 500 --out accounts.json\n  hash-to-bucket extract-addresses --bigtable --start-slot 216000000 --end-slot 216000999")]
+    ExtractAddresses {
+        /// RocksDB ledger directory
+        #[cfg(feature = "ledger")]
+        #[arg(long, value_name = "DIR", group = "source")]
+        ledger: Option<PathBuf>,
+
+        /// Read from the warehouse Bigtable
+        #[arg(long, group = "source")]
+        bigtable: bool,
+
+        /// Bigtable credential file, instead of GOOGLE_APPLICATION_CREDENTIALS
+        #[arg(long, value_name = "PATH", requires = "bigtable")]
+        bigtable_credentials: Option<String>,
+
+        /// Extract every slot of this epoch
+        #[arg(long)]
+        epoch: Option<u64>,
+
+        /// First slot extracted
+        #[arg(long, requires = "end_slot")]
+        start_slot: Option<u64>,
+
+        /// Last slot extracted
+        #[arg(long, requires = "start_slot")]
+        end_slot: Option<u64>,
+
+        /// Write the addresses here instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Track a program's accounts from a Yellowstone gRPC Geyser stream, analyzing the live set periodically
+    #[cfg(feature = "geyser")]
+    #[command(after_help = "Examples:\n  hash-to-bucket geyser --endpoint $GRPC --program Stake11111111111111111111111111111111111111 --initial-rpc-url $RPC")]
+    Geyser {
+        /// gRPC endpoint to subscribe to
+        #[arg(long, value_name = "URL")]
+        endpoint: String,
+
+        /// Access token sent as `x-token`
+        #[arg(long)]
+        x_token: Option<String>,
+
+        /// Program whose accounts are tracked
+        #[arg(long, value_name = "PUBKEY")]
+        program: Pubkey,
+
+        /// Track only accounts with exactly this many bytes of data
+        #[arg(long, value_name = "BYTES")]
+        data_size: Option<u64>,
+
+        /// Start from the program's accounts as this RPC endpoint reports them, rather than only those updated since subscribing
+        #[arg(long, value_name = "URL")]
+        initial_rpc_url: Option<String>,
+
+        /// Seconds between analyses of the live set
+        #[arg(long, default_value = "10")]
+        interval: NonZeroU64,
+
+        /// Epoch seeding the hashers
+        #[arg(long, default_value_t = 0)]
+        epoch: u64,
+    },
+}
+
+struct NameVisitor(Vec<&'static str>);
+
+impl HasherVisitor for NameVisitor {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], _hasher: H) {
+        self.0.push(name);
     }
+}
+
+fn hasher_names(cli: &Cli) -> Vec<&'static str> {
+    let mut names = NameVisitor(Vec::new());
+    for_each_hasher(cli, 0, &mut names);
+    names.0
+}
 
-    fn write(&mut self, bytes: &[u8]) {
-        self.0.update(bytes);
+fn parse_significance(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(alpha) if alpha > 0.0 && alpha < 1.0 => Ok(alpha),
+        _ => Err(format!("expected a significance level in (0, 1), got {arg:?}")),
     }
 }
 
-impl Blake3Hasher {
-    fn new_with_seed(seed: u64) -> Self {
-        let seed_bytes = seed.to_le_bytes();
-        let mut key = [0u8; 32];
-        for chunk in key.chunks_mut(8) {
-            chunk.copy_from_slice(&seed_bytes);
-        }
-        Self(blake3::Hasher::new_keyed(&key))
+fn parse_rpc_rate(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("expected a positive request rate, got {arg:?}")),
     }
 }
 
-fn de_stringified_pubkey<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
-    match JsonValue::deserialize(deserializer)? {
-        JsonValue::String(s) => s.parse().map_err(de::Error::custom),
-        _ => Err(de::Error::custom("wrong type")),
+fn parse_percentile(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(percentile) if percentile > 0.0 && percentile <= 100.0 => Ok(percentile),
+        _ => Err(format!("expected a percentile in (0, 100], got {arg:?}")),
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct Pubkey2(
-    #[serde(deserialize_with = "de_stringified_pubkey")]
-    Pubkey
-);
+fn format_row(epoch: u64, buckets: &[usize], sparse: bool, tie_break: ModeTieBreak, format: &AnalysisFormat) -> (String, BucketAnalysis, Option<OccupancyAnalysis>) {
+    let analysis = analyze_buckets_streaming(buckets, tie_break, &format.percentiles);
+    let mut row = format!("{epoch},{}", format.display(&analysis));
+    let occupancy = sparse.then(|| analyze_occupancy(buckets));
+    if let Some(occupancy) = &occupancy {
+        write!(row, ",{occupancy}").unwrap();
+    }
+    (row, analysis, occupancy)
+}
+
+// The seed hashers are keyed with for `epoch`, which is the epoch itself
+// unless the run has a nonce.
+fn epoch_seed(cli: &Cli, epoch: u64) -> u64 {
+    cli.nonce.map_or(epoch, |nonce| hashers::nonce_seed(epoch, nonce))
+}
 
-impl Deref for Pubkey2 {
-    type Target = Pubkey;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+/// The one `--hasher` a subcommand measuring a single hasher was given, blake3 by default.
+fn single_hasher(cli: &Cli) -> &str {
+    match cli.hashers.as_slice() {
+        [] => "blake3",
+        [hasher] => hasher,
+        _ => Cli::command().error(clap::error::ErrorKind::TooManyValues, "this subcommand runs a single --hasher").exit(),
     }
 }
 
-#[derive(Debug)]
-struct BucketAnalysis {
-    pub min: usize,
-    pub max: usize,
-    pub spread: usize,
-    pub mean: usize,
-    pub median: usize,
-    pub mode: usize,
-    pub mode_count: usize,
-    pub std_dev: f64,
+// Whether the registered hasher `name` runs: listed with --hasher or its
+// own flag, or blake3 when nothing is listed, or anything under --compare.
+fn selected(cli: &Cli, name: &str) -> bool {
+    let flagged = match name {
+        "murmur3-128" => cli.murmur3_128,
+        "murmur3-32" => cli.murmur3_32,
+        "ahash" => cli.ahash,
+        _ => false,
+    };
+    cli.compare || flagged || cli.hashers.iter().any(|hasher| hasher == name) || (cli.hashers.is_empty() && name == "blake3")
 }
 
-impl std::fmt::Display for BucketAnalysis {
-    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        write!(formatter, "{},{},{},{},{},{},{},{}", self.min, self.max, self.spread, self.mean, self.median, self.mode, self.mode_count, self.std_dev)
+fn for_each_hasher(cli: &Cli, epoch: u64, visitor: &mut impl HasherVisitor) {
+    if cli.portable_only {
+        visit_hashers(cli, epoch, &mut hashers::PortableOnly(visitor));
+    } else {
+        visit_hashers(cli, epoch, visitor);
     }
 }
 
-fn analyze_buckets(buckets: &mut [usize]) -> BucketAnalysis {
-    buckets.sort();
-    let min = buckets[0];
-    let max = buckets[BUCKETS - 1];
-    let spread = max - min;
-    let sum = buckets.iter().sum::<usize>();
-    let mean = sum / BUCKETS;
-    let median = buckets[BUCKETS / 2];
-    let mut freq = HashMap::new();
-    for bucket in buckets.iter() {
-        freq.entry(*bucket)
-            .and_modify(|f| *f += 1)
-            .or_insert(1);
+fn visit_hashers(cli: &Cli, epoch: u64, visitor: &mut impl HasherVisitor) {
+    let seed = epoch_seed(cli, epoch);
+    for name in hashers::registered_names() {
+        if selected(cli, name) {
+            hashers::visit_registered(name, seed, visitor);
+        }
+    }
+    if cli.siphash {
+        for (name13, name24, keying) in SIPHASH_VARIANTS {
+            let (key0, key1) = keying.keys(seed);
+            let key = [key0.to_le_bytes(), key1.to_le_bytes()].concat();
+            visitor.visit(name13, &key, siphasher::sip::SipHasher13::new_with_keys(key0, key1));
+            visitor.visit(name24, &key, siphasher::sip::SipHasher24::new_with_keys(key0, key1));
+        }
+    }
+    for opt in &cli.hasher_opts {
+        opt.visit(seed, visitor);
     }
-    let mut freq = freq.iter().collect::<Vec<_>>();
-    freq.sort_by_key(|(_,v)| *v);
-    let (mode, mode_count) = freq.last().map(|(k, v)| (**k, **v)).unwrap();
-    let std_dev = buckets.iter().map(|count| (*count as f64 - mean as f64).abs()).sum::<f64>() / (BUCKETS as f64);
+}
 
-    BucketAnalysis { min, max, spread, mean, median, mode, mode_count, std_dev }
+struct TestVisitor<'a, 'b> {
+    buckets: usize,
+    pool: &'a BufferPool,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    weights: Option<&'a [usize]>,
+    parallel: bool,
+    emit: &'b mut dyn FnMut(&'static str, Vec<usize>, Duration),
+}
+
+impl HasherVisitor for TestVisitor<'_, '_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let mut buckets = self.pool.take(self.buckets);
+        let time = if self.parallel {
+            do_test_parallel(&hasher, &mut buckets, self.reduction, self.addresses, self.weights)
+        } else {
+            do_test(hasher, &mut buckets, self.reduction, self.addresses, self.weights)
+        };
+        (self.emit)(name, buckets, time);
+    }
 }
 
-#[allow(dead_code)]
-fn address_to_bucket(buckets: usize, epoch: u64, address: &Pubkey2) -> usize {
-    let state = ahash::random_state::RandomState::with_seeds(epoch, epoch, epoch, epoch);
-    let hasher = state.build_hasher();
-    address_to_bucket_with_epoch_hasher(buckets, hasher, address)
+fn run_hashers(cli: &Cli, epoch: u64, addresses: &[Pubkey2], weights: Option<&[usize]>, pool: &BufferPool, emit: &mut dyn FnMut(&'static str, Vec<usize>, Duration)) {
+    for_each_hasher(cli, epoch, &mut TestVisitor { buckets: cli.buckets, pool, reduction: cli.reduction, addresses, weights, parallel: cli.threads.is_some(), emit });
 }
 
-fn address_to_bucket_with_epoch_hasher<H: Hasher>(buckets: usize, mut hasher: H, address: &Pubkey2) -> usize {
-    hasher.write(address.as_ref());
-    let hash = hasher.finish();
-    ((buckets as u128) * (hash as u128) / ((u64::MAX as u128) + 1)) as usize
+// Drops every entry, duplicates included, whose address appears in `excluded`.
+fn exclude(addresses: Vec<Pubkey2>, excluded: &[Pubkey2]) -> Vec<Pubkey2> {
+    let excluded = excluded.iter().map(|address| **address).collect::<HashSet<_>>();
+    addresses.into_iter().filter(|address| !excluded.contains(&**address)).collect()
 }
 
-fn do_test<H: Hasher + Clone>(hasher: H, epoch: u64, addresses: &[Pubkey2]) -> std::time::Duration {
-    let mut buckets = Vec::with_capacity(BUCKETS);
-    buckets.resize(BUCKETS, 0);
-    let start = std::time::Instant::now();
+// Collapses repeated addresses into one entry each, in order of first
+// appearance, with the number of times it appeared as its weight.
+fn fold_duplicates(addresses: Vec<Pubkey2>) -> (Vec<Pubkey2>, Vec<usize>) {
+    let mut index = HashMap::with_capacity(addresses.len());
+    let mut distinct = Vec::with_capacity(addresses.len());
+    let mut weights = Vec::with_capacity(addresses.len());
     for address in addresses {
-        let bucket = address_to_bucket_with_epoch_hasher(BUCKETS, hasher.clone(), address);
-        buckets[bucket] += 1;
+        match index.get(&*address) {
+            Some(i) => weights[*i] += 1,
+            None => {
+                index.insert(*address, distinct.len());
+                distinct.push(address);
+                weights.push(1);
+            }
+        }
     }
-    let time = std::time::Instant::now().duration_since(start);
-    println!("{epoch},{}", analyze_buckets(&mut buckets));
-    time
+    (distinct, weights)
+}
+
+fn blake3_bucket(cli: &Cli, epoch: u64, address: &Pubkey2) -> usize {
+    address_to_bucket_with_reduction(cli.buckets, cli.reduction, Blake3Hasher::new_with_seed(epoch_seed(cli, epoch)), address)
 }
 
 fn main() {
-    let file = std::fs::File::open("./addresses.json").unwrap();
-    let reader = std::io::BufReader::new(file);
-    let addresses: Vec<Pubkey2> = serde_json::from_reader(reader).unwrap();
+    let mut cli = Cli::parse();
+    if cli.random_nonce {
+        let nonce = rand::random();
+        eprintln!("using nonce {nonce}");
+        cli.nonce = Some(nonce);
+    }
+    if let Some(threads) = cli.threads {
+        rayon::ThreadPoolBuilder::new().num_threads(threads.get()).build_global().unwrap();
+    }
+    if let Err(err) = cli.reduction.validate(cli.buckets) {
+        Cli::command().error(clap::error::ErrorKind::ArgumentConflict, err).exit();
+    }
+    if let Err(err) = cli.map_index.validate(cli.buckets) {
+        Cli::command().error(clap::error::ErrorKind::ArgumentConflict, err).exit();
+    }
+    match &cli.command {
+        Some(Command::SnapshotChurn { old, new, epoch }) => {
+            let churn = churn::analyze_snapshot_churn(cli.buckets, *epoch, &load_addresses(old), &load_addresses(new), |epoch, address| blake3_bucket(&cli, epoch, address));
+            println!("{}", churn::SNAPSHOT_HEADER);
+            println!("{churn}");
+        }
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(*shell, &mut Cli::command(), "hash-to-bucket", &mut std::io::stdout());
+        }
+        Some(Command::Fetch { set, url, program, data_size, page_offset, out }) => {
+            let mut client = rpc_client(&cli, url);
+            let fetched = match set {
+                rpc::AccountSet::Program => client.program_account_keys(program.as_ref().unwrap(), *data_size, *page_offset).map(|(slot, keys)| (Some(slot), keys)),
+                rpc::AccountSet::Vote => client.vote_account_keys().map(|keys| (None, keys)),
+                rpc::AccountSet::Stake => client.stake_account_keys(*page_offset).map(|(slot, keys)| (Some(slot), keys)),
+            };
+            let (slot, keys) = fetched.unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, err).exit());
+            let keys = keys.iter().map(Pubkey::to_string).collect::<Vec<_>>();
+            match out {
+                Some(path) => serde_json::to_writer(create_file(path), &keys).unwrap(),
+                None => serde_json::to_writer(std::io::stdout(), &keys).unwrap(),
+            }
+            match slot {
+                Some(slot) => eprintln!("fetched {} accounts from {url} at slot {slot}", keys.len()),
+                None => eprintln!("fetched {} accounts from {url}", keys.len()),
+            }
+        }
+        Some(Command::Generate { pattern, count, prefix, program, out }) => {
+            let mut rng = rng::stream(cli.rng_seed, "generate", 0);
+            let keys = match pattern {
+                generate::Pattern::Random => generate::random(*count, &mut rng),
+                generate::Pattern::Vanity => generate::vanity(*count, prefix.as_ref().unwrap(), &mut rng),
+                generate::Pattern::Pda => generate::pdas(*count, program.as_ref().unwrap()),
+                generate::Pattern::SingleByte => generate::single_byte(*count, &mut rng),
+            };
+            let keys = keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+            match out {
+                Some(path) => serde_json::to_writer(create_file(path), &keys).unwrap(),
+                None => serde_json::to_writer(std::io::stdout(), &keys).unwrap(),
+            }
+        }
+        Some(Command::Assign { address, epoch }) => {
+            let hasher = single_hasher(&cli);
+            let address = Pubkey2(*address);
+            let single = epoch.0.end - epoch.0.start == 1;
+            if !single {
+                println!("{}", assign::HEADER);
+            }
+            for epoch in epoch.0.clone() {
+                let mut visitor = assign::AssignVisitor { buckets: cli.buckets, reduction: cli.reduction, address: &address, bucket: None };
+                hashers::visit_registered(hasher, epoch_seed(&cli, epoch), &mut visitor);
+                match visitor.bucket.unwrap() {
+                    bucket if single => println!("{bucket}"),
+                    bucket => println!("{epoch},{bucket}"),
+                }
+            }
+        }
+        Some(Command::Filter { epoch }) => {
+            let hasher = single_hasher(&cli);
+            let mut visitor = filter::FilterVisitor { buckets: cli.buckets, reduction: cli.reduction, input: std::io::stdin().lock(), output: std::io::stdout().lock(), result: Ok(0) };
+            hashers::visit_registered(hasher, epoch_seed(&cli, *epoch), &mut visitor);
+            // A reader closing the pipe early, like head, just ends the filter.
+            match visitor.result {
+                Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => {
+                    eprintln!("{err}");
+                    std::process::exit(1);
+                }
+                _ => {}
+            }
+        }
+        Some(Command::Set { op, left, right, out }) => {
+            let (result, stats) = set_ops::apply(*op, &load_addresses(left), &load_addresses(right));
+            let result = result.iter().map(|address| address.to_string()).collect::<Vec<_>>();
+            match out {
+                Some(path) => serde_json::to_writer(create_file(path), &result).unwrap(),
+                None => serde_json::to_writer(std::io::stdout(), &result).unwrap(),
+            }
+            eprintln!("{stats}");
+        }
+        Some(Command::Explain { address, seed }) => {
+            let bucketer = Bucketer::new(cli.buckets, *seed).with_reduction(cli.reduction).unwrap();
+            println!("{}", serde_json::to_string_pretty(&bucketer.explain(address)).unwrap());
+        }
+        Some(Command::Bench { threads, epochs }) => {
+            let addresses = load_input(&cli).0;
+            let mut visitor = bench::ScalingVisitor::new(cli.buckets, cli.reduction, &addresses, threads);
+            for epoch in 0..*epochs {
+                for_each_hasher(&cli, epoch, &mut visitor);
+            }
+            println!("{}", bench::HEADER);
+            for result in visitor.results() {
+                println!("{result}");
+            }
+        }
+        Some(Command::BenchReduction { rounds }) => {
+            let hashes = load_input(&cli).0.iter().map(|address| {
+                let mut hasher = Blake3Hasher::new_with_seed(0);
+                hasher.write(address.as_ref());
+                hasher.finish()
+            }).collect::<Vec<_>>();
+            println!("buckets,generic_ns,const_ns");
+            let timings = reduction::time_reductions::<BUCKETS>(&hashes, *rounds);
+            println!("{BUCKETS},{},{}", timings.generic_ns, timings.const_ns);
+            let timings = reduction::time_reductions::<4096>(&hashes, *rounds);
+            println!("4096,{},{}", timings.generic_ns, timings.const_ns);
+        }
+        Some(Command::Sweep { config, out, threads }) => {
+            let config = sweep::SweepConfig::load(config).unwrap_or_else(|err| {
+                Cli::command().error(clap::error::ErrorKind::InvalidValue, err).exit()
+            });
+            let addresses = load_input(&cli).0;
+            let mut sinks = open_sinks(&cli);
+            let (ran, skipped) = sweep::run(&config, &addresses, out, *threads, cli.mode_tie_break, &mut sinks).expect("failed to write sweep results");
+            eprintln!("ran {ran} combinations, skipped {skipped} already in {}", out.display());
+        }
+        Some(Command::CompareRuns { a, b }) => {
+            let load = |path| results::Results::load(path).unwrap_or_else(|err| {
+                Cli::command().error(clap::error::ErrorKind::Io, err).exit()
+            });
+            let (a, b) = (load(a), load(b));
+            println!("{}", compare::HEADER);
+            for delta in compare::compare(&a, &b) {
+                println!("{delta}");
+            }
+        }
+        Some(Command::BenchAnalysis { sizes, load, rounds }) => {
+            println!("buckets,sort_ns,streaming_ns,speedup");
+            for size in sizes {
+                let mut buckets = vec![0; size.get()];
+                for i in 0..(size.get() * load) as u64 {
+                    let mut hasher = Blake3Hasher::new_with_seed(0);
+                    hasher.write_u64(i);
+                    buckets[reduction::reduce(size.get(), hasher.finish())] += 1;
+                }
+                let timings = analysis::time_analysis(&buckets, cli.mode_tie_break, *rounds);
+                println!("{size},{},{},{}", timings.sort_ns, timings.streaming_ns, timings.sort_ns / timings.streaming_ns);
+            }
+        }
+        Some(Command::Elasticity { from, to, step, epochs }) => {
+            let hasher = single_hasher(&cli);
+            let addresses = load_input(&cli).0;
+            let bucket_counts = elasticity::bucket_counts(from.get(), *to, step.get(), cli.reduction);
+            let mut visitor = elasticity::ElasticityVisitor::new(&addresses, &bucket_counts, cli.reduction, cli.mode_tie_break);
+            for epoch in 0..*epochs {
+                hashers::visit_registered(hasher, epoch_seed(&cli, epoch), &mut visitor);
+            }
+            println!("{}", elasticity::HEADER);
+            for point in visitor.points() {
+                println!("{point}");
+            }
+        }
+        Some(Command::CheckPair { baseline, candidate, max_quality_regression, significance, epochs }) => {
+            let addresses = load_input(&cli).0;
+            let visitor = |hasher| {
+                let mut visitor = check_pair::PairVisitor::new(cli.buckets, cli.reduction, &addresses, cli.mode_tie_break);
+                for epoch in 0..*epochs {
+                    hashers::visit_registered(hasher, epoch_seed(&cli, epoch), &mut visitor);
+                }
+                visitor
+            };
+            let checks = check_pair::check(&visitor(baseline), &visitor(candidate), *max_quality_regression, *significance)
+                .unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::ValueValidation, err).exit());
+            println!("{}", check_pair::HEADER);
+            for check in &checks {
+                println!("{check}");
+            }
+            if checks.iter().any(|check| !check.pass) {
+                eprintln!("{candidate} regresses on {baseline} by more than {max_quality_regression}% at significance {significance}");
+                std::process::exit(1);
+            }
+        }
+        #[cfg(feature = "bigtable")]
+        Some(Command::ExtractAddresses {
+            #[cfg(feature = "ledger")]
+            ledger,
+            bigtable_credentials, epoch, start_slot, end_slot, out, .. }) => {
+            let source = ledger::LedgerSource::Bigtable { credential_path: bigtable_credentials.clone() };
+            #[cfg(feature = "ledger")]
+            let source = match ledger {
+                Some(path) => ledger::LedgerSource::Rocksdb(path.clone()),
+                None => source,
+            };
+            let slots = match (epoch, start_slot, end_slot) {
+                (Some(epoch), ..) => ledger::epoch_slots(*epoch),
+                (_, Some(start), Some(end)) if start <= end => *start..=*end,
+                (_, start, end) => Cli::command().error(clap::error::ErrorKind::ValueValidation, format!("--start-slot {} is after --end-slot {}", start.unwrap(), end.unwrap())).exit(),
+            };
+            let collector = ledger::extract_addresses(&source, slots.clone())
+                .unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, err).exit());
+            let addresses = collector.addresses.iter().map(|address| address.to_string()).collect::<Vec<_>>();
+            match out {
+                Some(path) => serde_json::to_writer(create_file(path), &addresses).unwrap(),
+                None => serde_json::to_writer(std::io::stdout(), &addresses).unwrap(),
+            }
+            eprintln!("{} addresses from {} transactions in {} blocks of slots {}..={}", addresses.len(), collector.transactions, collector.blocks, slots.start(), slots.end());
+        }
+        #[cfg(feature = "geyser")]
+        Some(Command::Geyser { endpoint, x_token, program, data_size, initial_rpc_url, interval, epoch }) => {
+            let mut live = match initial_rpc_url {
+                Some(url) => {
+                    let (slot, keys) = rpc_client(&cli, url)
+                        .program_account_keys(program, *data_size, None)
+                        .unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, err).exit());
+                    eprintln!("fetched {} accounts from {url} at slot {slot}", keys.len());
+                    live_set::LiveAccountSet::new(slot, keys)
+                }
+                None => live_set::LiveAccountSet::default(),
+            };
+            let config = geyser::GeyserConfig { endpoint: endpoint.clone(), x_token: x_token.clone(), program: *program, data_size: *data_size };
+            let pool = BufferPool::default();
+            println!("slot,addresses,hasher,{}", AnalysisFormat::new(&cli.percentiles).header());
+            let result = geyser::stream(&config, &mut live, Duration::from_secs(interval.get()), |live| {
+                if live.addresses().is_empty() {
+                    return;
+                }
+                run_hashers(&cli, *epoch, live.addresses(), None, &pool, &mut |name, buckets, _time| {
+                    println!("{},{},{name},{}", live.slot(), live.addresses().len(), analyze_buckets_streaming(&buckets, cli.mode_tie_break, &cli.percentiles));
+                    pool.give(buckets);
+                });
+            });
+            if let Err(err) = result {
+                Cli::command().error(clap::error::ErrorKind::Io, err).exit();
+            }
+        }
+        Some(Command::AhashConformance { record, check }) => {
+            let current = ahash_conformance::AhashConfig::current();
+            let io_error = |err: std::io::Error| -> ! { Cli::command().error(clap::error::ErrorKind::Io, err).exit() };
+            if let Some(path) = record {
+                std::fs::write(path, serde_json::to_string_pretty(&current).unwrap()).unwrap_or_else(|err| io_error(err));
+                eprintln!("recorded {current}");
+            }
+            if let Some(path) = check {
+                let recorded = std::fs::read(path).and_then(|bytes| Ok(serde_json::from_slice(&bytes)?)).unwrap_or_else(|err| io_error(err));
+                let differences = ahash_conformance::differences(&recorded, &current);
+                for difference in differences.iter() {
+                    eprintln!("WARNING: ahash {difference}");
+                }
+                if recorded.fingerprint != current.fingerprint {
+                    std::process::exit(1);
+                }
+                eprintln!("ahash output matches {}", path.display());
+            }
+        }
+        Some(Command::Replay { blockhashes, seed_rpc_url, epochs }) => {
+            let seeds = match (blockhashes, seed_rpc_url) {
+                (Some(path), _) => replay::load_epoch_seeds(path).unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, err).exit()),
+                (None, Some(url)) => replay::fetch_epoch_seeds(&mut rpc_client(&cli, url), *epochs)
+                    .unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, err).exit()),
+                (None, None) => unreachable!(),
+            };
+            let addresses = match cli.rpc_url.as_deref() {
+                Some(url) => load_rpc_addresses(&cli, url),
+                None => load_input(&cli).0,
+            };
+            let pool = BufferPool::default();
+            println!("hasher,epoch,blockhash,{}", AnalysisFormat::new(&cli.percentiles).header());
+            for seed in seeds {
+                let mut emit = |name: &'static str, buckets: Vec<usize>, _time| {
+                    let analysis = analyze_buckets_streaming(&buckets, cli.mode_tie_break, &cli.percentiles);
+                    println!("{name},{},{},{analysis}", seed.epoch, seed.blockhash);
+                    pool.give(buckets);
+                };
+                let mut visitor = TestVisitor { buckets: cli.buckets, pool: &pool, reduction: cli.reduction, addresses: &addresses, weights: None, parallel: cli.threads.is_some(), emit: &mut emit };
+                replay::for_each_seeded_hasher(&seed.blockhash, &mut visitor);
+            }
+        }
+        None => run_analysis(&cli),
+    }
+}
+
+fn rpc_client(cli: &Cli, url: &str) -> rpc::RpcClient {
+    rpc::RpcClient::new(rpc::RpcConfig {
+        url: url.to_string(),
+        max_retries: cli.rpc_retries,
+        initial_backoff: Duration::from_millis(cli.rpc_backoff_ms),
+        max_requests_per_second: cli.rpc_rate,
+    })
+}
+
+fn load_rpc_addresses(cli: &Cli, url: &str) -> Vec<Pubkey2> {
+    let program = cli.rpc_program.as_ref().unwrap();
+    let cache = cli.max_age.map(|max_age| account_cache::AccountCache::new(cli.rpc_cache_dir.clone(), Duration::from_secs(max_age)));
+    let io_error = |err: std::io::Error| -> ! { Cli::command().error(clap::error::ErrorKind::Io, err).exit() };
+    if let Some(cache) = cache.as_ref() {
+        if let Some((slot, keys)) = cache.get(url, program, cli.rpc_data_size).unwrap_or_else(|err| io_error(err)) {
+            eprintln!("loaded {} cached accounts read from {url} at slot {slot}", keys.len());
+            return keys.into_iter().map(Pubkey2).collect();
+        }
+    }
+    let (slot, keys) = rpc_client(cli, url)
+        .program_account_keys(program, cli.rpc_data_size, cli.rpc_page_offset)
+        .unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, err).exit());
+    eprintln!("fetched {} accounts from {url} at slot {slot}", keys.len());
+    if let Some(cache) = cache.as_ref() {
+        cache.put(url, program, cli.rpc_data_size, slot, &keys).unwrap_or_else(|err| io_error(err));
+    }
+    keys.into_iter().map(Pubkey2).collect()
+}
+
+fn input_format(cli: &Cli) -> input::InputFormat {
+    cli.input_format.unwrap_or_else(|| input::InputFormat::detect(&cli.input))
+}
+
+// --input in --input-format, with each tagged address's tag.
+fn load_input(cli: &Cli) -> (Vec<Pubkey2>, HashMap<Pubkey, String>) {
+    input::load_tagged(&cli.input, input_format(cli), &cli.csv_column).unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, err).exit())
+}
+
+type HasherCounts = (&'static str, Vec<usize>, Duration);
+
+/// Each epoch's bucket counts per hasher, the entries streamed, and each
+/// epoch's --approximate-distinct sketches if requested.
+fn stream_counts(cli: &Cli, chunk_size: NonZeroUsize) -> (Vec<Vec<HasherCounts>>, usize, Vec<hll::DistinctSketches>) {
+    let pool = BufferPool::default();
+    let mut epochs = (0..cli.epochs).map(|_| Vec::new()).collect::<Vec<_>>();
+    let mut sketches = match cli.approximate_distinct {
+        Some(precision) => (0..cli.epochs).map(|epoch| hll::DistinctSketches::new(epoch, cli.buckets, cli.reduction, precision)).collect(),
+        None => Vec::new(),
+    };
+    let entries = input::for_each_chunk(&cli.input, input_format(cli), &cli.csv_column, chunk_size, |chunk| {
+        for sketches in &mut sketches {
+            let epoch = sketches.epoch();
+            for_each_hasher(cli, epoch, &mut sketches.add(chunk));
+        }
+        for (epoch, hashers) in epochs.iter_mut().enumerate() {
+            let mut index = 0;
+            run_hashers(cli, epoch as u64, chunk, None, &pool, &mut |name, buckets, time| {
+                if index == hashers.len() {
+                    hashers.push((name, vec![0; cli.buckets], Duration::ZERO));
+                }
+                let (_, counts, total) = &mut hashers[index];
+                for (count, bucket) in counts.iter_mut().zip(&buckets) {
+                    *count += bucket;
+                }
+                *total += time;
+                pool.give(buckets);
+                index += 1;
+            });
+        }
+    })
+    .unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, err).exit());
+    if entries == 0 {
+        for (epoch, hashers) in epochs.iter_mut().enumerate() {
+            run_hashers(cli, epoch as u64, &[], None, &pool, &mut |name, buckets, time| hashers.push((name, buckets, time)));
+        }
+    }
+    (epochs, entries, sketches)
+}
+
+fn run_analysis(cli: &Cli) {
+    if cli.run_name.is_none() && cli.stats_out.is_none() && cli.sinks.contains(&sink::SinkSpec::Stdout) {
+        Cli::command().error(clap::error::ErrorKind::ArgumentConflict, "--sink stdout would interleave with results on stdout; pass --stats-out or --run-name").exit();
+    }
+    if let Some(Some(epoch)) = cli.histogram.filter(|only| only.is_some_and(|only| only >= cli.epochs)) {
+        Cli::command().error(clap::error::ErrorKind::ValueValidation, format!("--histogram {epoch} is past the last epoch, {}", cli.epochs - 1)).exit();
+    }
+    if let Some(precision) = cli.approximate_distinct {
+        // Streaming keeps every epoch's sketches until the input ends.
+        let epochs = if cli.stream_chunk.is_some() { cli.epochs } else { 1 };
+        let bytes = hll::sketch_bytes(epochs.saturating_mul(hasher_names(cli).len() as u64).saturating_mul(cli.buckets as u64), precision);
+        if bytes > hll::MAX_SKETCH_BYTES {
+            let message = format!("--approximate-distinct {precision} needs {bytes} bytes of sketches, more than {}; lower it or the buckets, hashers or streamed epochs", hll::MAX_SKETCH_BYTES);
+            Cli::command().error(clap::error::ErrorKind::ValueValidation, message).exit();
+        }
+    }
+    let streamed = cli.stream_chunk.map(|chunk_size| stream_counts(cli, chunk_size));
+    let (mut addresses, tags) = match (cli.rpc_url.as_deref(), &streamed) {
+        (_, Some(_)) => (Vec::new(), HashMap::new()),
+        (Some(url), None) => (load_rpc_addresses(cli, url), HashMap::new()),
+        (None, None) => load_input(cli),
+    };
+    if let Some(path) = cli.exclude.as_deref() {
+        let entries = addresses.len();
+        let excluded = load_addresses(path);
+        addresses = exclude(addresses, &excluded);
+        eprintln!("excluded {} of {entries} entries using {} listed addresses", entries - addresses.len(), excluded.len());
+    }
+    let stake_meta = cli.stake_meta.as_deref().map(stake::load_stake_meta);
+    if let (Some(meta), Some(epoch)) = (stake_meta.as_deref(), cli.active_at) {
+        let (active, stats) = stake::filter_active(addresses, meta, epoch);
+        eprintln!("stake at epoch {epoch}: {stats}");
+        addresses = active;
+    }
+    let rewards = match (cli.reward_amounts.as_deref(), cli.rewards_from_stake) {
+        (Some(path), _) => {
+            let (rewards, unlisted) = rewards::listed_rewards(&addresses, &rewards::load_reward_amounts(path));
+            eprintln!("{unlisted} of {} entries have no listed reward", addresses.len());
+            Some(rewards)
+        }
+        (None, Some(total)) => Some(rewards::stake_proportional_rewards(&addresses, stake_meta.as_deref().unwrap(), total)),
+        (None, None) => None,
+    };
+    let mut weights = None;
+    if cli.weight_by_count {
+        let entries = addresses.len();
+        let (distinct, counts) = fold_duplicates(addresses);
+        eprintln!("folded {entries} entries into {} weighted addresses", distinct.len());
+        addresses = distinct;
+        weights = Some(counts);
+    }
+    let num_entries = match &streamed {
+        Some((_, entries, _)) => *entries,
+        None => weights.as_ref().map_or(addresses.len(), |weights| weights.iter().sum()),
+    };
+    if let Some(command) = cli.reference_cmd.as_deref() {
+        let report = reference::check_reference(command, &addresses, cli.buckets, cli.reference_epochs, cli.reference_sample)
+            .expect("failed to run reference implementation");
+        for mismatch in report.mismatches.iter() {
+            eprintln!("reference mismatch: epoch {} address {} expected {} got {}", mismatch.epoch, mismatch.address, mismatch.expected, mismatch.actual);
+        }
+        if !report.mismatches.is_empty() {
+            eprintln!("{} of {} reference assignments differ", report.mismatches.len(), report.checked);
+            std::process::exit(1);
+        }
+        eprintln!("{} reference assignments match", report.checked);
+    }
+    if let Some(epochs) = cli.verify_order {
+        let pool = BufferPool::default();
+        if let Err(mismatch) = verify::verify_order_independence(epochs, cli.rng_seed, &addresses, |epoch, addresses, emit| run_hashers(cli, epoch, addresses, None, &pool, emit)) {
+            eprintln!("{mismatch}");
+            std::process::exit(1);
+        }
+        eprintln!("bucket counts independent of address order over {epochs} epochs");
+    }
+    #[cfg(feature = "memory-report")]
+    let footprints = cli.memory_report.then(|| {
+        let mut visitor = footprint::FootprintVisitor { addresses: &addresses, footprints: Vec::new() };
+        for_each_hasher(cli, 0, &mut visitor);
+        visitor.footprints
+    });
     let mut timings = HashMap::new();
-    println!("epoch,min,max,spread,mean,median,mode,mode_count,std_dev");
-    for epoch in 0u64..EPOCHS {
-        for (name, time) in [
-            /*
-            ("ahash", {
-                let state = ahash::random_state::RandomState::with_seeds(epoch, epoch, epoch, epoch);
-                let hasher = state.build_hasher();
-                do_test(hasher, epoch, &addresses)
-            }),
-            ("siphash24", {
-                let hasher = siphasher::sip::SipHasher24::new_with_keys(epoch, epoch);
-                do_test(hasher, epoch, &addresses)
-            }),
-            ("siphash13", {
-                let hasher = siphasher::sip::SipHasher13::new_with_keys(epoch, epoch);
-                do_test(hasher, epoch, &addresses)
-            }),
-            ("murmur3", {
-                let hasher = mur3::Hasher128::with_seed(epoch as u32);
-                do_test(hasher, epoch, &addresses)
-            }),
-            */
-            ("blake3", {
-                let hasher = Blake3Hasher::new_with_seed(epoch);
-                do_test(hasher, epoch, &addresses)
-            }),
-        ].iter() {
+    let sparse = num_entries < cli.buckets;
+    let mut format = AnalysisFormat::new(&cli.percentiles);
+    if cli.no_mode_columns {
+        let percentile_header = analysis::percentile_header(&cli.percentiles);
+        let mut columns = analysis::HEADER_WITHOUT_MODE.split(',').map(str::to_string).collect::<Vec<_>>();
+        columns.extend(percentile_header.split(',').filter(|column| !column.is_empty()).map(str::to_string));
+        format = format.with_columns(&columns).unwrap();
+    }
+    if let Some(columns) = cli.columns.as_deref() {
+        format = format.with_columns(columns).unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::InvalidValue, err).exit());
+    }
+    if let Some(precision) = cli.precision {
+        format = format.with_precision(precision);
+    }
+    let mut header = format!("epoch,{}", format.header());
+    if sparse {
+        write!(header, ",{}", analysis::OCCUPANCY_HEADER).unwrap();
+    }
+    let ahash = hasher_names(cli).contains(&"ahash").then(ahash_conformance::AhashConfig::current);
+    if let Some(ahash) = ahash.as_ref() {
+        eprintln!("WARNING: ahash output is not portable; results hold only for {ahash}");
+    }
+    // Stdout interleaves every hasher's rows, so name each row's hasher
+    // whenever more than one runs; a run directory has a file per hasher.
+    let hasher_column = cli.compare || (cli.run_name.is_none() && hasher_names(cli).len() > 1);
+    let results_header = match (cli.output_format, cli.format, hasher_column) {
+        (records::OutputFormat::Json | records::OutputFormat::Jsonl, ..) => String::new(),
+        (_, analysis::Format::Long, _) => analysis::LONG_HEADER.to_string(),
+        (_, analysis::Format::Wide, true) => format!("hasher,{header}"),
+        (_, analysis::Format::Wide, false) => header.clone(),
+    };
+    let mut output = match cli.run_name.clone() {
+        Some(run_name) => {
+            let manifest = Manifest::new(run_name, cli.input.clone(), streamed.as_ref().map_or(addresses.len(), |(_, entries, _)| *entries), cli.buckets, cli.reduction, cli.epochs, hasher_names(cli)).with_nonce(cli.nonce).with_ahash(ahash);
+            let run_dir = RunDir::create(&cli.output_dir, &manifest).expect("failed to create run directory");
+            if !cli.quiet {
+                eprintln!("writing results to {}", run_dir.path().display());
+            }
+            ResultsOutput::run_dir(run_dir, results_header, cli.output_format.extension())
+        }
+        None => {
+            let out: Box<dyn Write> = match cli.stats_out.as_deref() {
+                Some(path) => Box::new(create_file(path)),
+                None => Box::new(std::io::stdout()),
+            };
+            ResultsOutput::stream(out, &results_header).unwrap()
+        }
+    };
+    let mut sinks = open_sinks(cli);
+    let columns = header.split(',').skip(1).collect::<Vec<_>>();
+    let mut comparison = baseline::BaselineComparison::default();
+    let mut aggregator = aggregate::Aggregator::default();
+    let hashers_per_epoch = hasher_names(cli).len();
+    let rows_per_progress = hashers_per_epoch * 100;
+    let mut rows = 0;
+    let config = records::RunConfig { buckets: cli.buckets, reduction: cli.reduction, entries: num_entries, nonce: cli.nonce };
+    let mut records = Vec::new();
+    let mut spreads = Vec::<(&'static str, Vec<f64>)>::new();
+    pipeline::run(
+        cli.epochs,
+        hasher_names(cli).len(),
+        // With --threads, epochs share the global pool with their addresses.
+        cli.threads.map_or(Some(cli.hash_threads), |_| None),
+        cli.io_threads,
+        |epoch, pool, emit| match &streamed {
+            Some((epochs, ..)) => for (name, counts, time) in &epochs[epoch as usize] {
+                let mut buckets = pool.take(counts.len());
+                buckets.copy_from_slice(counts);
+                emit(name, buckets, *time);
+            },
+            None => run_hashers(cli, epoch, &addresses, weights.as_deref(), pool, emit),
+        },
+        |epoch, buckets| {
+            let chart = cli.histogram.is_some_and(|only| only.is_none_or(|only| only == epoch)).then(|| {
+                let mut chart = Vec::new();
+                histogram::write_histogram(buckets, &mut chart).unwrap();
+                chart
+            });
+            (format_row(epoch, buckets, sparse, cli.mode_tie_break, &format), chart)
+        },
+        |epoch, name, ((row, analysis, occupancy), chart), time| {
+            if analysis.p_value < cli.significance {
+                eprintln!("epoch {epoch}: {name} bucket counts reject uniformity at {} (chi_squared {}, p {})", cli.significance, analysis.chi_squared, analysis.p_value);
+            }
+            sinks.write_row(name, epoch, &columns, &row.split(',').skip(1).collect::<Vec<_>>()).unwrap();
+            match (cli.output_format, cli.format) {
+                (records::OutputFormat::Json | records::OutputFormat::Jsonl, _) => {}
+                (_, analysis::Format::Long) => writeln!(output.writer(name).unwrap(), "{}", analysis::long_rows(name, &header, &row)).unwrap(),
+                _ if hasher_column => writeln!(output.writer(name).unwrap(), "{name},{row}").unwrap(),
+                _ => writeln!(output.writer(name).unwrap(), "{row}").unwrap(),
+            }
+            if !(cli.portable_candidates && hashers::NON_PORTABLE.contains(&name)) {
+                comparison.record(name, &analysis, time);
+            }
+            aggregator.record(name, epoch, &analysis, time);
+            if let Some(chart) = chart {
+                match output.plot_writer(&format!("{name}-epoch-{epoch}-histogram.txt")).unwrap() {
+                    Some(mut plot) => plot.write_all(&chart).and_then(|()| plot.flush()).unwrap(),
+                    None => eprint!("epoch {epoch}: {name} buckets by load\n{}", String::from_utf8(chart).unwrap()),
+                }
+            }
+            if cli.histogram.is_some() {
+                match spreads.iter_mut().find(|(hasher, _)| *hasher == name) {
+                    Some((_, spreads)) => spreads.push(analysis.spread as f64),
+                    None => spreads.push((name, vec![analysis.spread as f64])),
+                }
+            }
+            rows += 1;
+            if !cli.quiet && rows % rows_per_progress == 0 {
+                eprintln!("{}/{} epochs", rows / rows_per_progress * 100, cli.epochs);
+            }
+            if cli.flush_every.is_some_and(|every| rows % hashers_per_epoch == 0 && (epoch + 1) % every == 0) {
+                output.checkpoint().unwrap();
+                sinks.flush().unwrap();
+            }
             timings.entry(name.to_string())
-                .and_modify(|v: &mut std::time::Duration| *v += *time)
-                .or_insert(*time);
+                .and_modify(|v: &mut Duration| *v += time)
+                .or_insert(time);
+            if cli.output_format != records::OutputFormat::Csv {
+                let percentiles = records::percentiles(&cli.percentiles, &analysis.percentiles);
+                let seed = epoch_seed(cli, epoch);
+                let record = records::Record::Epoch(Box::new(records::EpochRecord { hasher: name.into(), config: Cow::Borrowed(&config), epoch, seed, time_us: time.as_secs_f64() * 1e6, analysis, percentiles, occupancy }));
+                match cli.output_format {
+                    records::OutputFormat::Jsonl => writeln!(output.writer(name).unwrap(), "{}", serde_json::to_string(&record).unwrap()).unwrap(),
+                    _ => records.push(record),
+                }
+            }
+        },
+    );
+
+    let summaries = comparison.summaries().into_iter().map(|summary| records::Record::Summary(records::SummaryRecord { config: Cow::Borrowed(&config), summary }));
+    match cli.output_format {
+        records::OutputFormat::Csv => {}
+        records::OutputFormat::Json => {
+            records.extend(summaries);
+            let out = output.writer("results").unwrap();
+            serde_json::to_writer_pretty(&mut *out, &records).unwrap();
+            writeln!(out).unwrap();
+        }
+        records::OutputFormat::Jsonl => {
+            for summary in summaries {
+                writeln!(output.writer("summary").unwrap(), "{}", serde_json::to_string(&summary).unwrap()).unwrap();
+            }
+        }
+    }
+
+    if !spreads.is_empty() {
+        let mut lines = String::new();
+        for (name, spreads) in &spreads {
+            let (min, max) = spreads.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), spread| (min.min(*spread), max.max(*spread)));
+            writeln!(lines, "{name:<24} {} spread {min}..{max}", histogram::sparkline(spreads)).unwrap();
+        }
+        match output.plot_writer("spread-sparklines.txt").unwrap() {
+            Some(mut plot) => plot.write_all(lines.as_bytes()).and_then(|()| plot.flush()).unwrap(),
+            None => eprint!("{lines}"),
+        }
+    }
+
+    if cli.vs_blake3 {
+        for ratios in comparison.ratios() {
+            writeln!(output.report_writer("vs-blake3", baseline::HEADER).unwrap(), "{ratios}").unwrap();
+        }
+    }
+
+    if cli.aggregate {
+        for aggregate in aggregator.aggregates() {
+            writeln!(output.report_writer("aggregate", aggregate::HEADER).unwrap(), "{aggregate}").unwrap();
+        }
+    }
+
+    if cli.group_by_tag {
+        let pool = BufferPool::default();
+        for (tag, (members, member_weights)) in tags::group(&addresses, weights.as_deref(), &tags) {
+            for epoch in 0..cli.epochs {
+                run_hashers(cli, epoch, &members, member_weights.as_deref(), &pool, &mut |name, buckets, _time| {
+                    let (row, ..) = format_row(epoch, &buckets, sparse, cli.mode_tie_break, &format);
+                    writeln!(output.report_writer(&format!("{name}@{tag}"), &header).unwrap(), "{row}").unwrap();
+                    pool.give(buckets);
+                });
+            }
+        }
+    }
+
+    if cli.stability {
+        let mut visitor = stability::StabilityVisitor::new(cli.buckets, cli.reduction, &addresses);
+        for epoch in 0..cli.epochs {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        for score in visitor.scores(&tags) {
+            writeln!(output.report_writer("stability", stability::HEADER).unwrap(), "{score}").unwrap();
+        }
+    }
+
+    if let Some(rewards) = rewards.as_deref() {
+        let pool = BufferPool::default();
+        let header = format!("epoch,{}", AnalysisFormat::new(&cli.percentiles).header());
+        for epoch in 0..cli.epochs {
+            run_hashers(cli, epoch, &addresses, Some(rewards), &pool, &mut |name, buckets, _time| {
+                let analysis = analysis::analyze_buckets_streaming(&buckets, cli.mode_tie_break, &cli.percentiles);
+                writeln!(output.report_writer(&format!("{name}-rewards"), &header).unwrap(), "{epoch},{analysis}").unwrap();
+                pool.give(buckets);
+            });
+        }
+    }
+
+    if cli.autocorrelation {
+        let assign = |epoch| addresses.iter()
+            .map(|address| blake3_bucket(cli, epoch, address))
+            .collect::<Vec<_>>();
+        let mut previous = assign(0);
+        for epoch in 1..cli.epochs {
+            let current = assign(epoch);
+            let analysis = autocorrelation::analyze_autocorrelation(cli.buckets, &previous, &current);
+            let out = output.report_writer("blake3-autocorrelation", autocorrelation::HEADER).unwrap();
+            writeln!(out, "{epoch},{analysis}").unwrap();
+            previous = current;
+        }
+    }
+
+    if let Some(sample_size) = cli.audit {
+        let sample = reference::sample(&addresses, sample_size).collect::<Vec<_>>();
+        for epoch in 0..cli.epochs {
+            let mut visitor = audit::AuditVisitor {
+                epoch,
+                buckets: cli.buckets,
+                reduction: cli.reduction,
+                addresses: if epoch < cli.audit_epochs { &sample } else { &[] },
+                seeds: Vec::new(),
+                records: Vec::new(),
+            };
+            for_each_hasher(cli, epoch, &mut visitor);
+            for seed in visitor.seeds {
+                writeln!(output.report_writer("seeds", audit::SEEDS_HEADER).unwrap(), "{seed}").unwrap();
+            }
+            for record in visitor.records {
+                writeln!(output.report_writer("audit", audit::HEADER).unwrap(), "{record}").unwrap();
+            }
+        }
+    }
+
+    if let Some(k) = cli.sample_assignments {
+        for epoch in 0..cli.epochs {
+            let sample = assignments::sample(&addresses, k, cli.rng_seed, epoch);
+            let mut visitor = assignments::AssignmentVisitor { epoch, buckets: cli.buckets, reduction: cli.reduction, sample, assignments: Vec::new() };
+            for_each_hasher(cli, epoch, &mut visitor);
+            for assignment in visitor.assignments {
+                writeln!(output.report_writer("assignments", assignments::HEADER).unwrap(), "{assignment}").unwrap();
+            }
+        }
+    }
+
+    if let Some(top) = cli.top_buckets {
+        for epoch in 0..cli.epochs {
+            let mut visitor = top_buckets::TopBucketsVisitor {
+                epoch,
+                buckets: cli.buckets,
+                reduction: cli.reduction,
+                addresses: &addresses,
+                top,
+                examples: cli.bucket_examples,
+                rng_seed: cli.rng_seed,
+                rows: Vec::new(),
+            };
+            for_each_hasher(cli, epoch, &mut visitor);
+            for row in visitor.rows {
+                writeln!(output.report_writer("top-buckets", top_buckets::HEADER).unwrap(), "{row}").unwrap();
+            }
+        }
+    }
+
+    if let Some(precision) = cli.approximate_distinct {
+        for epoch in 0..cli.epochs {
+            let sketches = match &streamed {
+                Some((_, _, sketches)) => &sketches[epoch as usize],
+                None => &{
+                    let mut sketches = hll::DistinctSketches::new(epoch, cli.buckets, cli.reduction, precision);
+                    for_each_hasher(cli, epoch, &mut sketches.add(&addresses));
+                    sketches
+                },
+            };
+            for estimate in sketches.estimates() {
+                writeln!(output.report_writer("distinct", hll::HEADER).unwrap(), "{estimate}").unwrap();
+            }
+        }
+    }
+
+    if let Some(epochs) = cli.epoch_pairs {
+        let mut visitor = epoch_pairs::EpochPairVisitor::new(cli.buckets, cli.reduction, &addresses);
+        for epoch in 0..epochs.min(cli.epochs) {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        let expected_agreement = 1.0 / cli.buckets as f64;
+        for (hasher, matrix) in visitor.matrices() {
+            let mut most_similar = None;
+            for (epoch_a, row) in matrix.iter().enumerate() {
+                for (epoch_b, agreement) in row.iter().enumerate().skip(epoch_a + 1) {
+                    let pair = epoch_pairs::EpochPair { hasher, epoch_a: epoch_a as u64, epoch_b: epoch_b as u64, agreement: *agreement, expected_agreement };
+                    writeln!(output.report_writer("epoch-pairs", epoch_pairs::HEADER).unwrap(), "{pair}").unwrap();
+                    if most_similar.as_ref().is_none_or(|most: &epoch_pairs::EpochPair| pair.agreement > most.agreement) {
+                        most_similar = Some(pair);
+                    }
+                }
+            }
+            if let Some(pair) = most_similar {
+                eprintln!("{hasher}: most similar epochs {} and {} agree on {} of assignments, {} expected", pair.epoch_a, pair.epoch_b, pair.agreement, pair.expected_agreement);
+            }
+            if let Some(mut plot) = output.plot_writer(&format!("{hasher}-epoch-pairs.pgm")).unwrap() {
+                epoch_pairs::write_heatmap(&matrix, expected_agreement, &mut plot).unwrap();
+            }
+        }
+    }
+
+    if let Some(epochs) = cli.scaling {
+        let mut visitor = scaling::PrefixVisitor::new(cli.buckets, cli.reduction, &addresses);
+        for epoch in 0..epochs.min(cli.epochs) {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        let points = visitor.points();
+        for point in points.iter() {
+            writeln!(output.report_writer("scaling", scaling::HEADER).unwrap(), "{point}").unwrap();
+        }
+        if let Some(mut plot) = output.plot_writer("scaling.svg").unwrap() {
+            scaling::write_svg(&points, &mut plot).unwrap();
+        }
+    }
+
+    if cli.worst_case {
+        let mut visitor = worst_case::WorstCaseVisitor::new(cli.buckets, cli.reduction, &addresses);
+        for epoch in 0..cli.epochs {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        for worst in visitor.worst_cases() {
+            writeln!(output.report_writer("worst-case", worst_case::HEADER).unwrap(), "{worst}").unwrap();
+            eprintln!("{}", worst.guarantee());
+        }
+    }
+
+    if let Some(trials) = cli.grinding {
+        let mut visitor = grinding::GrindingVisitor::new(cli.buckets, cli.reduction, &addresses, trials, cli.rng_seed);
+        for epoch in 0..cli.epochs {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        for score in visitor.scores() {
+            writeln!(output.report_writer("grinding", grinding::HEADER).unwrap(), "{score}").unwrap();
+        }
+    }
+
+    if cli.compare_reductions {
+        let mut visitor = reduction_comparison::ReductionVisitor::new(cli.buckets, &addresses, cli.mode_tie_break);
+        for epoch in 0..cli.epochs {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        for score in visitor.scores() {
+            writeln!(output.report_writer("reductions", reduction_comparison::HEADER).unwrap(), "{score}").unwrap();
+        }
+    }
+
+    if cli.churn {
+        let mut visitor = churn::EpochChurnVisitor::new(cli.buckets, cli.reduction, &addresses);
+        for epoch in 0..cli.epochs {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        for churn in visitor.churns.iter() {
+            writeln!(output.report_writer("churn", churn::EPOCH_HEADER).unwrap(), "{churn}").unwrap();
+        }
+    }
+
+    if let Some(accounts) = cli.sybil {
+        let mut visitor = sybil::SybilVisitor::new(cli.buckets, cli.reduction, &addresses, accounts, cli.rng_seed);
+        for epoch in 0..cli.epochs {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        for impact in visitor.impacts() {
+            writeln!(output.report_writer("sybil", sybil::HEADER).unwrap(), "{impact}").unwrap();
+        }
+    }
+
+    if let Some(epoch) = cli.export_map {
+        let mut visitor = assignment_map::MapVisitor {
+            dir: &cli.map_dir,
+            epoch,
+            buckets: cli.buckets,
+            reduction: cli.reduction,
+            encoding: cli.map_index,
+            addresses: &addresses,
+            written: Vec::new(),
+        };
+        for_each_hasher(cli, epoch, &mut visitor);
+        for path in visitor.written {
+            eprintln!("wrote assignment map {}", path.display());
+        }
+    }
+
+    if let Some(dump) = cli.dump_assignments {
+        let per_hasher = hasher_names(cli).len() > 1;
+        let epochs = match dump {
+            assignment_map::DumpEpochs::Epoch(epoch) => epoch..epoch + 1,
+            assignment_map::DumpEpochs::All => 0..cli.epochs,
+        };
+        for epoch in epochs {
+            let epoch_suffix = (dump == assignment_map::DumpEpochs::All).then_some(epoch);
+            let mut visitor = assignment_map::DumpVisitor {
+                buckets: cli.buckets,
+                reduction: cli.reduction,
+                addresses: &addresses,
+                order: cli.intra_bucket_order.as_deref().map(|hasher| assignment_map::BucketOrder { hasher, seed: epoch_seed(cli, epoch) }),
+                path: |name| assignment_map::dump_path(&cli.out, per_hasher.then_some(name), epoch_suffix),
+                written: Vec::new(),
+            };
+            for_each_hasher(cli, epoch, &mut visitor);
+            for path in visitor.written {
+                eprintln!("wrote assignments {}", path.display());
+            }
         }
     }
 
-    for (name, time) in timings.into_iter() {
-        println!("{name}: {}",  (time / (EPOCHS as u32)).as_micros());
+    if let Some(epochs) = cli.lookahead {
+        let mut visitor = lookahead::LookaheadVisitor::new(cli.buckets, cli.reduction, &addresses);
+        for epoch in cli.lookahead_start..cli.lookahead_start + epochs {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        let header = lookahead::header(cli.lookahead_start, epochs);
+        for name in visitor.names() {
+            let out = output.report_writer(&format!("{name}-lookahead"), &header).unwrap();
+            for row in visitor.rows(name) {
+                writeln!(out, "{row}").unwrap();
+            }
+        }
+    }
+
+    if let Some(sample_rate) = cli.latency_sample {
+        let mut visitor = latency::LatencyVisitor::new(&addresses, sample_rate.get());
+        for epoch in 0..cli.epochs {
+            for_each_hasher(cli, epoch, &mut visitor);
+        }
+        for summary in visitor.summaries() {
+            writeln!(output.report_writer("latency", latency::HEADER).unwrap(), "{summary}").unwrap();
+        }
+    }
+
+    if cli.block_schedule {
+        let mut visitor = block_schedule::BlockScheduleVisitor::new(cli.buckets, cli.reduction, &addresses, weights.as_deref(), cli.block_account_limit);
+        for epoch in 0..cli.epochs {
+            for_each_hasher(cli, epoch, &mut visitor);
+            visitor.end_epoch();
+        }
+        for summary in visitor.summaries() {
+            writeln!(output.report_writer("blocks", block_schedule::HEADER).unwrap(), "{summary}").unwrap();
+        }
+    }
+
+    if cli.cu_model {
+        let mut visitor = cu_model::CuVisitor { schedule: cu_model::CURRENT_FEE_SCHEDULE, addresses: num_entries, buckets: cli.buckets, estimates: Vec::new() };
+        for_each_hasher(cli, 0, &mut visitor);
+        for estimate in visitor.estimates {
+            writeln!(output.report_writer("cu", cu_model::HEADER).unwrap(), "{estimate}").unwrap();
+        }
+    }
+
+    #[cfg(feature = "memory-report")]
+    for footprint in footprints.iter().flatten() {
+        writeln!(output.report_writer("memory", footprint::HEADER).unwrap(), "{footprint}").unwrap();
+    }
+
+    output.flush().unwrap();
+    sinks.flush().unwrap();
+    // Without a run directory the summary below already reports timings.
+    if cli.run_name.is_some() {
+        let mut log = output.log_writer().unwrap();
+        for (name, time) in timings.into_iter() {
+            writeln!(log, "{name}: {}",  (time / (cli.epochs as u32)).as_micros()).unwrap();
+        }
+    }
+    match cli.timings_out.as_deref() {
+        Some(path) => {
+            let mut out = std::io::BufWriter::new(create_file(path));
+            comparison.write_summary(&mut out).and_then(|()| out.flush()).unwrap();
+        }
+        None if !cli.quiet => comparison.write_summary(&mut std::io::stderr()).unwrap(),
+        None => {}
+    }
+}
+
+fn create_file(path: &std::path::Path) -> std::fs::File {
+    std::fs::File::create(path).unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, format!("{}: {err}", path.display())).exit())
+}
+
+fn open_sinks(cli: &Cli) -> sink::Sinks<'static> {
+    sink::Sinks(cli.sinks.iter().map(|spec| spec.open().unwrap_or_else(|err| Cli::command().error(clap::error::ErrorKind::Io, err).exit())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        let mut command = Cli::command();
+        Cli::command().debug_assert();
+        for subcommand in command.get_subcommands() {
+            let help = subcommand.get_after_help().map(ToString::to_string).unwrap_or_default();
+            assert!(help.contains(&format!("hash-to-bucket {}", subcommand.get_name())), "{} has no examples", subcommand.get_name());
+        }
+        for shell in [clap_complete::Shell::Bash, clap_complete::Shell::Zsh, clap_complete::Shell::Fish] {
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut command, "hash-to-bucket", &mut script);
+            assert!(String::from_utf8(script).unwrap().contains("check-pair"), "{shell}");
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_values() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["hash-to-bucket"].iter().chain(args)).map(|_| ()).map_err(|err| err.kind());
+        let rpc = ["--rpc-url", "http://localhost:8899", "--rpc-program", "Stake11111111111111111111111111111111111111"];
+        for rate in ["0", "-1", "inf"] {
+            assert_eq!(parse(&[&rpc[..], &[&format!("--rpc-rate={rate}")]].concat()), Err(clap::error::ErrorKind::ValueValidation), "{rate}");
+        }
+        assert_eq!(parse(&[&rpc[..], &["--rpc-rate", "0.5"]].concat()), Ok(()));
+        for alpha in ["0", "1", "1.5", "-0.1", "nan"] {
+            assert_eq!(parse(&[&format!("--significance={alpha}")]), Err(clap::error::ErrorKind::ValueValidation), "{alpha}");
+        }
+        assert_eq!(parse(&["--significance", "0.01"]), Ok(()));
+        for epochs in ["0", "1"] {
+            assert_eq!(parse(&["check-pair", "blake3", "xxh3", "--epochs", epochs]), Err(clap::error::ErrorKind::ValueValidation), "{epochs}");
+        }
+        assert_eq!(parse(&["check-pair", "blake3", "xxh3", "--epochs", "2"]), Ok(()));
+        #[cfg(feature = "geyser")]
+        {
+            let geyser = ["geyser", "--endpoint", "http://localhost:10000", "--program", "Stake11111111111111111111111111111111111111"];
+            assert_eq!(parse(&[&geyser[..], &["--interval", "0"]].concat()), Err(clap::error::ErrorKind::ValueValidation));
+            assert_eq!(parse(&geyser), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_hasher_aliases() {
+        let cli = Cli::try_parse_from(["hash-to-bucket", "--hasher", "sip13", "--hasher", "sip24", "--hasher", "murmur3", "--hasher", "xxh3"]).unwrap();
+        assert_eq!(cli.hashers, ["siphash13", "siphash24", "murmur3-128", "xxh3"]);
+        assert_eq!(hasher_names(&cli), ["siphash13", "siphash24", "murmur3-128", "xxh3"]);
+    }
+
+    #[test]
+    fn test_fold_duplicates() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (distinct, weights) = fold_duplicates([a, b, a, a].into_iter().map(Pubkey2).collect());
+        assert_eq!(distinct.iter().map(|address| **address).collect::<Vec<_>>(), vec![a, b]);
+        assert_eq!(weights, vec![3, 1]);
+    }
+
+    #[test]
+    fn test_exclude() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let kept = exclude([a, b, a, c].into_iter().map(Pubkey2).collect(), &[Pubkey2(a), Pubkey2(Pubkey::new_unique())]);
+        assert_eq!(kept.iter().map(|address| **address).collect::<Vec<_>>(), vec![b, c]);
+    }
+
+    #[test]
+    fn test_do_test_weights_match_duplicates() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let duplicated = [a, b, a, a].into_iter().map(Pubkey2).collect::<Vec<_>>();
+        let mut expected = vec![0; 4];
+        do_test(Blake3Hasher::new_with_seed(0), &mut expected, Reduction::Multiply, &duplicated, None);
+        let (distinct, weights) = fold_duplicates(duplicated);
+        let mut actual = vec![0; 4];
+        do_test(Blake3Hasher::new_with_seed(0), &mut actual, Reduction::Multiply, &distinct, Some(&weights));
+        assert_eq!(actual, expected);
     }
 }
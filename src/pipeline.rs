@@ -0,0 +1,175 @@
+use {
+    rayon::{prelude::*, ThreadPoolBuilder},
+    std::{
+        collections::BTreeMap,
+        num::NonZeroUsize,
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::Duration,
+    },
+};
+
+// Bounds the number of finished-but-unanalyzed bucket vectors held in memory
+// per hashing thread.
+const QUEUE_DEPTH_PER_THREAD: usize = 4;
+
+/// Bucket-count vectors recycled between epochs, so hashing threads don't
+/// allocate a fresh vector per epoch per hasher.
+#[derive(Default)]
+pub struct BufferPool(Mutex<Vec<Vec<usize>>>);
+
+impl BufferPool {
+    /// A zeroed vector of `len` counts.
+    pub fn take(&self, len: usize) -> Vec<usize> {
+        let mut buffer = self.0.lock().unwrap().pop().unwrap_or_default();
+        buffer.clear();
+        buffer.resize(len, 0);
+        buffer
+    }
+
+    pub fn give(&self, buffer: Vec<usize>) {
+        self.0.lock().unwrap().push(buffer);
+    }
+}
+
+struct Hashed {
+    epoch: u64,
+    index: usize,
+    hasher: &'static str,
+    buckets: Vec<usize>,
+    time: Duration,
+}
+
+struct Analyzed<R> {
+    epoch: u64,
+    index: usize,
+    hasher: &'static str,
+    row: R,
+    time: Duration,
+}
+
+/// Runs `hash` for every epoch on a rayon pool of `hash_threads` threads, or
+/// on the current pool if `None`, and hands each hasher's bucket counts to
+/// `analyze` on a separate pool of `io_threads` threads, so analysis and
+/// formatting don't steal time from hashing.
+/// `hash` must emit exactly `hashers_per_epoch` results per epoch, always in
+/// the same order; `write` then sees them in (epoch, emission) order
+/// regardless of which threads finished first. Bucket vectors should come
+/// from the pool passed to `hash`; they return to it once analyzed.
+pub fn run<H, A, W, R>(
+    epochs: u64,
+    hashers_per_epoch: usize,
+    hash_threads: Option<NonZeroUsize>,
+    io_threads: NonZeroUsize,
+    hash: H,
+    analyze: A,
+    mut write: W,
+) where
+    H: Fn(u64, &BufferPool, &mut dyn FnMut(&'static str, Vec<usize>, Duration)) + Sync,
+    A: Fn(u64, &[usize]) -> R + Sync,
+    W: FnMut(u64, &'static str, R, Duration),
+    R: Send,
+{
+    let hash_pool = hash_threads.map(|threads| ThreadPoolBuilder::new().num_threads(threads.get()).build().unwrap());
+    let hash_threads = hash_pool.as_ref().map_or_else(rayon::current_num_threads, |pool| pool.current_num_threads());
+    let (hashed_sender, hashed_receiver) = mpsc::sync_channel::<Hashed>(hash_threads * QUEUE_DEPTH_PER_THREAD);
+    let (analyzed_sender, analyzed_receiver) = mpsc::channel::<Analyzed<R>>();
+    // Owned only by the analysis threads, so that if they all die the hashing
+    // threads see a closed channel instead of blocking on a full one.
+    let hashed_receiver = Arc::new(Mutex::new(hashed_receiver));
+    let pool = BufferPool::default();
+
+    thread::scope(|scope| {
+        let (hash, buffers, hash_pool) = (&hash, &pool, &hash_pool);
+        scope.spawn(move || {
+            // Bridged so epochs start roughly in order, keeping few rows
+            // waiting on an earlier epoch to be written.
+            let hash_epochs = || {
+                (0..epochs).par_bridge().for_each_with(hashed_sender, |hashed_sender, epoch| {
+                    let mut index = 0;
+                    hash(epoch, buffers, &mut |hasher, buckets, time| {
+                        hashed_sender.send(Hashed { epoch, index, hasher, buckets, time }).unwrap();
+                        index += 1;
+                    });
+                    debug_assert_eq!(index, hashers_per_epoch);
+                })
+            };
+            match hash_pool {
+                Some(hash_pool) => hash_pool.install(hash_epochs),
+                None => hash_epochs(),
+            }
+        });
+
+        for _ in 0..io_threads.get() {
+            let analyzed_sender = analyzed_sender.clone();
+            let (hashed_receiver, analyze, pool) = (Arc::clone(&hashed_receiver), &analyze, &pool);
+            scope.spawn(move || loop {
+                let hashed = hashed_receiver.lock().unwrap().recv();
+                let Ok(Hashed { epoch, index, hasher, buckets, time }) = hashed else {
+                    break;
+                };
+                let row = analyze(epoch, &buckets);
+                pool.give(buckets);
+                analyzed_sender.send(Analyzed { epoch, index, hasher, row, time }).unwrap();
+            });
+        }
+        drop(analyzed_sender);
+        drop(hashed_receiver);
+
+        let mut pending = BTreeMap::new();
+        let mut next = (0, 0);
+        for analyzed in analyzed_receiver {
+            pending.insert((analyzed.epoch, analyzed.index), analyzed);
+            while let Some(Analyzed { hasher, row, time, epoch, .. }) = pending.remove(&next) {
+                write(epoch, hasher, row, time);
+                next = if next.1 + 1 == hashers_per_epoch { (next.0 + 1, 0) } else { (next.0, next.1 + 1) };
+            }
+        }
+        assert!(pending.is_empty(), "results missing from the pipeline");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(hash_threads: usize, io_threads: usize) -> Vec<(u64, &'static str, String)> {
+        let mut rows = Vec::new();
+        run(
+            10,
+            2,
+            NonZeroUsize::new(hash_threads),
+            NonZeroUsize::new(io_threads).unwrap(),
+            |epoch, pool, emit| {
+                let mut buckets = pool.take(1);
+                buckets[0] = epoch as usize;
+                emit("a", buckets, Duration::ZERO);
+                emit("b", vec![epoch as usize * 2], Duration::ZERO);
+            },
+            |epoch, buckets| format!("{epoch}:{buckets:?}"),
+            |epoch, hasher, row, _time| rows.push((epoch, hasher, row)),
+        );
+        rows
+    }
+
+    #[test]
+    fn test_buffer_pool_zeroes_recycled_buffers() {
+        let pool = BufferPool::default();
+        let mut buffer = pool.take(3);
+        buffer[1] = 7;
+        pool.give(buffer);
+        assert_eq!(pool.take(4), vec![0; 4]);
+        assert_eq!(pool.take(2), vec![0; 2]);
+    }
+
+    #[test]
+    fn test_run_ordering_independent_of_threads() {
+        let serial = collect(1, 1);
+        assert_eq!(serial.len(), 20);
+        assert_eq!(serial[0], (0, "a", "0:[0]".to_string()));
+        assert_eq!(serial[3], (1, "b", "1:[2]".to_string()));
+        assert_eq!(collect(3, 2), serial);
+        assert_eq!(collect(16, 5), serial);
+        assert_eq!(collect(0, 3), serial);
+    }
+}
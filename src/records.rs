@@ -0,0 +1,120 @@
+use {
+    crate::{
+        analysis::{BucketAnalysis, OccupancyAnalysis},
+        baseline::HasherSummary,
+        reduction::Reduction,
+    },
+    clap::ValueEnum,
+    serde::{Deserialize, Serialize},
+    std::borrow::Cow,
+};
+
+/// How per-epoch results and the final summary are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// CSV rows under a header, shaped by --format and --columns; the summary is a table on stderr
+    #[default]
+    Csv,
+    /// One JSON array of every epoch record followed by the summary records
+    Json,
+    /// One JSON record per line, epoch records followed by summary records
+    Jsonl,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// The run configuration every record carries.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RunConfig {
+    pub buckets: usize,
+    pub reduction: Reduction,
+    /// Input entries, duplicates included
+    pub entries: usize,
+    pub nonce: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Percentile {
+    pub percentile: f64,
+    pub count: usize,
+}
+
+/// One hasher's analysis of one epoch. The analysis's own percentiles are
+/// left empty when loaded; `percentiles` carries them.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct EpochRecord<'a> {
+    pub hasher: Cow<'static, str>,
+    pub config: Cow<'a, RunConfig>,
+    pub epoch: u64,
+    /// Seed the hasher was keyed with
+    pub seed: u64,
+    pub time_us: f64,
+    #[serde(flatten)]
+    pub analysis: BucketAnalysis,
+    pub percentiles: Vec<Percentile>,
+    /// Present when there are more buckets than entries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occupancy: Option<OccupancyAnalysis>,
+}
+
+/// Pairs the percentiles an analysis was computed with with its counts.
+pub fn percentiles(percentiles: &[f64], counts: &[usize]) -> Vec<Percentile> {
+    percentiles.iter().zip(counts).map(|(percentile, count)| Percentile { percentile: *percentile, count: *count }).collect()
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct SummaryRecord<'a> {
+    pub config: Cow<'a, RunConfig>,
+    #[serde(flatten)]
+    pub summary: HasherSummary,
+}
+
+/// A machine-readable output record, tagged by its `record` field.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum Record<'a> {
+    Epoch(Box<EpochRecord<'a>>),
+    Summary(SummaryRecord<'a>),
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn test_records() {
+        let config = RunConfig { buckets: 4, reduction: Reduction::Multiply, entries: 2, nonce: None };
+        let analysis = BucketAnalysis { max: 2, spread: 2, percentiles: vec![0, 2], ..BucketAnalysis::default() };
+        let occupancy = OccupancyAnalysis { occupied: 1, collisions: 1, expected_empty: 2.25 };
+        let percentiles = percentiles(&[10.0, 99.0], &analysis.percentiles);
+        let record = Record::Epoch(Box::new(EpochRecord { hasher: "blake3".into(), config: Cow::Borrowed(&config), epoch: 3, seed: 3, time_us: 5.0, analysis, percentiles, occupancy: Some(occupancy) }));
+        let value = serde_json::to_value(&record).unwrap();
+        assert_eq!(value["record"], "epoch");
+        assert_eq!(value["hasher"], "blake3");
+        assert_eq!(value["config"], json!({"buckets": 4, "reduction": "multiply", "entries": 2, "nonce": null}));
+        assert_eq!((&value["epoch"], &value["seed"], &value["time_us"]), (&json!(3), &json!(3), &json!(5.0)));
+        assert_eq!((&value["max"], &value["spread"], &value["p_value"]), (&json!(2), &json!(2), &json!(0.0)));
+        assert_eq!(value["percentiles"], json!([{"percentile": 10.0, "count": 0}, {"percentile": 99.0, "count": 2}]));
+        assert_eq!(value["occupancy"]["collisions"], 1);
+        // Loaded back, only the analysis's percentiles are missing, carried by the record instead.
+        let Record::Epoch(loaded) = serde_json::from_value::<Record>(value).unwrap() else { panic!("not an epoch record") };
+        let Record::Epoch(mut epoch) = record else { unreachable!() };
+        epoch.analysis.percentiles.clear();
+        assert_eq!(loaded, epoch);
+
+        let summary = HasherSummary { hasher: "xxh3".into(), portable: true, epochs: 2, spread: 1.0, std_dev: 0.5, max_over_mean: 1.5, empty_buckets: 0.0, time_us: 3.0 };
+        let record = Record::Summary(SummaryRecord { config: Cow::Borrowed(&config), summary });
+        let value = serde_json::to_value(&record).unwrap();
+        assert_eq!((&value["record"], &value["hasher"], &value["epochs"]), (&json!("summary"), &json!("xxh3"), &json!(2)));
+        assert_eq!(value["config"]["buckets"], 4);
+        assert_eq!(serde_json::from_str::<Record>(&serde_json::to_string(&record).unwrap()).unwrap(), record);
+    }
+}
@@ -0,0 +1,300 @@
+use {
+    clap::ValueEnum,
+    serde::{Deserialize, Serialize},
+    std::{
+        cell::RefCell,
+        hint::black_box,
+        sync::{Arc, Mutex},
+        time::Instant,
+    },
+};
+
+/// Points each bucket places on the hash ring.
+pub const RING_REPLICAS: u64 = 64;
+
+/// Most buckets the ring reduction takes; each bucket's points take
+/// `RING_REPLICAS * 16` bytes, so a ring of this many takes 64 MiB.
+pub const MAX_RING_BUCKETS: usize = 1 << 16;
+
+// Rings kept built, the least recently used dropped first.
+const RING_CACHE_CAPACITY: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Reduction {
+    /// `floor(buckets * hash / 2^64)`, i.e. the high bits of the hash
+    #[default]
+    Multiply,
+    /// `hash & (buckets - 1)`, i.e. the low bits; power-of-two bucket counts only
+    Mask,
+    /// `hash % buckets`
+    Modulo,
+    /// `floor(buckets * (hash mod 2^32) / 2^32)`, multiply-shift over the low
+    /// 32 bits only; at most 2^32 buckets
+    Fastrange32,
+    /// Lamping and Veach's jump consistent hash, seeded with the hash
+    Jump,
+    /// The first of the buckets' points on a hash ring at or after the hash,
+    /// each bucket placing [`RING_REPLICAS`] points; at most
+    /// [`MAX_RING_BUCKETS`] buckets
+    Ring,
+}
+
+impl Reduction {
+    pub fn validate(self, buckets: usize) -> Result<(), String> {
+        match self {
+            Self::Mask if !buckets.is_power_of_two() => {
+                Err(format!("mask reduction needs a power-of-two bucket count, got {buckets}"))
+            }
+            Self::Fastrange32 if buckets as u64 > 1 << 32 => {
+                Err(format!("fastrange32 reduction supports at most 2^32 buckets, got {buckets}"))
+            }
+            Self::Ring if buckets > MAX_RING_BUCKETS => {
+                Err(format!("ring reduction supports at most {MAX_RING_BUCKETS} buckets, got {buckets}; jump is consistent at any count"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub fn reduce(self, buckets: usize, hash: u64) -> usize {
+        match self {
+            Self::Multiply => reduce(buckets, hash),
+            Self::Mask => (hash & (buckets as u64 - 1)) as usize,
+            Self::Modulo => (hash % buckets as u64) as usize,
+            Self::Fastrange32 => ((hash as u32 as u64 * buckets as u64) >> 32) as usize,
+            Self::Jump => jump(buckets, hash),
+            Self::Ring => ring(buckets, hash),
+        }
+    }
+
+    /// [`reduce`](Self::reduce) written out, e.g. `floor(100 * 1234 / 2^64) = 0`.
+    pub fn arithmetic(self, buckets: usize, hash: u64) -> String {
+        let bucket = self.reduce(buckets, hash);
+        match self {
+            Self::Multiply => format!("floor({buckets} * {hash} / 2^64) = {bucket}"),
+            Self::Mask => format!("{hash} & {} = {bucket}", buckets - 1),
+            Self::Modulo => format!("{hash} % {buckets} = {bucket}"),
+            Self::Fastrange32 => format!("floor({buckets} * ({hash} mod 2^32) / 2^32) = {bucket}"),
+            Self::Jump => format!("jump_consistent_hash({hash}, {buckets}) = {bucket}"),
+            Self::Ring => format!("ring_successor({hash}, {buckets} x {RING_REPLICAS} points) = {bucket}"),
+        }
+    }
+
+    /// Whether growing the bucket count by one moves only the addresses the
+    /// new bucket takes, about `1 / buckets` of them.
+    pub fn is_consistent(self) -> bool {
+        matches!(self, Self::Jump | Self::Ring)
+    }
+}
+
+/// Jump consistent hash (Lamping and Veach, 2014), with `hash` as the key.
+pub fn jump(buckets: usize, hash: u64) -> usize {
+    let (mut key, mut bucket, mut next) = (hash, -1i64, 0i64);
+    while next < buckets as i64 {
+        bucket = next;
+        key = key.wrapping_mul(2_862_933_555_777_941_757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    bucket as usize
+}
+
+// SplitMix64's finalizer, placing bucket `bucket`'s `replica`-th point.
+fn ring_point(bucket: u64, replica: u64) -> u64 {
+    let mut z = (bucket << 32 | replica).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// (point, bucket) pairs sorted by point; buckets past MAX_RING_BUCKETS are
+// rejected by Reduction::validate.
+type Ring = Arc<[(u64, u32)]>;
+
+// Rings by bucket count, most recently used last, shared by every thread.
+static RINGS: Mutex<Vec<(usize, Ring)>> = Mutex::new(Vec::new());
+
+thread_local! {
+    // The ring this thread last used, so repeated lookups skip the lock.
+    static LAST_RING: RefCell<Option<(usize, Ring)>> = const { RefCell::new(None) };
+}
+
+fn build_ring(buckets: usize) -> Ring {
+    let mut points = (0..buckets as u64)
+        .flat_map(|bucket| (0..RING_REPLICAS).map(move |replica| (ring_point(bucket, replica), bucket as u32)))
+        .collect::<Vec<_>>();
+    points.sort_unstable();
+    points.into()
+}
+
+// Moves `buckets`' ring to the most recently used end of `rings`, if cached.
+fn take_ring(rings: &mut Vec<(usize, Ring)>, buckets: usize) -> Option<Ring> {
+    let (_, ring) = rings.remove(rings.iter().position(|(cached, _)| *cached == buckets)?);
+    rings.push((buckets, Arc::clone(&ring)));
+    Some(ring)
+}
+
+// Built without holding the lock, so other threads' lookups aren't held up;
+// a ring another thread cached meanwhile is used instead.
+fn cached_ring(buckets: usize) -> Ring {
+    if let Some(ring) = take_ring(&mut RINGS.lock().unwrap(), buckets) {
+        return ring;
+    }
+    let ring = build_ring(buckets);
+    let mut rings = RINGS.lock().unwrap();
+    take_ring(&mut rings, buckets).unwrap_or_else(|| {
+        if rings.len() == RING_CACHE_CAPACITY {
+            rings.remove(0);
+        }
+        rings.push((buckets, Arc::clone(&ring)));
+        ring
+    })
+}
+
+/// The bucket owning the first ring point at or after `hash`, wrapping
+/// around. A bucket's points don't depend on the bucket count, so adding a
+/// bucket only takes over the arcs its own points land on.
+pub fn ring(buckets: usize, hash: u64) -> usize {
+    let points = LAST_RING.with(|last| {
+        let mut last = last.borrow_mut();
+        match &*last {
+            Some((cached, ring)) if *cached == buckets => Arc::clone(ring),
+            _ => Arc::clone(&last.insert((buckets, cached_ring(buckets))).1),
+        }
+    });
+    let next = points.partition_point(|(point, _)| *point < hash);
+    points[next % points.len()].1 as usize
+}
+
+/// Maps a 64-bit hash onto `buckets` buckets by multiply-shift:
+/// `floor(buckets * hash / 2^64)`.
+#[inline]
+pub fn reduce(buckets: usize, hash: u64) -> usize {
+    (((buckets as u128) * (hash as u128)) >> 64) as usize
+}
+
+/// [`reduce`] with the bucket count fixed at compile time. For power-of-two
+/// counts multiply-shift degenerates to keeping the top `log2(M)` bits, which
+/// is what this compiles to; otherwise the multiplier is at least a constant.
+/// Results are identical to `reduce(M, hash)`.
+#[inline]
+pub fn assign_bucket<const M: usize>(hash: u64) -> usize {
+    const { assert!(M > 0, "bucket count must be non-zero") };
+    if M == 1 {
+        0
+    } else if M.is_power_of_two() {
+        (hash >> (64 - M.trailing_zeros())) as usize
+    } else {
+        reduce(M, hash)
+    }
+}
+
+#[derive(Debug)]
+pub struct ReductionTimings {
+    pub generic_ns: f64,
+    pub const_ns: f64,
+}
+
+/// Nanoseconds per reduction of `hashes` through the runtime and const
+/// generic paths, best of `rounds`.
+pub fn time_reductions<const M: usize>(hashes: &[u64], rounds: usize) -> ReductionTimings {
+    let time = |reduce: &dyn Fn(u64) -> usize| {
+        (0..rounds.max(1))
+            .map(|_| {
+                let start = Instant::now();
+                let mut sum = 0usize;
+                for hash in hashes {
+                    sum = sum.wrapping_add(reduce(*hash));
+                }
+                black_box(sum);
+                start.elapsed().as_nanos() as f64 / hashes.len().max(1) as f64
+            })
+            .fold(f64::INFINITY, f64::min)
+    };
+    let buckets = black_box(M);
+    ReductionTimings {
+        generic_ns: time(&|hash| reduce(buckets, hash)),
+        const_ns: time(&|hash| assign_bucket::<M>(hash)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASHES: &[u64] = &[0, 1, 0x7fff_ffff_ffff_ffff, 0x8000_0000_0000_0000, 0xdead_beef_cafe_f00d, u64::MAX];
+
+    #[test]
+    fn test_assign_bucket_matches_reduce() {
+        for hash in HASHES {
+            assert_eq!(assign_bucket::<1>(*hash), reduce(1, *hash));
+            assert_eq!(assign_bucket::<2>(*hash), reduce(2, *hash));
+            assert_eq!(assign_bucket::<100>(*hash), reduce(100, *hash));
+            assert_eq!(assign_bucket::<4096>(*hash), reduce(4096, *hash));
+            assert_eq!(assign_bucket::<432_000>(*hash), reduce(432_000, *hash));
+        }
+    }
+
+    #[test]
+    fn test_mask_reduction() {
+        assert_eq!(Reduction::Mask.validate(4096), Ok(()));
+        assert!(Reduction::Mask.validate(100).is_err());
+        assert_eq!(Reduction::Multiply.validate(100), Ok(()));
+        assert_eq!(Reduction::Mask.reduce(1, u64::MAX), 0);
+        assert_eq!(Reduction::Mask.reduce(16, 0xdead_beef_cafe_f00d), 0xd);
+        assert_eq!(Reduction::Multiply.reduce(16, 0xdead_beef_cafe_f00d), 0xd);
+        assert_eq!(Reduction::Mask.reduce(4096, u64::MAX), 4095);
+    }
+
+    #[test]
+    fn test_modulo_and_fastrange32() {
+        assert_eq!(Reduction::Modulo.reduce(100, 1234), 34);
+        assert_eq!(Reduction::Fastrange32.reduce(100, 0xffff_ffff_0000_0000), 0);
+        assert_eq!(Reduction::Fastrange32.reduce(100, u32::MAX as u64), 99);
+        assert_eq!(Reduction::Fastrange32.reduce(16, 0xdead_beef_cafe_f00d), 0xc);
+        assert!(Reduction::Fastrange32.validate(1 << 32).is_ok());
+        assert!(Reduction::Fastrange32.validate((1 << 32) + 1).is_err());
+    }
+
+    #[test]
+    fn test_jump() {
+        assert_eq!(jump(1, 0xdead_beef), 0);
+        assert_eq!(jump(10, 0), 0);
+        for hash in HASHES {
+            assert!(Reduction::Jump.reduce(100, *hash) < 100);
+        }
+    }
+
+    #[test]
+    fn test_consistent_reductions_move_few() {
+        let hashes = (0u64..10_000).map(|i| ring_point(i, 0)).collect::<Vec<_>>();
+        for reduction in [Reduction::Jump, Reduction::Ring] {
+            assert!(reduction.is_consistent());
+            let moved = hashes.iter().filter(|hash| reduction.reduce(10, **hash) != reduction.reduce(11, **hash)).count();
+            // Only the new bucket's share moves, and only into it.
+            assert!(moved < 10_000 / 11 * 2, "{reduction:?} moved {moved}");
+            assert!(hashes.iter().all(|hash| reduction.reduce(10, *hash) == reduction.reduce(11, *hash) || reduction.reduce(11, *hash) == 10));
+        }
+        let moved = hashes.iter().filter(|hash| reduce(10, **hash) != reduce(11, **hash)).count();
+        assert!(moved > 10_000 / 3);
+    }
+
+    #[test]
+    fn test_ring_cache_is_bounded() {
+        let first = (0..100).map(|hash| ring(3, hash << 56)).collect::<Vec<_>>();
+        for buckets in 1..=RING_CACHE_CAPACITY * 2 {
+            assert!(ring(buckets, u64::MAX) < buckets);
+            assert!(RINGS.lock().unwrap().len() <= RING_CACHE_CAPACITY);
+        }
+        assert_eq!((0..100).map(|hash| ring(3, hash << 56)).collect::<Vec<_>>(), first);
+        assert!(Reduction::Ring.validate(MAX_RING_BUCKETS).is_ok());
+        assert!(Reduction::Ring.validate(MAX_RING_BUCKETS + 1).is_err());
+    }
+
+    #[test]
+    fn test_reduce_bounds() {
+        assert_eq!(reduce(100, 0), 0);
+        assert_eq!(reduce(100, u64::MAX), 99);
+        assert_eq!(reduce(4096, u64::MAX), 4095);
+    }
+}
@@ -0,0 +1,156 @@
+use {
+    crate::{
+        analysis::{analyze_buckets_streaming, ModeTieBreak},
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    clap::ValueEnum,
+    std::{collections::HashMap, fmt},
+};
+
+pub const HEADER: &str = "hasher,reduction,epochs,mean_spread,mean_std_dev,mean_max_over_mean,mean_chi_squared,min_p_value";
+
+/// One hasher's distribution under one reduction, averaged over the epochs
+/// run, except `min_p_value`, the least uniform epoch's.
+#[derive(Debug, PartialEq)]
+pub struct ReductionScore {
+    pub hasher: &'static str,
+    pub reduction: Reduction,
+    pub epochs: u64,
+    pub mean_spread: f64,
+    pub mean_std_dev: f64,
+    pub mean_max_over_mean: f64,
+    pub mean_chi_squared: f64,
+    pub min_p_value: f64,
+}
+
+impl fmt::Display for ReductionScore {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let reduction = self.reduction.to_possible_value().unwrap();
+        write!(
+            formatter,
+            "{},{},{},{},{},{},{},{}",
+            self.hasher,
+            reduction.get_name(),
+            self.epochs,
+            self.mean_spread,
+            self.mean_std_dev,
+            self.mean_max_over_mean,
+            self.mean_chi_squared,
+            self.min_p_value
+        )
+    }
+}
+
+/// Hashes every address once per visit and buckets the hashes under every
+/// reduction valid for the bucket count, so the reductions differ only in
+/// how they map the same hashes.
+pub struct ReductionVisitor<'a> {
+    buckets: usize,
+    reductions: Vec<Reduction>,
+    addresses: &'a [Pubkey2],
+    tie_break: ModeTieBreak,
+    names: Vec<&'static str>,
+    scores: HashMap<&'static str, Vec<ReductionScore>>,
+}
+
+impl<'a> ReductionVisitor<'a> {
+    pub fn new(buckets: usize, addresses: &'a [Pubkey2], tie_break: ModeTieBreak) -> Self {
+        let reductions = Reduction::value_variants().iter().copied().filter(|reduction| reduction.validate(buckets).is_ok()).collect();
+        Self { buckets, reductions, addresses, tie_break, names: Vec::new(), scores: HashMap::new() }
+    }
+
+    /// Each hasher's score under each reduction, hashers in visiting order.
+    pub fn scores(&self) -> Vec<&ReductionScore> {
+        self.names.iter().flat_map(|name| &self.scores[name]).collect()
+    }
+}
+
+impl HasherVisitor for ReductionVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let hashes = self
+            .addresses
+            .iter()
+            .map(|address| {
+                let mut hasher = hasher.clone();
+                hasher.write(address.as_ref());
+                hasher.finish()
+            })
+            .collect::<Vec<_>>();
+        let scores = self.scores.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            self.reductions
+                .iter()
+                .map(|reduction| ReductionScore {
+                    hasher: name,
+                    reduction: *reduction,
+                    epochs: 0,
+                    mean_spread: 0.0,
+                    mean_std_dev: 0.0,
+                    mean_max_over_mean: 0.0,
+                    mean_chi_squared: 0.0,
+                    min_p_value: 1.0,
+                })
+                .collect()
+        });
+        for score in scores {
+            let mut counts = vec![0; self.buckets];
+            for hash in &hashes {
+                counts[score.reduction.reduce(self.buckets, *hash)] += 1;
+            }
+            let analysis = analyze_buckets_streaming(&counts, self.tie_break, &[]);
+            // Running means, so scores stay readable between visits.
+            score.epochs += 1;
+            let weight = 1.0 / score.epochs as f64;
+            score.mean_spread += (analysis.spread as f64 - score.mean_spread) * weight;
+            score.mean_std_dev += (analysis.std_dev - score.mean_std_dev) * weight;
+            score.mean_max_over_mean += (analysis.max_over_mean - score.mean_max_over_mean) * weight;
+            score.mean_chi_squared += (analysis.chi_squared - score.mean_chi_squared) * weight;
+            score.min_p_value = score.min_p_value.min(analysis.p_value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::hashers::{Blake3Hasher, Murmur3x32Hasher, SeedableHasher},
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_scores_per_valid_reduction() {
+        let addresses = (0..2000).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = ReductionVisitor::new(10, &addresses, ModeTieBreak::default());
+        for epoch in 0..2 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+        }
+        let scores = visitor.scores();
+        // Mask needs a power-of-two bucket count.
+        assert_eq!(
+            scores.iter().map(|score| score.reduction).collect::<Vec<_>>(),
+            vec![Reduction::Multiply, Reduction::Modulo, Reduction::Fastrange32, Reduction::Jump, Reduction::Ring]
+        );
+        assert!(scores.iter().all(|score| score.epochs == 2 && score.mean_max_over_mean > 1.0));
+        assert!(scores[0].to_string().starts_with("blake3,multiply,2,"));
+        assert!(scores[2].to_string().starts_with("blake3,fastrange32,2,"));
+    }
+
+    #[test]
+    fn test_murmur3_x32_low_bit_reductions() {
+        let addresses = (0..16_000).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = ReductionVisitor::new(16, &addresses, ModeTieBreak::default());
+        for epoch in 0..2 {
+            visitor.visit("murmur3-32", &Murmur3x32Hasher::seed_key(epoch), Murmur3x32Hasher::with_seed(epoch));
+        }
+        let scores = visitor.scores();
+        assert_eq!(scores.len(), Reduction::value_variants().len());
+        for score in &scores {
+            // All of the addresses in one bucket would be a max_over_mean of 16.
+            assert!(score.mean_max_over_mean < 2.0, "{score}");
+            assert!(score.to_string().starts_with(&format!("murmur3-32,{},2,", score.reduction.to_possible_value().unwrap().get_name())));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use {
+    crate::{address_to_bucket_with_epoch_hasher, hashers::Blake3Hasher, Pubkey2},
+    std::{
+        io::{self, BufRead, BufReader, Write},
+        process::{Command, Stdio},
+        thread,
+    },
+};
+
+// Reference implementations are driven over a line protocol: for every
+// (epoch, address) pair we write `<hasher> <epoch> <buckets> <base58 pubkey>`
+// to the command's stdin and expect exactly one decimal bucket index per line
+// on its stdout, in the same order.
+const REFERENCE_HASHER: &str = "blake3";
+
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    pub epoch: u64,
+    pub address: String,
+    pub expected: usize,
+    pub actual: usize,
+}
+
+#[derive(Debug)]
+pub struct ReferenceReport {
+    pub checked: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+pub fn sample(addresses: &[Pubkey2], sample_size: usize) -> impl Iterator<Item = &Pubkey2> {
+    let stride = (addresses.len() / sample_size.max(1)).max(1);
+    addresses.iter().step_by(stride).take(sample_size)
+}
+
+pub fn check_reference(
+    command: &str,
+    addresses: &[Pubkey2],
+    buckets: usize,
+    epochs: u64,
+    sample_size: usize,
+) -> io::Result<ReferenceReport> {
+    let mut cases = Vec::new();
+    for epoch in 0..epochs {
+        for address in sample(addresses, sample_size) {
+            let expected = address_to_bucket_with_epoch_hasher(buckets, Blake3Hasher::new_with_seed(epoch), address);
+            cases.push((epoch, address.to_string(), expected));
+        }
+    }
+
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let requests = cases
+        .iter()
+        .map(|(epoch, address, _)| format!("{REFERENCE_HASHER} {epoch} {buckets} {address}\n"))
+        .collect::<String>();
+    // Feed stdin from another thread so a reference that answers as it reads
+    // can't deadlock against a full stdout pipe.
+    let writer = thread::spawn(move || stdin.write_all(requests.as_bytes()));
+
+    let mut mismatches = Vec::new();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    for (epoch, address, expected) in cases.iter() {
+        let line = lines.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "reference exited before answering every request")
+        })??;
+        let actual = line.trim().parse::<usize>().map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("bad reference output {line:?}: {err}"))
+        })?;
+        if actual != *expected {
+            mismatches.push(Mismatch { epoch: *epoch, address: address.clone(), expected: *expected, actual });
+        }
+    }
+    writer.join().unwrap()?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("reference exited with {status}")));
+    }
+
+    Ok(ReferenceReport { checked: cases.len(), mismatches })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use {super::*, solana_sdk::pubkey::Pubkey};
+
+    fn addresses(count: usize) -> Vec<Pubkey2> {
+        (0..count).map(|_| Pubkey2(Pubkey::new_unique())).collect()
+    }
+
+    #[test]
+    fn test_check_reference_agrees() {
+        let report = check_reference("awk '{ print 0 }'", &addresses(10), 1, 2, 5).unwrap();
+        assert_eq!(report.checked, 10);
+        assert!(report.mismatches.is_empty());
+    }
+
+    #[test]
+    fn test_check_reference_disagrees() {
+        let report = check_reference("awk '{ print 1 }'", &addresses(4), 1, 1, 4).unwrap();
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.mismatches.len(), 4);
+        assert_eq!(report.mismatches[0].actual, 1);
+    }
+
+    #[test]
+    fn test_check_reference_short_output() {
+        assert!(check_reference("head -n 1 | awk '{ print 0 }'", &addresses(4), 1, 1, 4).is_err());
+    }
+
+    #[test]
+    fn test_check_reference_exit_status() {
+        assert!(check_reference("awk '{ print 0 }'; exit 3", &addresses(4), 1, 1, 4).is_err());
+    }
+}
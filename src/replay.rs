@@ -0,0 +1,96 @@
+use {
+    crate::{
+        hashers::{Blake3Hasher, HasherVisitor},
+        rpc::{RpcClient, RpcError},
+    },
+    serde::{de, Deserialize, Deserializer},
+    serde_json::json,
+    solana_sdk::{epoch_schedule::EpochSchedule, hash::Hash},
+    std::{hash::Hasher, path::Path},
+};
+
+/// The blockhash that seeds an epoch's reward partitioning: the parent of the
+/// epoch's first block.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct EpochSeed {
+    pub epoch: u64,
+    #[serde(deserialize_with = "de_stringified_hash")]
+    pub blockhash: Hash,
+}
+
+fn de_stringified_hash<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hash, D::Error> {
+    String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+}
+
+/// Reads a JSON array of `{epoch, blockhash}`.
+pub fn load_epoch_seeds(path: &Path) -> std::io::Result<Vec<EpochSeed>> {
+    let file = std::fs::File::open(path)?;
+    Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+}
+
+/// Seeds of the `count` epochs up to and including the cluster's current one.
+pub fn fetch_epoch_seeds(client: &mut RpcClient, count: u64) -> Result<Vec<EpochSeed>, RpcError> {
+    let schedule = client.call("getEpochSchedule", json!([]))?;
+    let schedule: EpochSchedule = serde_json::from_value(schedule.clone()).map_err(|_| RpcError::Response(schedule.to_string()))?;
+    let info = client.call("getEpochInfo", json!([]))?;
+    let current = info["epoch"].as_u64().ok_or_else(|| RpcError::Response(info.to_string()))?;
+    let mut seeds = Vec::new();
+    for epoch in current.saturating_sub(count.saturating_sub(1))..=current {
+        let slots = client.call("getBlocksWithLimit", json!([schedule.get_first_slot_in_epoch(epoch), 1]))?;
+        let slot = slots[0].as_u64().ok_or_else(|| RpcError::Response(slots.to_string()))?;
+        let block = client.call("getBlock", json!([slot, {"transactionDetails": "none", "rewards": false, "maxSupportedTransactionVersion": 0}]))?;
+        let blockhash = block["previousBlockhash"].as_str().and_then(|hash| hash.parse().ok()).ok_or_else(|| RpcError::Response(block.to_string()))?;
+        seeds.push(EpochSeed { epoch, blockhash });
+    }
+    Ok(seeds)
+}
+
+/// Visits the hashers keyed directly by a real epoch seed: SipHash-1-3 fed
+/// the blockhash ahead of each address, as the runtime partitions rewards,
+/// and blake3 keyed with the blockhash.
+pub fn for_each_seeded_hasher(blockhash: &Hash, visitor: &mut impl HasherVisitor) {
+    let mut siphash = siphasher::sip::SipHasher13::new();
+    siphash.write(blockhash.as_ref());
+    visitor.visit("siphash13-blockhash", blockhash.as_ref(), siphash);
+    visitor.visit("blake3-blockhash", blockhash.as_ref(), Blake3Hasher::new_with_key(&blockhash.to_bytes()));
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{hashers::AnalysisHasher, reduction},
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    struct Buckets(Vec<(&'static str, usize)>, Pubkey);
+
+    impl HasherVisitor for Buckets {
+        fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], mut hasher: H) {
+            hasher.write(self.1.as_ref());
+            self.0.push((name, reduction::reduce(16, hasher.finish())));
+        }
+    }
+
+    #[test]
+    fn test_siphash_matches_runtime_partitioning() {
+        let blockhash = Hash::new_unique();
+        let address = Pubkey::new_unique();
+        let mut visitor = Buckets(Vec::new(), address);
+        for_each_seeded_hasher(&blockhash, &mut visitor);
+        let mut hasher = siphasher::sip::SipHasher13::new();
+        hasher.write(blockhash.as_ref());
+        hasher.write(address.as_ref());
+        let expected = (16u128 * hasher.finish() as u128 / (u64::MAX as u128 + 1)) as usize;
+        assert_eq!(visitor.0[0], ("siphash13-blockhash", expected));
+        assert_eq!(visitor.0[1].0, "blake3-blockhash");
+    }
+
+    #[test]
+    fn test_parse_epoch_seeds() {
+        let blockhash = Hash::new_unique();
+        let seeds: Vec<EpochSeed> = serde_json::from_str(&format!(r#"[{{"epoch": 600, "blockhash": "{blockhash}"}}]"#)).unwrap();
+        assert_eq!(seeds, vec![EpochSeed { epoch: 600, blockhash }]);
+        assert!(serde_json::from_str::<Vec<EpochSeed>>(r#"[{"epoch": 600, "blockhash": "not base58!"}]"#).is_err());
+    }
+}
@@ -0,0 +1,350 @@
+#[cfg(feature = "sqlite")]
+use rusqlite::{Connection, OpenFlags};
+use {
+    crate::{
+        analysis::{BucketAnalysis, OccupancyAnalysis, HEADER, LONG_HEADER, OCCUPANCY_HEADER},
+        records::{Percentile, Record},
+    },
+    serde_json::{Map, Value},
+    std::{collections::HashMap, fs, ops::RangeBounds, path::Path},
+};
+
+// Columns identifying which configuration a CSV row belongs to besides the
+// epoch, joined with `/` into its hasher as `compare` does.
+const KEY_COLUMNS: &[&str] = &["hasher", "buckets", "seed", "reduction"];
+
+/// One hasher's analysis of one epoch.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultRow {
+    pub hasher: String,
+    pub epoch: u64,
+    /// Columns of [`HEADER`] the results left out, as `--columns` and
+    /// `--no-mode-columns` do, are zero; the analysis's own percentiles are
+    /// empty, `percentiles` carries them
+    pub analysis: BucketAnalysis,
+    pub percentiles: Vec<Percentile>,
+    pub occupancy: Option<OccupancyAnalysis>,
+    /// Only JSON records carry timings
+    pub time_us: Option<f64>,
+    /// The columns of [`HEADER`] the results held
+    pub columns: Vec<&'static str>,
+}
+
+impl ResultRow {
+    /// A row from its columns by name, numbers whatever their source; columns
+    /// of [`HEADER`], percentile columns and occupancy columns.
+    fn from_columns<'a>(hasher: String, epoch: u64, values: impl IntoIterator<Item = (&'a str, f64)>) -> Result<Self, String> {
+        let Value::Object(mut analysis) = serde_json::to_value(BucketAnalysis::default()).unwrap() else { unreachable!() };
+        let (mut occupancy, mut percentiles, mut columns) = (Map::new(), Vec::new(), Vec::new());
+        for (column, value) in values {
+            // Counts may have been stored as floats, as SQLite does.
+            let number = if value >= 0.0 && value.fract() == 0.0 && value <= u64::MAX as f64 { Value::from(value as u64) } else { Value::from(value) };
+            if let Some(column) = HEADER.split(',').find(|known| *known == column) {
+                analysis.insert(column.to_string(), number);
+                columns.push(column);
+            } else if OCCUPANCY_HEADER.split(',').any(|known| known == column) {
+                occupancy.insert(column.to_string(), number);
+            } else if let Some(percentile) = column.strip_prefix('p').and_then(|percentile| percentile.parse::<f64>().ok()) {
+                let count = number.as_u64().ok_or_else(|| format!("{column} of {value} isn't a count"))?;
+                percentiles.push(Percentile { percentile, count: count as usize });
+            } else {
+                return Err(format!("unknown column {column}"));
+            }
+        }
+        let analysis = serde_json::from_value(Value::Object(analysis)).map_err(|err| err.to_string())?;
+        let occupancy = match occupancy.is_empty() {
+            true => None,
+            false => Some(serde_json::from_value(Value::Object(occupancy)).map_err(|err| format!("occupancy: {err}"))?),
+        };
+        Ok(Self { hasher, epoch, analysis, percentiles, occupancy, time_us: None, columns })
+    }
+
+    /// Every metric the results held, named as their columns are: those of
+    /// [`HEADER`], percentile columns such as `p99`, occupancy columns and
+    /// `time_us`.
+    pub fn metrics(&self) -> Vec<(String, f64)> {
+        let analysis = HEADER.split(',').zip(self.analysis.metrics()).filter(|(column, _)| self.columns.contains(column));
+        let percentiles = self.percentiles.iter().map(|percentile| (format!("p{}", percentile.percentile), percentile.count as f64));
+        let occupancy = self.occupancy.iter().flat_map(|occupancy| {
+            [("occupied", occupancy.occupied as f64), ("collisions", occupancy.collisions as f64), ("expected_empty", occupancy.expected_empty)]
+        });
+        analysis
+            .map(|(column, metric)| (column.to_string(), metric.value()))
+            .chain(percentiles)
+            .chain(occupancy.map(|(column, value)| (column.to_string(), value)))
+            .chain(self.time_us.map(|time_us| ("time_us".to_string(), time_us)))
+            .collect()
+    }
+
+    pub fn metric(&self, metric: &str) -> Option<f64> {
+        self.metrics().into_iter().find(|(name, _)| name == metric).map(|(_, value)| value)
+    }
+}
+
+/// Per-epoch results in the order they were written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Results {
+    pub rows: Vec<ResultRow>,
+}
+
+impl Results {
+    /// Loads a results file by its extension: CSV, wide or long, JSON or
+    /// JSON Lines records or sink rows, or a SQLite sink database. A run
+    /// directory loads every hasher its manifest lists. Rows without a
+    /// hasher are attributed to the file's stem.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let error = |err: String| format!("{}: {err}", path.display());
+        if path.is_dir() {
+            return Self::load_run_dir(path);
+        }
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        match path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            #[cfg(feature = "sqlite")]
+            Some("db" | "sqlite" | "sqlite3") => Self::load_sqlite(path).map_err(error),
+            #[cfg(not(feature = "sqlite"))]
+            Some("db" | "sqlite" | "sqlite3") => Err(error("loading SQLite results needs the sqlite feature".to_string())),
+            Some("json" | "jsonl" | "ndjson") => Self::parse_json(&fs::read_to_string(path).map_err(|err| error(err.to_string()))?, &stem).map_err(error),
+            _ => Self::parse_csv(&fs::read_to_string(path).map_err(|err| error(err.to_string()))?, &stem).map_err(error),
+        }
+    }
+
+    fn load_run_dir(dir: &Path) -> Result<Self, String> {
+        let manifest = dir.join("manifest.json");
+        let manifest = fs::read_to_string(&manifest).map_err(|err| format!("{}: {err}", manifest.display()))?;
+        let manifest = serde_json::from_str::<Value>(&manifest).map_err(|err| format!("{}: {err}", dir.display()))?;
+        let hashers = manifest["hashers"].as_array().ok_or_else(|| format!("{}: manifest lists no hashers", dir.display()))?;
+        let results = dir.join("results");
+        // JSON runs write one file for every hasher, other formats one each.
+        if results.join("results.json").exists() {
+            return Self::load(&results.join("results.json"));
+        }
+        let mut loaded = Self::default();
+        for hasher in hashers.iter().filter_map(Value::as_str) {
+            let path = ["csv", "jsonl"].iter().map(|extension| results.join(format!("{hasher}.{extension}"))).find(|path| path.exists());
+            let path = path.ok_or_else(|| format!("{}: no results for {hasher}", dir.display()))?;
+            loaded.rows.extend(Self::load(&path)?.rows);
+        }
+        Ok(loaded)
+    }
+
+    /// Rows of long results, one value per hasher, epoch and metric, in the
+    /// order each hasher and epoch first appears.
+    fn from_long(values: impl IntoIterator<Item = (String, u64, String, f64)>) -> Result<Self, String> {
+        let mut rows = Vec::<(String, u64, Vec<(String, f64)>)>::new();
+        let mut index = HashMap::<(String, u64), usize>::new();
+        for (hasher, epoch, metric, value) in values {
+            let row = *index.entry((hasher.clone(), epoch)).or_insert_with(|| {
+                rows.push((hasher, epoch, Vec::new()));
+                rows.len() - 1
+            });
+            rows[row].2.push((metric, value));
+        }
+        let rows = rows.into_iter().map(|(hasher, epoch, values)| {
+            let error = |err| format!("{hasher} epoch {epoch}: {err}");
+            ResultRow::from_columns(hasher.clone(), epoch, values.iter().map(|(metric, value)| (metric.as_str(), *value))).map_err(error)
+        });
+        Ok(Self { rows: rows.collect::<Result<_, _>>()? })
+    }
+
+    /// Parses the per-epoch section of a results CSV, up to the first blank
+    /// line, where reports start. Rows of a table without key columns
+    /// belong to `hasher`, which fails if an epoch repeats: hashers sharing
+    /// a table are told apart by its hasher column.
+    pub fn parse_csv(text: &str, hasher: &str) -> Result<Self, String> {
+        let mut lines = text.lines().take_while(|line| !line.is_empty());
+        let header_line = lines.next().ok_or("empty results")?;
+        let header = header_line.split(',').collect::<Vec<_>>();
+        if header_line == LONG_HEADER {
+            let values = lines.enumerate().map(|(number, line)| {
+                let error = || format!("malformed results row {}: {line}", number + 2);
+                let [epoch, hasher, metric, value] = line.splitn(4, ',').collect::<Vec<_>>()[..] else {
+                    return Err(error());
+                };
+                Ok((hasher.to_string(), epoch.parse::<u64>().map_err(|_| error())?, metric.to_string(), value.parse::<f64>().map_err(|_| error())?))
+            });
+            return Self::from_long(values.collect::<Result<Vec<_>, _>>()?);
+        }
+        let epoch_column = header.iter().position(|column| *column == "epoch").ok_or("results have no epoch column")?;
+        let key_columns = header.iter().enumerate().filter(|(_, column)| KEY_COLUMNS.contains(column)).map(|(index, _)| index).collect::<Vec<_>>();
+        let mut results = Self::default();
+        for (number, line) in lines.enumerate() {
+            let fields = line.split(',').collect::<Vec<_>>();
+            let error = |err: String| format!("malformed results row {}: {line}: {err}", number + 2);
+            if fields.len() != header.len() {
+                return Err(error(format!("expected {} fields", header.len())));
+            }
+            let epoch = fields[epoch_column].parse::<u64>().map_err(|err| error(err.to_string()))?;
+            let hasher = match key_columns.is_empty() {
+                true if results.rows.iter().any(|row| row.epoch == epoch) => return Err(error(format!("epoch {epoch} repeats in results without a hasher column"))),
+                true => hasher.to_string(),
+                false => key_columns.iter().map(|index| fields[*index]).collect::<Vec<_>>().join("/"),
+            };
+            let values = (0..header.len())
+                .filter(|index| *index != epoch_column && !key_columns.contains(index))
+                .map(|index| Ok((header[index], fields[index].parse::<f64>().map_err(|_| error(format!("{} isn't a number", header[index])))?)))
+                .collect::<Result<Vec<_>, String>>()?;
+            results.rows.push(ResultRow::from_columns(hasher, epoch, values).map_err(error)?);
+        }
+        Ok(results)
+    }
+
+    /// Parses a JSON array of records, or one record or sink row per line.
+    /// Summary records are skipped, and sink rows' values that aren't
+    /// numbers.
+    pub fn parse_json(text: &str, hasher: &str) -> Result<Self, String> {
+        let objects = if text.trim_start().starts_with('[') {
+            serde_json::from_str::<Vec<Map<String, Value>>>(text).map_err(|err| err.to_string())?
+        } else {
+            serde_json::Deserializer::from_str(text).into_iter::<Map<String, Value>>().collect::<Result<_, _>>().map_err(|err| err.to_string())?
+        };
+        let mut results = Self::default();
+        for object in objects {
+            if object.contains_key("record") {
+                if let Record::Epoch(record) = serde_json::from_value::<Record>(Value::Object(object)).map_err(|err| err.to_string())? {
+                    let record = *record;
+                    let columns = HEADER.split(',').collect();
+                    let (hasher, epoch, time_us) = (record.hasher.into_owned(), record.epoch, Some(record.time_us));
+                    results.rows.push(ResultRow { hasher, epoch, analysis: record.analysis, percentiles: record.percentiles, occupancy: record.occupancy, time_us, columns });
+                }
+                continue;
+            }
+            let epoch = object.get("epoch").and_then(Value::as_u64).ok_or("row has no epoch")?;
+            let hasher = object.get("hasher").and_then(Value::as_str).unwrap_or(hasher).to_string();
+            let values = object.iter().filter(|(field, _)| !matches!(field.as_str(), "epoch" | "hasher")).filter_map(|(field, value)| Some((field.as_str(), value.as_f64()?)));
+            results.rows.push(ResultRow::from_columns(hasher, epoch, values).map_err(|err| format!("epoch {epoch}: {err}"))?);
+        }
+        Ok(results)
+    }
+
+    /// Reads the `results(hasher, epoch, metric, value)` table a SQLite sink
+    /// writes, skipping values that aren't numbers.
+    #[cfg(feature = "sqlite")]
+    pub fn load_sqlite(path: &Path) -> Result<Self, String> {
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(|err| err.to_string())?;
+        Self::read_sqlite(&connection)
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn read_sqlite(connection: &Connection) -> Result<Self, String> {
+        let read = || -> rusqlite::Result<Vec<_>> {
+            let mut select = connection.prepare("SELECT hasher, epoch, metric, value FROM results WHERE typeof(value) IN ('integer', 'real') ORDER BY rowid")?;
+            let values = select.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64, row.get::<_, String>(2)?, row.get::<_, f64>(3)?)))?;
+            values.collect()
+        };
+        Self::from_long(read().map_err(|err| err.to_string())?)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ResultRow> {
+        self.rows.iter()
+    }
+
+    /// Every hasher with results, in the order first seen.
+    pub fn hashers(&self) -> Vec<&str> {
+        let mut hashers = Vec::new();
+        for row in &self.rows {
+            if !hashers.contains(&row.hasher.as_str()) {
+                hashers.push(row.hasher.as_str());
+            }
+        }
+        hashers
+    }
+
+    /// The rows `predicate` accepts.
+    pub fn filter(&self, mut predicate: impl FnMut(&ResultRow) -> bool) -> Self {
+        Self { rows: self.rows.iter().filter(|row| predicate(row)).cloned().collect() }
+    }
+
+    pub fn hasher(&self, hasher: &str) -> Self {
+        self.filter(|row| row.hasher == hasher)
+    }
+
+    pub fn epochs(&self, epochs: impl RangeBounds<u64>) -> Self {
+        self.filter(|row| epochs.contains(&row.epoch))
+    }
+
+    /// `metric` at each epoch of the rows that have it.
+    pub fn series<'a>(&'a self, metric: &'a str) -> impl Iterator<Item = (u64, f64)> + 'a {
+        self.rows.iter().filter_map(move |row| Some((row.epoch, row.metric(metric)?)))
+    }
+}
+
+impl<'a> IntoIterator for &'a Results {
+    type Item = &'a ResultRow;
+    type IntoIter = std::slice::Iter<'a, ResultRow>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            baseline::HasherSummary,
+            records::{EpochRecord, RunConfig, SummaryRecord},
+            reduction::Reduction,
+        },
+        std::borrow::Cow,
+    };
+
+    fn row(hasher: &str, epoch: u64, metrics: &[(&str, f64)]) -> ResultRow {
+        ResultRow::from_columns(hasher.to_string(), epoch, metrics.iter().copied()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let wide = Results::parse_csv("epoch,max,p99\n0,5,4\n1,7,6\n\nhasher,churn\nblake3,1\n", "blake3").unwrap();
+        assert_eq!(wide.rows, vec![row("blake3", 0, &[("max", 5.0), ("p99", 4.0)]), row("blake3", 1, &[("max", 7.0), ("p99", 6.0)])]);
+        assert_eq!((wide.rows[1].analysis.max, &wide.rows[1].percentiles), (7, &vec![Percentile { percentile: 99.0, count: 6 }]));
+        assert_eq!((wide.rows[0].metric("max"), wide.rows[0].metric("p99"), wide.rows[0].metric("min")), (Some(5.0), Some(4.0), None));
+        let compare = Results::parse_csv("hasher,epoch,max\nblake3,0,5\nxxh3,0,6\n", "stdout").unwrap();
+        assert_eq!(compare.hashers(), vec!["blake3", "xxh3"]);
+        let long = Results::parse_csv("epoch,hasher,metric,value\n0,xxh3,max,6\n0,xxh3,min,2\n1,xxh3,max,8\n", "stdout").unwrap();
+        assert_eq!(long.rows, vec![row("xxh3", 0, &[("max", 6.0), ("min", 2.0)]), row("xxh3", 1, &[("max", 8.0)])]);
+        let sweep = Results::parse_csv("hasher,buckets,seed,reduction,epoch,max\nblake3,64,epoch,mask,3,9\n", "sweep").unwrap();
+        assert_eq!(sweep.rows, vec![row("blake3/64/epoch/mask", 3, &[("max", 9.0)])]);
+        let occupancy = Results::parse_csv("epoch,max,occupied,collisions,expected_empty\n0,2,5,1,3.5\n", "x").unwrap();
+        assert_eq!(occupancy.rows[0].occupancy, Some(OccupancyAnalysis { occupied: 5, collisions: 1, expected_empty: 3.5 }));
+        assert!(Results::parse_csv("epoch,max\n0\n", "x").is_err());
+        // Hashers sharing a table without a hasher column can't be told apart.
+        assert!(Results::parse_csv("epoch,max\n0,5\n0,6\n", "stdout").is_err());
+        assert!(Results::parse_csv("epoch,max\n0,5.5\n", "x").is_err());
+        assert!(Results::parse_csv("epoch,churn\n0,5\n", "x").is_err());
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let config = RunConfig { buckets: 4, reduction: Reduction::Multiply, entries: 2, nonce: None };
+        let analysis = BucketAnalysis { max: 3, spread: 2, chi_squared: 1.5, ..BucketAnalysis::default() };
+        let percentiles = vec![Percentile { percentile: 99.0, count: 3 }];
+        let occupancy = Some(OccupancyAnalysis { occupied: 3, collisions: 1, expected_empty: 1.25 });
+        let epoch = EpochRecord { hasher: "blake3".into(), config: Cow::Borrowed(&config), epoch: 2, seed: 2, time_us: 4.0, analysis: analysis.clone(), percentiles: percentiles.clone(), occupancy: occupancy.clone() };
+        let summary = SummaryRecord { config: Cow::Borrowed(&config), summary: HasherSummary { hasher: "blake3".into(), portable: true, epochs: 1, spread: 2.0, std_dev: 0.0, max_over_mean: 1.0, empty_buckets: 0.0, time_us: 4.0 } };
+        let records = serde_json::to_string(&[Record::Epoch(Box::new(epoch)), Record::Summary(summary)]).unwrap();
+        let columns = HEADER.split(',').collect();
+        let expected = ResultRow { hasher: "blake3".to_string(), epoch: 2, analysis, percentiles, occupancy, time_us: Some(4.0), columns };
+        assert_eq!(Results::parse_json(&records, "results").unwrap().rows, vec![expected]);
+        let lines = "{\"hasher\": \"fnv\", \"epoch\": 0, \"max\": 4, \"note\": \"x\"}\n{\"epoch\": 1, \"max\": 5}\n";
+        assert_eq!(Results::parse_json(lines, "xxh3").unwrap().rows, vec![row("fnv", 0, &[("max", 4.0)]), row("xxh3", 1, &[("max", 5.0)])]);
+        assert!(Results::parse_json("{\"record\": \"epoch\", \"hasher\": \"fnv\", \"epoch\": 0}", "x").is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_and_helpers() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE results (hasher TEXT NOT NULL, epoch INTEGER NOT NULL, metric TEXT NOT NULL, value);
+                 INSERT INTO results VALUES ('blake3', 0, 'max', 5.0), ('blake3', 0, 'mode', 'n/a'), ('fnv', 0, 'max', 6.0), ('blake3', 1, 'max', 7.0);",
+            )
+            .unwrap();
+        let results = Results::read_sqlite(&connection).unwrap();
+        assert_eq!(results.rows, vec![row("blake3", 0, &[("max", 5.0)]), row("fnv", 0, &[("max", 6.0)]), row("blake3", 1, &[("max", 7.0)])]);
+        assert_eq!(results.hasher("blake3").series("max").collect::<Vec<_>>(), vec![(0, 5.0), (1, 7.0)]);
+        assert_eq!(results.epochs(1..).rows.len(), 1);
+        assert_eq!((&results).into_iter().filter(|row| row.metric("max") > Some(5.5)).count(), 2);
+    }
+}
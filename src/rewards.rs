@@ -0,0 +1,70 @@
+use {
+    crate::{stake::StakeMeta, Pubkey2},
+    serde::Deserialize,
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::HashMap, path::Path},
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RewardAmount {
+    pub pubkey: Pubkey2,
+    pub lamports: u64,
+}
+
+pub fn load_reward_amounts(path: &Path) -> Vec<RewardAmount> {
+    let file = std::fs::File::open(path).unwrap();
+    serde_json::from_reader(std::io::BufReader::new(file)).unwrap()
+}
+
+/// Each entry's reward, in order, and how many entries had none listed (and
+/// are credited zero).
+pub fn listed_rewards(addresses: &[Pubkey2], amounts: &[RewardAmount]) -> (Vec<usize>, usize) {
+    let amounts = amounts.iter().map(|amount| (*amount.pubkey, amount.lamports)).collect::<HashMap<Pubkey, _>>();
+    let mut unlisted = 0;
+    let rewards = addresses
+        .iter()
+        .map(|address| {
+            amounts.get(&**address).copied().unwrap_or_else(|| {
+                unlisted += 1;
+                0
+            }) as usize
+        })
+        .collect();
+    (rewards, unlisted)
+}
+
+/// Splits `total_lamports` across the entries in proportion to their
+/// delegated stake, rounding down. Entries without metadata get nothing.
+pub fn stake_proportional_rewards(addresses: &[Pubkey2], meta: &[StakeMeta], total_lamports: u64) -> Vec<usize> {
+    let stakes = meta.iter().map(|meta| (*meta.pubkey, meta.stake)).collect::<HashMap<Pubkey, _>>();
+    let stakes = addresses.iter().map(|address| stakes.get(&**address).copied().unwrap_or_default()).collect::<Vec<_>>();
+    let total_stake = stakes.iter().map(|stake| *stake as u128).sum::<u128>().max(1);
+    stakes
+        .into_iter()
+        .map(|stake| (stake as u128 * total_lamports as u128 / total_stake) as usize)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listed_rewards() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let amounts = [RewardAmount { pubkey: Pubkey2(a), lamports: 7 }];
+        let (rewards, unlisted) = listed_rewards(&[Pubkey2(a), Pubkey2(b), Pubkey2(a)], &amounts);
+        assert_eq!(rewards, vec![7, 0, 7]);
+        assert_eq!(unlisted, 1);
+    }
+
+    #[test]
+    fn test_stake_proportional_rewards() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let meta = |pubkey, stake| StakeMeta { pubkey: Pubkey2(pubkey), activation_epoch: 0, deactivation_epoch: u64::MAX, stake };
+        let metas = [meta(a, 1), meta(b, 3)];
+        let rewards = stake_proportional_rewards(&[Pubkey2(a), Pubkey2(b), Pubkey2(c)], &metas, 1000);
+        assert_eq!(rewards, vec![250, 750, 0]);
+        assert_eq!(stake_proportional_rewards(&[Pubkey2(c)], &metas, 1000), vec![0]);
+    }
+}
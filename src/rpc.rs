@@ -0,0 +1,235 @@
+use {
+    clap::ValueEnum,
+    serde_json::{json, Value as JsonValue},
+    solana_sdk::{pubkey::Pubkey, stake},
+    std::{
+        fmt,
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+#[derive(Clone, Debug)]
+pub struct RpcConfig {
+    pub url: String,
+    /// Attempts after the first for requests failing in transport or with
+    /// HTTP 429 or 5xx
+    pub max_retries: u32,
+    /// Delay before the first retry, doubling for each one after
+    pub initial_backoff: Duration,
+    /// Upper bound on requests per second, if any
+    pub max_requests_per_second: Option<f64>,
+}
+
+#[derive(Debug)]
+pub enum RpcError {
+    Transport(String),
+    Rpc(JsonValue),
+    Response(String),
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Self::Transport(err) => write!(formatter, "rpc transport error: {err}"),
+            Self::Rpc(err) => write!(formatter, "rpc error: {err}"),
+            Self::Response(err) => write!(formatter, "unexpected rpc response: {err}"),
+        }
+    }
+}
+
+/// A set of accounts an address file can be fetched from.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum AccountSet {
+    /// Accounts a given program owns
+    Program,
+    /// Vote accounts, current and delinquent
+    Vote,
+    /// Stake accounts, initialized or not
+    Stake,
+}
+
+/// A blocking JSON-RPC client that spaces requests out to stay under a rate
+/// limit and retries transient failures with exponential backoff.
+pub struct RpcClient {
+    config: RpcConfig,
+    agent: ureq::Agent,
+    last_request: Option<Instant>,
+}
+
+impl RpcClient {
+    pub fn new(config: RpcConfig) -> Self {
+        Self { config, agent: ureq::Agent::new(), last_request: None }
+    }
+
+    fn throttle(&mut self) {
+        if let (Some(rate), Some(last)) = (self.config.max_requests_per_second, self.last_request) {
+            let interval = Duration::from_secs_f64(1.0 / rate);
+            if let Some(wait) = interval.checked_sub(last.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+        self.last_request = Some(Instant::now());
+    }
+
+    pub fn call(&mut self, method: &str, params: JsonValue) -> Result<JsonValue, RpcError> {
+        let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+        let mut attempt = 0;
+        loop {
+            self.throttle();
+            let retry_after = match self.agent.post(&self.config.url).send_json(&body) {
+                Ok(response) => {
+                    let mut response = response.into_json::<JsonValue>().map_err(|err| RpcError::Response(err.to_string()))?;
+                    if let Some(err) = response.get("error") {
+                        return Err(RpcError::Rpc(err.clone()));
+                    }
+                    return response.get_mut("result").map(JsonValue::take).ok_or_else(|| RpcError::Response(response.to_string()));
+                }
+                Err(ureq::Error::Status(status, response)) if status == 429 || status >= 500 => {
+                    let retry_after = response.header("retry-after").and_then(|seconds| seconds.parse().ok()).map(Duration::from_secs);
+                    (format!("HTTP {status}"), retry_after)
+                }
+                Err(ureq::Error::Status(status, response)) => {
+                    return Err(RpcError::Transport(format!("HTTP {status}: {}", response.into_string().unwrap_or_default())));
+                }
+                Err(err) => (err.to_string(), None),
+            };
+            if attempt == self.config.max_retries {
+                return Err(RpcError::Transport(retry_after.0));
+            }
+            thread::sleep(retry_after.1.unwrap_or_else(|| backoff(self.config.initial_backoff, attempt)));
+            attempt += 1;
+        }
+    }
+
+    /// Keys of the accounts `program` owns, optionally only those of
+    /// `data_size` bytes, with the latest slot any of them was read at.
+    /// Account data is sliced away so responses carry little more than keys.
+    /// With `page_offset`, the accounts are fetched in 256 pages, one per
+    /// value of the data byte at that offset, so no single response has to
+    /// hold the whole set.
+    pub fn program_account_keys(&mut self, program: &Pubkey, data_size: Option<u64>, page_offset: Option<usize>) -> Result<(u64, Vec<Pubkey>), RpcError> {
+        let pages = match page_offset {
+            Some(offset) => (0..=u8::MAX).map(|byte| Some((offset, byte))).collect(),
+            None => vec![None],
+        };
+        let mut slot = 0;
+        let mut keys = Vec::new();
+        for page in pages {
+            let params = program_accounts_params(program, data_size, page);
+            let response = self.call("getProgramAccounts", params)?;
+            let (page_slot, accounts) = match (response["context"]["slot"].as_u64(), response["value"].as_array()) {
+                (Some(slot), Some(accounts)) => (slot, accounts),
+                _ => return Err(RpcError::Response(response.to_string())),
+            };
+            slot = slot.max(page_slot);
+            for account in accounts {
+                let key = account["pubkey"].as_str().and_then(|key| key.parse().ok()).ok_or_else(|| RpcError::Response(account.to_string()))?;
+                keys.push(key);
+            }
+        }
+        Ok((slot, keys))
+    }
+
+    /// Keys of every vote account the cluster knows, delinquent ones
+    /// included.
+    pub fn vote_account_keys(&mut self) -> Result<Vec<Pubkey>, RpcError> {
+        vote_pubkeys(&self.call("getVoteAccounts", json!([]))?)
+    }
+
+    /// Keys of every account of the stake program, by the stake account size.
+    pub fn stake_account_keys(&mut self, page_offset: Option<usize>) -> Result<(u64, Vec<Pubkey>), RpcError> {
+        self.program_account_keys(&stake::program::id(), Some(stake::state::StakeStateV2::size_of() as u64), page_offset)
+    }
+}
+
+fn vote_pubkeys(response: &JsonValue) -> Result<Vec<Pubkey>, RpcError> {
+    let mut keys = Vec::new();
+    for list in ["current", "delinquent"] {
+        let accounts = response[list].as_array().ok_or_else(|| RpcError::Response(response.to_string()))?;
+        for account in accounts {
+            keys.push(account["votePubkey"].as_str().and_then(|key| key.parse().ok()).ok_or_else(|| RpcError::Response(account.to_string()))?);
+        }
+    }
+    Ok(keys)
+}
+
+pub fn backoff(initial: Duration, attempt: u32) -> Duration {
+    initial.saturating_mul(1 << attempt.min(16))
+}
+
+fn base58_byte(byte: u8) -> String {
+    match byte {
+        0 => "1".to_string(),
+        byte if byte < 58 => (BASE58_ALPHABET[byte as usize] as char).to_string(),
+        byte => [BASE58_ALPHABET[byte as usize / 58], BASE58_ALPHABET[byte as usize % 58]].iter().map(|digit| *digit as char).collect(),
+    }
+}
+
+fn program_accounts_params(program: &Pubkey, data_size: Option<u64>, page: Option<(usize, u8)>) -> JsonValue {
+    let mut filters = Vec::new();
+    if let Some(data_size) = data_size {
+        filters.push(json!({"dataSize": data_size}));
+    }
+    if let Some((offset, byte)) = page {
+        filters.push(json!({"memcmp": {"offset": offset, "bytes": base58_byte(byte)}}));
+    }
+    json!([program.to_string(), {"encoding": "base64", "withContext": true, "dataSlice": {"offset": 0, "length": 0}, "filters": filters}])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base58_byte() {
+        for byte in 0..=u8::MAX {
+            let encoded = base58_byte(byte);
+            let decoded = encoded.bytes().fold(0u32, |value, digit| value * 58 + BASE58_ALPHABET.iter().position(|c| *c == digit).unwrap() as u32);
+            assert_eq!(decoded, byte as u32, "{encoded}");
+        }
+        assert_eq!(base58_byte(57), "z");
+        assert_eq!(base58_byte(58), "21");
+    }
+
+    #[test]
+    fn test_backoff() {
+        let initial = Duration::from_millis(100);
+        assert_eq!(backoff(initial, 0), initial);
+        assert_eq!(backoff(initial, 3), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn test_program_accounts_params() {
+        let program = Pubkey::new_unique();
+        let params = program_accounts_params(&program, Some(200), Some((12, 3)));
+        assert_eq!(params[0], program.to_string());
+        assert_eq!(params[1]["dataSlice"]["length"], 0);
+        assert_eq!(params[1]["filters"][0]["dataSize"], 200);
+        assert_eq!(params[1]["filters"][1]["memcmp"]["bytes"], "4");
+    }
+
+    #[test]
+    fn test_vote_pubkeys() {
+        let (current, delinquent) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let response = json!({
+            "current": [{"votePubkey": current.to_string(), "activatedStake": 1}],
+            "delinquent": [{"votePubkey": delinquent.to_string(), "activatedStake": 0}],
+        });
+        assert_eq!(vote_pubkeys(&response).unwrap(), vec![current, delinquent]);
+        assert!(vote_pubkeys(&json!({"current": []})).is_err());
+    }
+
+    #[test]
+    fn test_retries_unreachable_endpoint() {
+        let mut client = RpcClient::new(RpcConfig {
+            url: "http://127.0.0.1:1".to_string(),
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_requests_per_second: None,
+        });
+        assert!(matches!(client.call("getSlot", json!([])), Err(RpcError::Transport(_))));
+    }
+}
@@ -0,0 +1,247 @@
+use {
+    crate::{ahash_conformance::AhashConfig, reduction::Reduction},
+    serde::Serialize,
+    std::{
+        collections::{HashMap, HashSet},
+        fs::{self, File},
+        io::{self, BufWriter, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const RESULTS_DIR: &str = "results";
+const PLOTS_DIR: &str = "plots";
+const LOGS_DIR: &str = "logs";
+const LOG_FILE: &str = "run.log";
+// Large enough that results reach the file system about once per checkpoint
+// rather than every few rows.
+const RESULTS_BUFFER: usize = 1 << 20;
+
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    pub run_name: String,
+    pub run_id: String,
+    pub version: &'static str,
+    pub input: PathBuf,
+    pub addresses: usize,
+    pub buckets: usize,
+    pub reduction: Reduction,
+    pub epochs: u64,
+    pub hashers: Vec<&'static str>,
+    /// Mixed into every epoch's seed, if the run had one
+    pub nonce: Option<u64>,
+    /// The ahash build the run used, if it ran ahash, whose output isn't
+    /// portable across versions or platforms
+    pub ahash: Option<AhashConfig>,
+}
+
+impl Manifest {
+    // The run id is derived from everything that affects the results, so
+    // re-running an identical configuration lands in the same directory.
+    pub fn new(run_name: String, input: PathBuf, addresses: usize, buckets: usize, reduction: Reduction, epochs: u64, hashers: Vec<&'static str>) -> Self {
+        let mut manifest = Self {
+            run_name,
+            run_id: String::new(),
+            version: env!("CARGO_PKG_VERSION"),
+            input,
+            addresses,
+            buckets,
+            reduction,
+            epochs,
+            hashers,
+            nonce: None,
+            ahash: None,
+        };
+        manifest.assign_run_id();
+        manifest
+    }
+
+    pub fn with_nonce(mut self, nonce: Option<u64>) -> Self {
+        self.nonce = nonce;
+        self.assign_run_id();
+        self
+    }
+
+    pub fn with_ahash(mut self, ahash: Option<AhashConfig>) -> Self {
+        self.ahash = ahash;
+        self.assign_run_id();
+        self
+    }
+
+    fn assign_run_id(&mut self) {
+        self.run_id.clear();
+        let config = serde_json::to_vec(self).unwrap();
+        self.run_id = blake3::hash(&config).to_hex()[..16].to_string();
+    }
+}
+
+/// `<base>/<run_name>-<run_id>/` containing the manifest plus `results/`,
+/// `plots/` and `logs/` subdirectories.
+pub struct RunDir {
+    path: PathBuf,
+}
+
+impl RunDir {
+    pub fn create(base: &Path, manifest: &Manifest) -> io::Result<Self> {
+        let path = base.join(format!("{}-{}", manifest.run_name, manifest.run_id));
+        for dir in [RESULTS_DIR, PLOTS_DIR, LOGS_DIR] {
+            fs::create_dir_all(path.join(dir))?;
+        }
+        let manifest_file = File::create(path.join(MANIFEST_FILE))?;
+        serde_json::to_writer_pretty(manifest_file, manifest)?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn results_path(&self, file: &str) -> PathBuf {
+        self.path.join(RESULTS_DIR).join(file)
+    }
+
+    pub fn plot_path(&self, plot: &str) -> PathBuf {
+        self.path.join(PLOTS_DIR).join(plot)
+    }
+
+    pub fn log_path(&self) -> PathBuf {
+        self.path.join(LOGS_DIR).join(LOG_FILE)
+    }
+}
+
+/// Destination for per-epoch result rows: either all hashers interleaved on
+/// one stream, stdout or a file, or one file per hasher inside a run
+/// directory. Additional CSV reports are appended to the stream as their own
+/// sections, or get their own file alongside the per-hasher results. An
+/// empty results header writes none, for formats without one.
+pub enum ResultsOutput {
+    Stream {
+        out: BufWriter<Box<dyn Write>>,
+        reports: HashSet<String>,
+    },
+    RunDir {
+        run_dir: RunDir,
+        header: String,
+        /// Of the per-hasher results files
+        extension: &'static str,
+        files: HashMap<String, BufWriter<File>>,
+    },
+}
+
+impl ResultsOutput {
+    pub fn stream(out: Box<dyn Write>, header: &str) -> io::Result<Self> {
+        let mut out = BufWriter::with_capacity(RESULTS_BUFFER, out);
+        if !header.is_empty() {
+            writeln!(out, "{header}")?;
+        }
+        Ok(Self::Stream { out, reports: HashSet::new() })
+    }
+
+    pub fn run_dir(run_dir: RunDir, header: String, extension: &'static str) -> Self {
+        Self::RunDir { run_dir, header, extension, files: HashMap::new() }
+    }
+
+    pub fn writer(&mut self, hasher: &str) -> io::Result<&mut dyn Write> {
+        match self {
+            Self::Stream { out, .. } => Ok(out),
+            Self::RunDir { header, extension, .. } => {
+                let (file, header) = (format!("{hasher}.{extension}"), header.clone());
+                self.file_writer(file, &header)
+            }
+        }
+    }
+
+    pub fn report_writer(&mut self, report: &str, header: &str) -> io::Result<&mut dyn Write> {
+        match self {
+            Self::Stream { out, reports } => {
+                if reports.insert(report.to_string()) {
+                    writeln!(out)?;
+                    writeln!(out, "{header}")?;
+                }
+                Ok(out)
+            }
+            Self::RunDir { .. } => self.file_writer(format!("{report}.csv"), header),
+        }
+    }
+
+    fn file_writer(&mut self, name: String, header: &str) -> io::Result<&mut dyn Write> {
+        let Self::RunDir { run_dir, files, .. } = self else {
+            unreachable!("only run directories have result files");
+        };
+        if !files.contains_key(&name) {
+            let mut file = BufWriter::with_capacity(RESULTS_BUFFER, File::create(run_dir.results_path(&name))?);
+            if !header.is_empty() {
+                writeln!(file, "{header}")?;
+            }
+            files.insert(name.clone(), file);
+        }
+        Ok(files.get_mut(&name).unwrap())
+    }
+
+    /// A writer for the file `plot` in the run directory's `plots/`; plots
+    /// aren't written to a stream.
+    pub fn plot_writer(&self, plot: &str) -> io::Result<Option<BufWriter<File>>> {
+        match self {
+            Self::Stream { .. } => Ok(None),
+            Self::RunDir { run_dir, .. } => Ok(Some(BufWriter::new(File::create(run_dir.plot_path(plot))?))),
+        }
+    }
+
+    /// A writer for the run directory's log; without one, logs go to stderr
+    /// so the stream carries only results.
+    pub fn log_writer(&self) -> io::Result<Box<dyn Write>> {
+        match self {
+            Self::Stream { .. } => Ok(Box::new(io::stderr())),
+            Self::RunDir { run_dir, .. } => Ok(Box::new(BufWriter::new(File::create(run_dir.log_path())?))),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stream { out, .. } => out.flush(),
+            Self::RunDir { files, .. } => files.values_mut().try_for_each(|file| file.flush()),
+        }
+    }
+
+    /// Flushes, and makes sure run directory files reached the disk, so a
+    /// crash loses no more than what was written since.
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        self.flush()?;
+        match self {
+            Self::Stream { .. } => Ok(()),
+            Self::RunDir { files, .. } => files.values().try_for_each(|file| file.get_ref().sync_data()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(run_name: &str, buckets: usize) -> Manifest {
+        Manifest::new(run_name.to_string(), PathBuf::from("addresses.json"), 10, buckets, Reduction::Multiply, 1, vec!["blake3"])
+    }
+
+    #[test]
+    fn test_run_id_is_deterministic() {
+        assert_eq!(manifest("a", 100).run_id, manifest("a", 100).run_id);
+        assert_ne!(manifest("a", 100).run_id, manifest("a", 101).run_id);
+        assert_ne!(manifest("a", 100).run_id, manifest("b", 100).run_id);
+        assert_eq!(manifest("a", 100).with_nonce(None).run_id, manifest("a", 100).run_id);
+        assert_ne!(manifest("a", 100).with_nonce(Some(1)).run_id, manifest("a", 100).run_id);
+    }
+
+    #[test]
+    fn test_stream() {
+        let path = std::env::temp_dir().join(format!("hash-to-bucket-stream-{}.csv", std::process::id()));
+        let mut output = ResultsOutput::stream(Box::new(File::create(&path).unwrap()), "epoch,max").unwrap();
+        writeln!(output.writer("blake3").unwrap(), "0,5").unwrap();
+        writeln!(output.report_writer("churn", "hasher,churn").unwrap(), "blake3,1").unwrap();
+        writeln!(output.report_writer("churn", "hasher,churn").unwrap(), "xxh3,2").unwrap();
+        assert!(output.plot_writer("scaling.svg").unwrap().is_none());
+        output.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "epoch,max\n0,5\n\nhasher,churn\nblake3,1\nxxh3,2\n");
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        analysis::{analyze_buckets_streaming, ModeTieBreak},
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    std::{
+        collections::HashMap,
+        fmt,
+        io::{self, Write},
+    },
+};
+
+pub const HEADER: &str = "hasher,addresses,epochs,std_dev,expected_std_dev,std_dev_ratio,max_over_mean";
+
+#[derive(Debug, PartialEq)]
+pub struct ScalingPoint {
+    pub hasher: &'static str,
+    pub addresses: usize,
+    pub epochs: u64,
+    /// Mean over epochs of the bucket counts' sample standard deviation, as
+    /// the per-epoch std_dev column reports it
+    pub std_dev: f64,
+    /// Expected sample standard deviation of bucket counts under uniformly
+    /// random assignment
+    pub expected_std_dev: f64,
+    pub std_dev_ratio: f64,
+    pub max_over_mean: f64,
+}
+
+impl fmt::Display for ScalingPoint {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{},{}", self.hasher, self.addresses, self.epochs, self.std_dev, self.expected_std_dev, self.std_dev_ratio, self.max_over_mean)
+    }
+}
+
+/// Prefix lengths analyzed: powers of ten from 1000 below `total`, then
+/// `total` itself.
+pub fn prefix_sizes(total: usize) -> Vec<usize> {
+    let mut sizes = std::iter::successors(Some(1000), |size: &usize| size.checked_mul(10)).take_while(|size| *size < total).collect::<Vec<_>>();
+    if total > 0 {
+        sizes.push(total);
+    }
+    sizes
+}
+
+/// Square root of the expected sample variance of bucket counts when
+/// `addresses` are assigned uniformly at random to `buckets`, which is
+/// `addresses * (1 - 1/buckets) / (buckets - 1)`, the mean count.
+pub fn expected_std_dev(addresses: usize, buckets: usize) -> f64 {
+    match buckets {
+        0 | 1 => 0.0,
+        _ => (addresses as f64 / buckets as f64).sqrt(),
+    }
+}
+
+#[derive(Default)]
+struct Sums {
+    epochs: u64,
+    std_dev: Vec<f64>,
+    max_over_mean: Vec<f64>,
+}
+
+/// Measures every visited hasher at each prefix of the addresses in one pass
+/// per epoch, snapshotting the bucket counts as each prefix completes.
+pub struct PrefixVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    sizes: Vec<usize>,
+    names: Vec<&'static str>,
+    sums: HashMap<&'static str, Sums>,
+}
+
+impl<'a> PrefixVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2]) -> Self {
+        Self { buckets, reduction, addresses, sizes: prefix_sizes(addresses.len()), names: Vec::new(), sums: HashMap::new() }
+    }
+
+    /// Each hasher's curve, in visiting order.
+    pub fn points(&self) -> Vec<ScalingPoint> {
+        let mut points = Vec::new();
+        for name in &self.names {
+            let sums = &self.sums[name];
+            for (i, addresses) in self.sizes.iter().enumerate() {
+                let std_dev = sums.std_dev[i] / sums.epochs as f64;
+                let expected_std_dev = expected_std_dev(*addresses, self.buckets);
+                points.push(ScalingPoint {
+                    hasher: name,
+                    addresses: *addresses,
+                    epochs: sums.epochs,
+                    std_dev,
+                    expected_std_dev,
+                    std_dev_ratio: std_dev / expected_std_dev,
+                    max_over_mean: sums.max_over_mean[i] / sums.epochs as f64,
+                });
+            }
+        }
+        points
+    }
+}
+
+impl HasherVisitor for PrefixVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let sums = self.sums.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            Sums { epochs: 0, std_dev: vec![0.0; self.sizes.len()], max_over_mean: vec![0.0; self.sizes.len()] }
+        });
+        sums.epochs += 1;
+        let mut counts = vec![0; self.buckets];
+        let mut next = 0;
+        for (i, address) in self.addresses.iter().enumerate() {
+            counts[address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address)] += 1;
+            if i + 1 == self.sizes[next] {
+                let analysis = analyze_buckets_streaming(&counts, ModeTieBreak::default(), &[]);
+                sums.std_dev[next] += analysis.std_dev;
+                sums.max_over_mean[next] += analysis.max_over_mean;
+                next += 1;
+            }
+        }
+    }
+}
+
+/// Renders each hasher's `std_dev_ratio` against log-scaled prefix length as
+/// an SVG line chart, with the ratio of 1 random assignment gives marked.
+pub fn write_svg(points: &[ScalingPoint], out: &mut dyn Write) -> io::Result<()> {
+    const WIDTH: f64 = 640.0;
+    const HEIGHT: f64 = 400.0;
+    const COLORS: &[&str] = &["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f"];
+    let log_n = |point: &ScalingPoint| (point.addresses as f64).log10();
+    let (min_x, max_x) = points.iter().map(log_n).fold((f64::MAX, f64::MIN), |(min, max), x| (min.min(x), max.max(x)));
+    let max_y = points.iter().map(|point| point.std_dev_ratio).fold(2.0, f64::max);
+    let x = |value: f64| if max_x > min_x { (value - min_x) / (max_x - min_x) * WIDTH } else { 0.0 };
+    let y = |value: f64| HEIGHT - value / max_y * HEIGHT;
+    writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-10 -10 {} {}">"#, WIDTH + 20.0, HEIGHT + 20.0)?;
+    writeln!(out, r#"<line x1="0" y1="{0}" x2="{WIDTH}" y2="{0}" stroke="gray" stroke-dasharray="4"/>"#, y(1.0))?;
+    let mut hashers = Vec::<&str>::new();
+    for point in points {
+        if !hashers.contains(&point.hasher) {
+            hashers.push(point.hasher);
+        }
+    }
+    for (i, hasher) in hashers.iter().enumerate() {
+        let line = points
+            .iter()
+            .filter(|point| point.hasher == *hasher)
+            .map(|point| format!("{:.1},{:.1}", x(log_n(point)), y(point.std_dev_ratio)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(out, r#"<polyline fill="none" stroke="{}" points="{line}"><title>{hasher}</title></polyline>"#, COLORS[i % COLORS.len()])?;
+    }
+    writeln!(out, "</svg>")
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::hashers::Blake3Hasher,
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_prefix_sizes() {
+        assert_eq!(prefix_sizes(0), Vec::<usize>::new());
+        assert_eq!(prefix_sizes(500), vec![500]);
+        assert_eq!(prefix_sizes(1000), vec![1000]);
+        assert_eq!(prefix_sizes(25_000), vec![1000, 10_000, 25_000]);
+    }
+
+    #[test]
+    fn test_scaling_points() {
+        let addresses = (0..2500).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = PrefixVisitor::new(10, Reduction::Multiply, &addresses);
+        for epoch in 0..4 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+        }
+        let points = visitor.points();
+        assert_eq!(points.iter().map(|point| (point.addresses, point.epochs)).collect::<Vec<_>>(), vec![(1000, 4), (2500, 4)]);
+        assert_eq!(points[1].expected_std_dev, 250f64.sqrt());
+        assert!(points.iter().all(|point| point.std_dev_ratio > 0.3 && point.std_dev_ratio < 3.0));
+
+        let mut svg = Vec::new();
+        write_svg(&points, &mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert_eq!(svg.matches("<polyline").count(), 1);
+        assert!(svg.contains("<title>blake3</title>"));
+    }
+
+    #[test]
+    fn test_expected_std_dev() {
+        assert_eq!(expected_std_dev(2500, 10), 250f64.sqrt());
+        assert_eq!(expected_std_dev(2500, 1), 0.0);
+    }
+}
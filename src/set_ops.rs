@@ -0,0 +1,102 @@
+use {
+    crate::Pubkey2,
+    clap::ValueEnum,
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::HashSet, fmt},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum SetOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SetStats {
+    pub left: usize,
+    pub left_distinct: usize,
+    pub right: usize,
+    pub right_distinct: usize,
+    pub common: usize,
+    pub result: usize,
+}
+
+impl fmt::Display for SetStats {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            formatter,
+            "left: {} ({} distinct), right: {} ({} distinct), common: {}, result: {}",
+            self.left, self.left_distinct, self.right, self.right_distinct, self.common, self.result,
+        )
+    }
+}
+
+/// Applies `op` to the two address lists, deduplicating and keeping the order
+/// in which addresses first appear (left before right).
+pub fn apply(op: SetOp, left: &[Pubkey2], right: &[Pubkey2]) -> (Vec<Pubkey>, SetStats) {
+    let distinct = |addresses: &[Pubkey2]| {
+        let mut seen = HashSet::with_capacity(addresses.len());
+        addresses.iter().map(|address| **address).filter(|address| seen.insert(*address)).collect::<Vec<_>>()
+    };
+    let left_distinct = distinct(left);
+    let right_distinct = distinct(right);
+    let right_set = right_distinct.iter().collect::<HashSet<_>>();
+    let common = left_distinct.iter().filter(|address| right_set.contains(address)).count();
+
+    let result: Vec<Pubkey> = match op {
+        SetOp::Union => {
+            let left_set = left_distinct.iter().collect::<HashSet<_>>();
+            let right_only = right_distinct.iter().filter(|address| !left_set.contains(address));
+            left_distinct.iter().chain(right_only).copied().collect()
+        }
+        SetOp::Intersection => left_distinct.iter().filter(|address| right_set.contains(address)).copied().collect(),
+        SetOp::Difference => left_distinct.iter().filter(|address| !right_set.contains(address)).copied().collect(),
+    };
+    let stats = SetStats {
+        left: left.len(),
+        left_distinct: left_distinct.len(),
+        right: right.len(),
+        right_distinct: right_distinct.len(),
+        common,
+        result: result.len(),
+    };
+    (result, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(bytes: &[u8]) -> Vec<Pubkey2> {
+        bytes.iter().map(|byte| Pubkey2(Pubkey::new_from_array([*byte; 32]))).collect()
+    }
+
+    fn keys(bytes: &[u8]) -> Vec<Pubkey> {
+        addresses(bytes).iter().map(|address| **address).collect()
+    }
+
+    #[test]
+    fn test_apply() {
+        let left = addresses(&[1, 2, 2, 3]);
+        let right = addresses(&[4, 3, 2]);
+
+        let (union, stats) = apply(SetOp::Union, &left, &right);
+        assert_eq!(union, keys(&[1, 2, 3, 4]));
+        assert_eq!(stats, SetStats { left: 4, left_distinct: 3, right: 3, right_distinct: 3, common: 2, result: 4 });
+
+        let (intersection, _) = apply(SetOp::Intersection, &left, &right);
+        assert_eq!(intersection, keys(&[2, 3]));
+
+        let (difference, stats) = apply(SetOp::Difference, &left, &right);
+        assert_eq!(difference, keys(&[1]));
+        assert_eq!(stats.result, 1);
+    }
+
+    #[test]
+    fn test_apply_empty() {
+        let (result, stats) = apply(SetOp::Union, &[], &[]);
+        assert!(result.is_empty());
+        assert_eq!(stats.common, 0);
+    }
+}
@@ -0,0 +1,318 @@
+#[cfg(feature = "sqlite")]
+use rusqlite::{params, types::Value as SqlValue, Connection};
+use {
+    serde_json::{Map, Value as JsonValue},
+    std::{
+        collections::BTreeMap,
+        fs::{self, File},
+        io::{self, BufWriter, Write},
+        path::PathBuf,
+        str::FromStr,
+    },
+};
+
+/// A destination per-epoch results stream to, alongside the run's stdout or
+/// run directory output.
+pub trait OutputSink {
+    /// Receives one hasher's analysis of one epoch, `values` matching
+    /// `columns`.
+    fn write_row(&mut self, hasher: &str, epoch: u64, columns: &[&str], values: &[&str]) -> io::Result<()>;
+
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// Fans every row out to each of its sinks in turn.
+#[derive(Default)]
+pub struct Sinks<'a>(pub Vec<Box<dyn OutputSink + 'a>>);
+
+impl OutputSink for Sinks<'_> {
+    fn write_row(&mut self, hasher: &str, epoch: u64, columns: &[&str], values: &[&str]) -> io::Result<()> {
+        self.0.iter_mut().try_for_each(|sink| sink.write_row(hasher, epoch, columns, values))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.iter_mut().try_for_each(|sink| sink.flush())
+    }
+}
+
+/// A `--sink` argument: `stdout`, or one of `csv`, `json`, `sqlite` (with
+/// the `sqlite` feature) and `prometheus` followed by `=PATH`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SinkSpec {
+    Stdout,
+    Csv(PathBuf),
+    Json(PathBuf),
+    #[cfg(feature = "sqlite")]
+    Sqlite(PathBuf),
+    Prometheus(PathBuf),
+}
+
+impl FromStr for SinkSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        match spec.split_once('=') {
+            None if spec == "stdout" => Ok(Self::Stdout),
+            Some(("csv", path)) if !path.is_empty() => Ok(Self::Csv(path.into())),
+            Some(("json", path)) if !path.is_empty() => Ok(Self::Json(path.into())),
+            #[cfg(feature = "sqlite")]
+            Some(("sqlite", path)) if !path.is_empty() => Ok(Self::Sqlite(path.into())),
+            Some(("prometheus", path)) if !path.is_empty() => Ok(Self::Prometheus(path.into())),
+            _ => Err(format!("expected stdout or csv|json|sqlite|prometheus=PATH, got {spec:?}")),
+        }
+    }
+}
+
+impl SinkSpec {
+    pub fn open(&self) -> io::Result<Box<dyn OutputSink>> {
+        Ok(match self {
+            Self::Stdout => Box::new(CsvSink::new(io::stdout())),
+            Self::Csv(path) => Box::new(CsvSink::new(SyncedFile::create(path)?)),
+            Self::Json(path) => Box::new(JsonSink(SyncedFile::create(path)?)),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(path) => Box::new(SqliteSink::new(Connection::open(path).map_err(io::Error::other)?)?),
+            Self::Prometheus(path) => Box::new(PrometheusSink::new(path.clone())),
+        })
+    }
+}
+
+/// A buffered file that reaches disk on every flush, so a flushed sink
+/// survives a crash.
+pub struct SyncedFile(BufWriter<File>);
+
+impl SyncedFile {
+    pub fn create(path: &PathBuf) -> io::Result<Self> {
+        Ok(Self(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl Write for SyncedFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()?;
+        self.0.get_ref().sync_data()
+    }
+}
+
+/// Wide CSV rows led by the hasher and epoch, as `--compare` writes them.
+pub struct CsvSink<W> {
+    out: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, header_written: false }
+    }
+}
+
+impl<W: Write> OutputSink for CsvSink<W> {
+    fn write_row(&mut self, hasher: &str, epoch: u64, columns: &[&str], values: &[&str]) -> io::Result<()> {
+        if !self.header_written {
+            writeln!(self.out, "hasher,epoch,{}", columns.join(","))?;
+            self.header_written = true;
+        }
+        writeln!(self.out, "{hasher},{epoch},{}", values.join(","))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+// Numeric columns become JSON numbers, anything else a string.
+fn json_value(value: &str) -> JsonValue {
+    serde_json::from_str::<serde_json::Number>(value).map_or_else(|_| JsonValue::from(value), JsonValue::Number)
+}
+
+/// One JSON object per line, keyed by column.
+pub struct JsonSink<W>(pub W);
+
+impl<W: Write> OutputSink for JsonSink<W> {
+    fn write_row(&mut self, hasher: &str, epoch: u64, columns: &[&str], values: &[&str]) -> io::Result<()> {
+        let mut object = Map::new();
+        object.insert("hasher".to_string(), hasher.into());
+        object.insert("epoch".to_string(), epoch.into());
+        for (column, value) in columns.iter().zip(values) {
+            object.insert(column.to_string(), json_value(value));
+        }
+        serde_json::to_writer(&mut self.0, &object)?;
+        writeln!(self.0)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// A long-format `results(hasher, epoch, metric, value)` table, committed
+/// on every flush.
+#[cfg(feature = "sqlite")]
+pub struct SqliteSink {
+    connection: Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteSink {
+    pub fn new(connection: Connection) -> io::Result<Self> {
+        connection
+            .execute_batch("CREATE TABLE IF NOT EXISTS results (hasher TEXT NOT NULL, epoch INTEGER NOT NULL, metric TEXT NOT NULL, value); BEGIN;")
+            .map_err(io::Error::other)?;
+        Ok(Self { connection })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl OutputSink for SqliteSink {
+    fn write_row(&mut self, hasher: &str, epoch: u64, columns: &[&str], values: &[&str]) -> io::Result<()> {
+        let mut insert = self.connection.prepare_cached("INSERT INTO results VALUES (?1, ?2, ?3, ?4)").map_err(io::Error::other)?;
+        for (column, value) in columns.iter().zip(values) {
+            let value = value.parse::<f64>().map_or_else(|_| SqlValue::Text(value.to_string()), SqlValue::Real);
+            insert.execute(params![hasher, epoch as i64, column, value]).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.connection.execute_batch("COMMIT; BEGIN;").map_err(io::Error::other)
+    }
+}
+
+/// A Prometheus textfile-collector file holding every hasher's latest
+/// epoch as gauges, replaced atomically as each epoch completes.
+pub struct PrometheusSink {
+    path: PathBuf,
+    epoch: Option<u64>,
+    // (metric, hasher) -> value
+    latest: BTreeMap<(String, String), f64>,
+}
+
+impl PrometheusSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, epoch: None, latest: BTreeMap::new() }
+    }
+
+    fn write_file(&self) -> io::Result<()> {
+        let Some(epoch) = self.epoch else {
+            return Ok(());
+        };
+        let temp = self.path.with_extension("prom.tmp");
+        let mut out = BufWriter::new(File::create(&temp)?);
+        writeln!(out, "# TYPE hash_to_bucket_epoch gauge")?;
+        writeln!(out, "hash_to_bucket_epoch {epoch}")?;
+        let mut previous_metric = None;
+        for ((metric, hasher), value) in self.latest.iter() {
+            if previous_metric != Some(metric) {
+                writeln!(out, "# TYPE hash_to_bucket_{metric} gauge")?;
+                previous_metric = Some(metric);
+            }
+            writeln!(out, "hash_to_bucket_{metric}{{hasher=\"{hasher}\"}} {value}")?;
+        }
+        out.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+        fs::rename(temp, &self.path)
+    }
+}
+
+// Prometheus metric names allow only [a-zA-Z0-9_:] after their prefix, so
+// e.g. the p99.9 column becomes p99_9.
+fn metric_name(column: &str) -> String {
+    column.chars().map(|c| if c.is_ascii_alphanumeric() || c == ':' { c } else { '_' }).collect()
+}
+
+impl OutputSink for PrometheusSink {
+    fn write_row(&mut self, hasher: &str, epoch: u64, columns: &[&str], values: &[&str]) -> io::Result<()> {
+        if self.epoch.is_some_and(|previous| previous != epoch) {
+            self.write_file()?;
+        }
+        self.epoch = Some(epoch);
+        for (column, value) in columns.iter().zip(values) {
+            if let Ok(value) = value.parse() {
+                self.latest.insert((metric_name(column), hasher.to_string()), value);
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_file()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sink_spec() {
+        assert_eq!("stdout".parse(), Ok(SinkSpec::Stdout));
+        #[cfg(feature = "sqlite")]
+        assert_eq!("sqlite=runs/a.db".parse(), Ok(SinkSpec::Sqlite("runs/a.db".into())));
+        assert_eq!("prometheus=a=b.prom".parse(), Ok(SinkSpec::Prometheus("a=b.prom".into())));
+        assert!("csv".parse::<SinkSpec>().is_err());
+        assert!("csv=".parse::<SinkSpec>().is_err());
+        assert!("parquet=a".parse::<SinkSpec>().is_err());
+    }
+
+    #[test]
+    fn test_fan_out() {
+        let mut csv = Vec::new();
+        let mut json = Vec::new();
+        let mut sinks = Sinks(vec![Box::new(CsvSink::new(&mut csv)), Box::new(JsonSink(&mut json))]);
+        sinks.write_row("blake3", 0, &["min", "max"], &["3", "9.5"]).unwrap();
+        sinks.write_row("fnv", 0, &["min", "max"], &["4", "8"]).unwrap();
+        sinks.flush().unwrap();
+        drop(sinks);
+        assert_eq!(String::from_utf8(csv).unwrap(), "hasher,epoch,min,max\nblake3,0,3,9.5\nfnv,0,4,8\n");
+        assert_eq!(String::from_utf8(json).unwrap().lines().next(), Some(r#"{"epoch":0,"hasher":"blake3","max":9.5,"min":3}"#));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_sink() {
+        let mut sink = SqliteSink::new(Connection::open_in_memory().unwrap()).unwrap();
+        sink.write_row("blake3", 2, &["min", "max"], &["3", "9"]).unwrap();
+        sink.flush().unwrap();
+        let max: f64 = sink.connection.query_row("SELECT value FROM results WHERE hasher = 'blake3' AND epoch = 2 AND metric = 'max'", [], |row| row.get(0)).unwrap();
+        assert_eq!(max, 9.0);
+    }
+
+    #[test]
+    fn test_synced_csv_sink_reaches_file_on_flush() {
+        let path = std::env::temp_dir().join(format!("hash-to-bucket-sink-{}.csv", std::process::id()));
+        let mut sink = CsvSink::new(SyncedFile::create(&path).unwrap());
+        sink.write_row("blake3", 0, &["max"], &["9"]).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        sink.flush().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(contents, "hasher,epoch,max\nblake3,0,9\n");
+    }
+
+    #[test]
+    fn test_prometheus_sink() {
+        let path = std::env::temp_dir().join(format!("hash-to-bucket-sink-{}.prom", std::process::id()));
+        let mut sink = PrometheusSink::new(path.clone());
+        sink.write_row("blake3", 0, &["max"], &["9"]).unwrap();
+        sink.write_row("fnv", 0, &["max"], &["8"]).unwrap();
+        assert!(!path.exists());
+        sink.write_row("blake3", 1, &["max"], &["7"]).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("hash_to_bucket_max{hasher=\"blake3\"} 9\n"));
+        sink.flush().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            contents,
+            "# TYPE hash_to_bucket_epoch gauge\nhash_to_bucket_epoch 1\n# TYPE hash_to_bucket_max gauge\nhash_to_bucket_max{hasher=\"blake3\"} 7\nhash_to_bucket_max{hasher=\"fnv\"} 8\n"
+        );
+
+        let mut sink = PrometheusSink::new(path.clone());
+        sink.write_row("blake3", 0, &["p99.9"], &["12"]).unwrap();
+        sink.flush().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(contents.ends_with("# TYPE hash_to_bucket_p99_9 gauge\nhash_to_bucket_p99_9{hasher=\"blake3\"} 12\n"), "{contents}");
+    }
+}
@@ -0,0 +1,132 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        tags, Pubkey2,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashMap},
+        fmt,
+    },
+};
+
+pub const HEADER: &str = "hasher,tag,addresses,epochs,mean_distinct_buckets,expected_distinct_buckets";
+
+#[derive(Debug, PartialEq)]
+pub struct StabilityScore {
+    pub hasher: &'static str,
+    pub tag: String,
+    pub addresses: usize,
+    pub epochs: u64,
+    pub mean_distinct_buckets: f64,
+    pub expected_distinct_buckets: f64,
+}
+
+impl fmt::Display for StabilityScore {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{}", self.hasher, self.tag, self.addresses, self.epochs, self.mean_distinct_buckets, self.expected_distinct_buckets)
+    }
+}
+
+/// Distinct buckets an address is expected to visit over `epochs`
+/// independently seeded epochs.
+pub fn expected_distinct_buckets(buckets: usize, epochs: u64) -> f64 {
+    let m = buckets as f64;
+    m * (1.0 - (1.0 - 1.0 / m).powf(epochs as f64))
+}
+
+struct Visits {
+    epochs: u64,
+    // Each address's distinct buckets visited, sorted; at most one per epoch,
+    // so this stays small however many buckets there are.
+    seen: Vec<Vec<u32>>,
+}
+
+/// Tracks which buckets every address visits under each hasher, across
+/// however many epochs it visits.
+pub struct StabilityVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    names: Vec<&'static str>,
+    visits: HashMap<&'static str, Visits>,
+}
+
+impl<'a> StabilityVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2]) -> Self {
+        Self { buckets, reduction, addresses, names: Vec::new(), visits: HashMap::new() }
+    }
+
+    /// Each hasher's mean number of distinct buckets visited per address,
+    /// per tag.
+    pub fn scores(&self, tags: &HashMap<Pubkey, String>) -> Vec<StabilityScore> {
+        let mut scores = Vec::new();
+        for name in &self.names {
+            let visits = &self.visits[name];
+            let mut by_tag = BTreeMap::<&str, (usize, u64)>::new();
+            for (address, seen) in self.addresses.iter().zip(&visits.seen) {
+                let (addresses, distinct) = by_tag.entry(tags::tag(tags, address)).or_default();
+                *addresses += 1;
+                *distinct += seen.len() as u64;
+            }
+            for (tag, (addresses, distinct)) in by_tag {
+                scores.push(StabilityScore {
+                    hasher: name,
+                    tag: tag.to_string(),
+                    addresses,
+                    epochs: visits.epochs,
+                    mean_distinct_buckets: distinct as f64 / addresses as f64,
+                    expected_distinct_buckets: expected_distinct_buckets(self.buckets, visits.epochs),
+                });
+            }
+        }
+        scores
+    }
+}
+
+impl HasherVisitor for StabilityVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let visits = self.visits.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            Visits { epochs: 0, seen: vec![Vec::new(); self.addresses.len()] }
+        });
+        visits.epochs += 1;
+        for (address, seen) in self.addresses.iter().zip(visits.seen.iter_mut()) {
+            let bucket = address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address) as u32;
+            if let Err(index) = seen.binary_search(&bucket) {
+                seen.insert(index, bucket);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{hashers::Blake3Hasher, test_hashers::ConstantHasher},
+    };
+
+    #[test]
+    fn test_stability_scores() {
+        let addresses = (0..200).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let tags = HashMap::from([(*addresses[0], "vote".to_string())]);
+        let mut visitor = StabilityVisitor::new(100, Reduction::Multiply, &addresses);
+        for epoch in 0..8 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+            visitor.visit("constant", &[], ConstantHasher);
+        }
+        let scores = visitor.scores(&tags);
+        assert_eq!(scores.iter().map(|score| (score.hasher, score.tag.as_str(), score.addresses)).collect::<Vec<_>>(), vec![
+            ("blake3", tags::UNTAGGED, 199),
+            ("blake3", "vote", 1),
+            ("constant", tags::UNTAGGED, 199),
+            ("constant", "vote", 1),
+        ]);
+        assert!(scores[0].mean_distinct_buckets > 7.0 && scores[0].mean_distinct_buckets <= 8.0);
+        assert_eq!(scores[2].mean_distinct_buckets, 1.0);
+        assert!((scores[0].expected_distinct_buckets - expected_distinct_buckets(100, 8)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,107 @@
+use {
+    crate::Pubkey2,
+    serde::Deserialize,
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::HashMap, fmt, path::Path},
+};
+
+/// Delegation of one stake account, as in its `Delegation`.
+/// `deactivation_epoch` is `u64::MAX` for stake that was never deactivated.
+#[derive(Clone, Debug, Deserialize)]
+pub struct StakeMeta {
+    pub pubkey: Pubkey2,
+    pub activation_epoch: u64,
+    pub deactivation_epoch: u64,
+    /// Delegated lamports; only needed to derive rewards from stake
+    #[serde(default)]
+    pub stake: u64,
+}
+
+impl StakeMeta {
+    /// Whether the delegation is effective during `epoch`: activation
+    /// finished before it, and deactivation, if any, didn't begin until it.
+    pub fn is_active(&self, epoch: u64) -> bool {
+        self.activation_epoch < epoch && epoch <= self.deactivation_epoch
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct StakeFilterStats {
+    pub active: usize,
+    pub inactive: usize,
+    pub unknown: usize,
+}
+
+impl fmt::Display for StakeFilterStats {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "active: {}, inactive: {}, without stake metadata: {}", self.active, self.inactive, self.unknown)
+    }
+}
+
+pub fn load_stake_meta(path: &Path) -> Vec<StakeMeta> {
+    let file = std::fs::File::open(path).unwrap();
+    serde_json::from_reader(std::io::BufReader::new(file)).unwrap()
+}
+
+/// Keeps only the entries whose stake is actively delegated during `epoch`.
+/// Entries without metadata aren't stake accounts, so aren't in the rewards
+/// partition set either, and are dropped.
+pub fn filter_active(addresses: Vec<Pubkey2>, meta: &[StakeMeta], epoch: u64) -> (Vec<Pubkey2>, StakeFilterStats) {
+    let active = meta.iter().map(|meta| (*meta.pubkey, meta.is_active(epoch))).collect::<HashMap<Pubkey, _>>();
+    let mut stats = StakeFilterStats::default();
+    let addresses = addresses
+        .into_iter()
+        .filter(|address| match active.get(&**address) {
+            Some(true) => {
+                stats.active += 1;
+                true
+            }
+            Some(false) => {
+                stats.inactive += 1;
+                false
+            }
+            None => {
+                stats.unknown += 1;
+                false
+            }
+        })
+        .collect();
+    (addresses, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(pubkey: Pubkey, activation_epoch: u64, deactivation_epoch: u64) -> StakeMeta {
+        StakeMeta { pubkey: Pubkey2(pubkey), activation_epoch, deactivation_epoch, stake: 0 }
+    }
+
+    #[test]
+    fn test_is_active() {
+        let stake = meta(Pubkey::new_unique(), 10, 20);
+        assert!(!stake.is_active(10));
+        assert!(stake.is_active(11));
+        assert!(stake.is_active(20));
+        assert!(!stake.is_active(21));
+        assert!(meta(Pubkey::new_unique(), 0, u64::MAX).is_active(1_000));
+    }
+
+    #[test]
+    fn test_filter_active() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let metas = [meta(a, 0, u64::MAX), meta(b, 5, 6)];
+        let (kept, stats) = filter_active([a, b, c, a].into_iter().map(Pubkey2).collect(), &metas, 7);
+        assert_eq!(kept.iter().map(|address| **address).collect::<Vec<_>>(), vec![a, a]);
+        assert_eq!(stats, StakeFilterStats { active: 2, inactive: 1, unknown: 1 });
+    }
+
+    #[test]
+    fn test_stake_meta_deserialize() {
+        let pubkey = Pubkey::new_unique();
+        let json = format!(r#"[{{"pubkey":"{pubkey}","activation_epoch":3,"deactivation_epoch":18446744073709551615}}]"#);
+        let metas = serde_json::from_str::<Vec<StakeMeta>>(&json).unwrap();
+        assert_eq!(*metas[0].pubkey, pubkey);
+        assert_eq!((metas[0].activation_epoch, metas[0].deactivation_epoch), (3, u64::MAX));
+    }
+}
@@ -0,0 +1,364 @@
+use {
+    crate::{
+        analysis::{self, analyze_buckets_streaming, ModeTieBreak},
+        hashers::{self, AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        sink::OutputSink,
+        Pubkey2,
+    },
+    clap::ValueEnum,
+    serde::Deserialize,
+    std::{
+        collections::HashSet,
+        fmt::Write as _,
+        fs::{self, OpenOptions},
+        io::{self, BufRead, BufReader, Write},
+        num::NonZeroUsize,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            mpsc,
+        },
+        thread,
+    },
+};
+
+/// How each epoch number becomes the seed the hasher is keyed with.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SeedStrategy {
+    Epoch,
+    Blake3Derived,
+}
+
+impl SeedStrategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Epoch => "epoch",
+            Self::Blake3Derived => "blake3-derived",
+        }
+    }
+
+    pub fn seed(self, epoch: u64) -> u64 {
+        match self {
+            Self::Epoch => epoch,
+            Self::Blake3Derived => {
+                let key = blake3::derive_key("hash-to-bucket sweep seed", &epoch.to_le_bytes());
+                u64::from_le_bytes(key[..8].try_into().unwrap())
+            }
+        }
+    }
+}
+
+/// The experiment matrix: every combination of the listed values is run
+/// for `epochs` epochs starting at 0.
+#[derive(Debug, Deserialize)]
+pub struct SweepConfig {
+    pub hashers: Vec<String>,
+    pub buckets: Vec<usize>,
+    pub seeds: Vec<SeedStrategy>,
+    pub reductions: Vec<Reduction>,
+    pub epochs: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Combination {
+    pub hasher: String,
+    pub buckets: usize,
+    pub seed: SeedStrategy,
+    pub reduction: Reduction,
+}
+
+impl Combination {
+    // The leading columns of every row this combination produces, and how a
+    // resumed sweep recognizes it as done.
+    fn key(&self) -> String {
+        let reduction = self.reduction.to_possible_value().unwrap();
+        format!("{},{},{},{}", self.hasher, self.buckets, self.seed.name(), reduction.get_name())
+    }
+}
+
+impl SweepConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut config: Self = serde_json::from_slice(&fs::read(path).map_err(|err| err.to_string())?).map_err(|err| err.to_string())?;
+        let registered = hashers::registered_names();
+        for hasher in config.hashers.iter_mut() {
+            *hasher = hashers::canonical_name(hasher).to_string();
+        }
+        if let Some(hasher) = config.hashers.iter().find(|hasher| !registered.contains(&hasher.as_str())) {
+            return Err(format!("unknown hasher {hasher}; expected one of {}", registered.join(", ")));
+        }
+        if config.buckets.contains(&0) {
+            return Err("bucket counts must be non-zero".to_string());
+        }
+        Ok(config)
+    }
+
+    /// Every valid combination, skipping reductions a bucket count doesn't
+    /// support.
+    pub fn combinations(&self) -> Vec<Combination> {
+        let mut combinations = Vec::new();
+        for hasher in &self.hashers {
+            for buckets in &self.buckets {
+                for seed in &self.seeds {
+                    for reduction in self.reductions.iter().filter(|reduction| reduction.validate(*buckets).is_ok()) {
+                        combinations.push(Combination { hasher: hasher.clone(), buckets: *buckets, seed: *seed, reduction: *reduction });
+                    }
+                }
+            }
+        }
+        combinations
+    }
+}
+
+pub fn header() -> String {
+    format!("hasher,buckets,seed,reduction,epoch,{}", analysis::HEADER)
+}
+
+type HashFn = Box<dyn Fn(&[u8]) -> u64 + Send + Sync>;
+
+struct HashFnVisitor(Vec<HashFn>);
+
+impl HasherVisitor for HashFnVisitor {
+    fn visit<H: AnalysisHasher>(&mut self, _name: &'static str, _key: &[u8], hasher: H) {
+        self.0.push(Box::new(move |bytes| {
+            let mut hasher = hasher.clone();
+            hasher.write(bytes);
+            hasher.finish()
+        }));
+    }
+}
+
+/// Bucket counts for each of `combinations`, which must share a seed
+/// strategy, at `epoch`. Addresses are traversed once: each is hashed once
+/// per distinct hasher, and that hash feeds every bucket count and reduction
+/// using the hasher.
+pub fn count_shared(combinations: &[&Combination], epoch: u64, addresses: &[Pubkey2]) -> Vec<Vec<usize>> {
+    let mut hashers = Vec::<&str>::new();
+    let targets = combinations
+        .iter()
+        .map(|combination| {
+            debug_assert_eq!(combination.seed, combinations[0].seed);
+            let index = hashers.iter().position(|hasher| *hasher == combination.hasher).unwrap_or_else(|| {
+                hashers.push(&combination.hasher);
+                hashers.len() - 1
+            });
+            (index, combination.buckets, combination.reduction)
+        })
+        .collect::<Vec<_>>();
+    let mut hash_fns = HashFnVisitor(Vec::with_capacity(hashers.len()));
+    if let Some(combination) = combinations.first() {
+        let seed = combination.seed.seed(epoch);
+        for hasher in &hashers {
+            assert!(hashers::visit_registered(hasher, seed, &mut hash_fns));
+        }
+    }
+    let mut counts = targets.iter().map(|(_, buckets, _)| vec![0; *buckets]).collect::<Vec<_>>();
+    let mut hashes = vec![0; hashers.len()];
+    for address in addresses {
+        for (hash, hash_fn) in hashes.iter_mut().zip(&hash_fns.0) {
+            *hash = hash_fn(address.as_ref());
+        }
+        for ((index, buckets, reduction), counts) in targets.iter().zip(counts.iter_mut()) {
+            counts[reduction.reduce(*buckets, hashes[*index])] += 1;
+        }
+    }
+    counts
+}
+
+// Where the keys of the combinations whose rows are all in `out` are kept,
+// each appended only once those rows are flushed.
+fn done_path(out: &Path) -> PathBuf {
+    let mut path = out.as_os_str().to_owned();
+    path.push(".done");
+    path.into()
+}
+
+fn row_key(line: &str) -> String {
+    line.splitn(5, ',').take(4).collect::<Vec<_>>().join(",")
+}
+
+/// Keys of the combinations already completed in `out`, after dropping any
+/// rows an interrupted run left of combinations it didn't complete.
+fn completed(out: &Path) -> io::Result<HashSet<String>> {
+    let keys = match fs::read_to_string(done_path(out)) {
+        Ok(done) => done.lines().map(str::to_string).collect::<HashSet<_>>(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => HashSet::new(),
+        Err(err) => return Err(err),
+    };
+    let file = match fs::File::open(out) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(keys),
+        Err(err) => return Err(err),
+    };
+    let mut kept = format!("{}\n", header());
+    let mut dropped = false;
+    for line in BufReader::new(file).lines().skip(1) {
+        let line = line?;
+        if keys.contains(&row_key(&line)) {
+            writeln!(kept, "{line}").unwrap();
+        } else {
+            dropped = true;
+        }
+    }
+    if dropped {
+        fs::write(out, kept)?;
+    }
+    Ok(keys)
+}
+
+/// Runs every combination not already in `out`, one seed strategy at a
+/// time with `threads` threads splitting its epochs, and appends each
+/// strategy's rows in a single write once its epochs are done, marking its
+/// combinations complete only once that write is flushed, so an interrupted
+/// sweep resumes where it left off. Rows also stream to `sink`, hashers
+/// keyed by bucket count, seed and reduction columns. Returns the number of
+/// combinations run and skipped.
+pub fn run(
+    config: &SweepConfig,
+    addresses: &[Pubkey2],
+    out: &Path,
+    threads: NonZeroUsize,
+    tie_break: ModeTieBreak,
+    sink: &mut dyn OutputSink,
+) -> io::Result<(usize, usize)> {
+    let columns = header().split(',').skip(1).filter(|column| *column != "epoch").map(str::to_string).collect::<Vec<_>>();
+    let columns = columns.iter().map(String::as_str).collect::<Vec<_>>();
+    let done = completed(out)?;
+    let combinations = config.combinations();
+    let pending = combinations.iter().filter(|combination| !done.contains(&combination.key())).collect::<Vec<_>>();
+    let mut file = OpenOptions::new().create(true).append(true).open(out)?;
+    let mut done_file = OpenOptions::new().create(true).append(true).open(done_path(out))?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{}", header())?;
+    }
+    for seed in &config.seeds {
+        let group = pending.iter().copied().filter(|combination| combination.seed == *seed).collect::<Vec<_>>();
+        if group.is_empty() {
+            continue;
+        }
+        let next = AtomicU64::new(0);
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..threads.get() {
+                let (sender, next, group) = (sender.clone(), &next, &group);
+                scope.spawn(move || loop {
+                    let epoch = next.fetch_add(1, Ordering::Relaxed);
+                    if epoch >= config.epochs {
+                        break;
+                    }
+                    let rows = count_shared(group, epoch, addresses)
+                        .iter()
+                        .map(|counts| analyze_buckets_streaming(counts, tie_break, &[]).to_string())
+                        .collect::<Vec<_>>();
+                    if sender.send((epoch, rows)).is_err() {
+                        break;
+                    }
+                });
+            }
+        });
+        drop(sender);
+        let mut by_epoch = receiver.into_iter().collect::<Vec<_>>();
+        by_epoch.sort_by_key(|(epoch, _)| *epoch);
+        let mut rows = String::new();
+        for (index, combination) in group.iter().enumerate() {
+            let key = combination.key();
+            for (epoch, analyses) in &by_epoch {
+                writeln!(rows, "{key},{epoch},{}", analyses[index]).unwrap();
+                let values = key.split(',').skip(1).chain(analyses[index].split(',')).collect::<Vec<_>>();
+                sink.write_row(&combination.hasher, *epoch, &columns, &values)?;
+            }
+        }
+        file.write_all(rows.as_bytes())?;
+        file.flush()?;
+        file.sync_data()?;
+        sink.flush()?;
+        let keys = group.iter().map(|combination| format!("{}\n", combination.key())).collect::<String>();
+        done_file.write_all(keys.as_bytes())?;
+        done_file.flush()?;
+    }
+    Ok((pending.len(), combinations.len() - pending.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::sink::{CsvSink, Sinks},
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    fn config() -> SweepConfig {
+        SweepConfig {
+            hashers: vec!["blake3".to_string(), "siphash13".to_string()],
+            buckets: vec![8, 10],
+            seeds: vec![SeedStrategy::Epoch, SeedStrategy::Blake3Derived],
+            reductions: vec![Reduction::Multiply, Reduction::Mask],
+            epochs: 3,
+        }
+    }
+
+    #[test]
+    fn test_combinations_skip_invalid_reductions() {
+        let combinations = config().combinations();
+        // mask is only valid for 8 buckets: 2 hashers * 2 seeds * (2 + 1)
+        assert_eq!(combinations.len(), 12);
+        assert!(!combinations.iter().any(|combination| combination.buckets == 10 && combination.reduction == Reduction::Mask));
+    }
+
+    #[test]
+    fn test_count_shared_matches_separate_passes() {
+        let addresses = (0..200).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let combinations = config().combinations().into_iter().filter(|combination| combination.seed == SeedStrategy::Blake3Derived).collect::<Vec<_>>();
+        let group = combinations.iter().collect::<Vec<_>>();
+        let shared = count_shared(&group, 5, &addresses);
+        for (combination, shared) in combinations.iter().zip(shared) {
+            let mut separate = vec![0; combination.buckets];
+            for address in &addresses {
+                let mut hash_fns = HashFnVisitor(Vec::new());
+                hashers::visit_registered(&combination.hasher, combination.seed.seed(5), &mut hash_fns);
+                separate[combination.reduction.reduce(combination.buckets, hash_fns.0[0](address.as_ref()))] += 1;
+            }
+            assert_eq!(shared, separate, "{combination:?}");
+        }
+    }
+
+    #[test]
+    fn test_run_resumes() {
+        let addresses = (0..64).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let out = std::env::temp_dir().join(format!("hash-to-bucket-sweep-{}.csv", std::process::id()));
+        let _ = fs::remove_file(&out);
+        let _ = fs::remove_file(done_path(&out));
+        let config = config();
+        let threads = NonZeroUsize::new(2).unwrap();
+        let mut streamed = Vec::new();
+        assert_eq!(run(&config, &addresses, &out, threads, ModeTieBreak::Lowest, &mut CsvSink::new(&mut streamed)).unwrap(), (12, 0));
+        let first = fs::read_to_string(&out).unwrap();
+        assert_eq!(first.lines().count(), 1 + 12 * 3);
+        // The sink leads with the hasher and epoch, then the same columns.
+        let streamed = String::from_utf8(streamed).unwrap();
+        assert_eq!(streamed.lines().next().unwrap(), format!("hasher,epoch,buckets,seed,reduction,{}", analysis::HEADER));
+        let reorder = |line: &str| {
+            let fields = line.split(',').collect::<Vec<_>>();
+            [&fields[..1], &fields[4..5], &fields[1..4], &fields[5..]].concat().join(",")
+        };
+        assert_eq!(first.lines().skip(1).map(reorder).collect::<Vec<_>>(), streamed.lines().skip(1).collect::<Vec<_>>());
+        assert_eq!(run(&config, &addresses, &out, threads, ModeTieBreak::Lowest, &mut Sinks::default()).unwrap(), (0, 12));
+        assert_eq!(fs::read_to_string(&out).unwrap(), first);
+
+        // An interrupted write leaves rows, the last maybe cut short, of a
+        // combination never marked done; resuming drops and reruns them.
+        let done = fs::read_to_string(done_path(&out)).unwrap();
+        let keys = done.lines().collect::<Vec<_>>();
+        let (kept, interrupted) = keys.split_at(11);
+        fs::write(done_path(&out), kept.iter().map(|key| format!("{key}\n")).collect::<String>()).unwrap();
+        assert!(first.contains(interrupted[0]));
+        fs::write(&out, &first[..first.len() - 10]).unwrap();
+        assert_eq!(run(&config, &addresses, &out, threads, ModeTieBreak::Lowest, &mut Sinks::default()).unwrap(), (1, 11));
+        let resumed = fs::read_to_string(&out).unwrap();
+        assert_eq!(resumed.lines().count(), 1 + 12 * 3);
+        assert_eq!(resumed.lines().filter(|line| row_key(line) == interrupted[0]).count(), 3);
+        fs::remove_file(&out).unwrap();
+        fs::remove_file(done_path(&out)).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        grinding::{grind, random_key},
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        rng, Pubkey2,
+    },
+    std::{collections::HashMap, fmt},
+};
+
+pub const HEADER: &str = "hasher,strategy,accounts,epochs,baseline_overload,overload,attempts_per_account";
+
+/// How the sybil entity creates its accounts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    /// Fresh random keypairs, as an entity indifferent to placement would
+    Random,
+    /// Keypairs ground until each lands in the epoch's most loaded bucket
+    Targeted,
+}
+
+impl Strategy {
+    const ALL: [Self; 2] = [Self::Random, Self::Targeted];
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        formatter.write_str(match self {
+            Self::Random => "random",
+            Self::Targeted => "targeted",
+        })
+    }
+}
+
+/// Worst-bucket overload, `max / mean - 1`, before and after one entity
+/// adds `accounts` accounts, averaged over the epochs run.
+#[derive(Debug, PartialEq)]
+pub struct SybilImpact {
+    pub hasher: &'static str,
+    pub strategy: Strategy,
+    pub accounts: usize,
+    pub epochs: u64,
+    pub baseline_overload: f64,
+    pub overload: f64,
+    /// Keys the entity drew per account it kept
+    pub attempts_per_account: f64,
+}
+
+impl fmt::Display for SybilImpact {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{},{}", self.hasher, self.strategy, self.accounts, self.epochs, self.baseline_overload, self.overload, self.attempts_per_account)
+    }
+}
+
+fn overload(counts: &[usize]) -> f64 {
+    let total = counts.iter().sum::<usize>();
+    if total == 0 {
+        return 0.0;
+    }
+    counts.iter().max().map_or(0.0, |max| *max as f64 * counts.len() as f64 / total as f64 - 1.0)
+}
+
+#[derive(Default)]
+struct Totals {
+    epochs: u64,
+    baseline_overload: f64,
+    // Indexed like Strategy::ALL.
+    overload: [f64; 2],
+    attempts: [u64; 2],
+}
+
+/// Injects `accounts` sybil accounts into each visited hasher's epoch under
+/// every [`Strategy`], treating the n-th visit of a hasher as epoch n.
+pub struct SybilVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    accounts: usize,
+    rng_seed: u64,
+    names: Vec<&'static str>,
+    totals: HashMap<&'static str, Totals>,
+}
+
+impl<'a> SybilVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2], accounts: usize, rng_seed: u64) -> Self {
+        Self { buckets, reduction, addresses, accounts, rng_seed, names: Vec::new(), totals: HashMap::new() }
+    }
+
+    /// Each hasher's impact under each strategy, in visiting order.
+    pub fn impacts(&self) -> Vec<SybilImpact> {
+        let mut impacts = Vec::new();
+        for name in &self.names {
+            let totals = &self.totals[name];
+            let epochs = totals.epochs.max(1) as f64;
+            for (i, strategy) in Strategy::ALL.into_iter().enumerate() {
+                impacts.push(SybilImpact {
+                    hasher: name,
+                    strategy,
+                    accounts: self.accounts,
+                    epochs: totals.epochs,
+                    baseline_overload: totals.baseline_overload / epochs,
+                    overload: totals.overload[i] / epochs,
+                    attempts_per_account: totals.attempts[i] as f64 / (totals.epochs as f64 * self.accounts as f64).max(1.0),
+                });
+            }
+        }
+        impacts
+    }
+}
+
+impl HasherVisitor for SybilVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let totals = self.totals.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            Totals::default()
+        });
+        let epoch = totals.epochs;
+        totals.epochs += 1;
+        let bucket = |address: &Pubkey2| address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address);
+        let mut counts = vec![0; self.buckets];
+        for address in self.addresses {
+            counts[bucket(address)] += 1;
+        }
+        totals.baseline_overload += overload(&counts);
+        // The most loaded bucket, lowest on ties.
+        let target = counts.iter().enumerate().rev().max_by_key(|(_, count)| **count).map_or(0, |(target, _)| target);
+        for (i, strategy) in Strategy::ALL.into_iter().enumerate() {
+            let mut rng = rng::stream(self.rng_seed, "sybil", epoch);
+            let mut injected = counts.clone();
+            for _ in 0..self.accounts {
+                let (key, attempts) = match strategy {
+                    Strategy::Random => (Some(random_key(&mut rng)), 1),
+                    Strategy::Targeted => grind(self.buckets, self.reduction, &hasher, target, &mut rng),
+                };
+                if let Some(key) = key {
+                    injected[bucket(&key)] += 1;
+                }
+                totals.attempts[i] += attempts;
+            }
+            totals.overload[i] += overload(&injected);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn test_overload() {
+        assert_eq!(overload(&[5, 5, 5, 5]), 0.0);
+        assert_eq!(overload(&[2, 6, 0, 0]), 2.0);
+        assert_eq!(overload(&[0, 0]), 0.0);
+    }
+
+    #[test]
+    fn test_targeted_outweighs_random() {
+        let addresses = (0..1000).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = SybilVisitor::new(10, Reduction::Multiply, &addresses, 50, 0);
+        for epoch in 0..3 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+        }
+        let impacts = visitor.impacts();
+        assert_eq!(impacts.iter().map(|impact| (impact.strategy, impact.epochs)).collect::<Vec<_>>(), vec![(Strategy::Random, 3), (Strategy::Targeted, 3)]);
+        let (random, targeted) = (&impacts[0], &impacts[1]);
+        assert_eq!(random.attempts_per_account, 1.0);
+        assert!(targeted.attempts_per_account > 5.0);
+        assert!(targeted.overload > random.overload);
+        // All 50 land on the most loaded bucket: (max + 50) / (1050 / 10) - 1.
+        assert!(targeted.overload > 50.0 / 105.0);
+    }
+}
@@ -0,0 +1,87 @@
+use {
+    crate::Pubkey2,
+    serde::Deserialize,
+    solana_sdk::pubkey::Pubkey,
+    std::collections::{BTreeMap, HashMap},
+};
+
+/// The group of addresses whose input entry carried no tag.
+pub const UNTAGGED: &str = "untagged";
+
+/// One input entry: a bare base58 address, or an address carrying a
+/// free-form provenance tag such as "stake" or "vote".
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum InputEntry {
+    Address(Pubkey2),
+    Tagged { pubkey: Pubkey2, tag: String },
+}
+
+impl InputEntry {
+    pub fn into_address(self) -> Pubkey2 {
+        match self {
+            Self::Address(address) => address,
+            Self::Tagged { pubkey, .. } => pubkey,
+        }
+    }
+}
+
+/// The entries' addresses in order, and each tagged address's tag. An
+/// address tagged more than once keeps its last tag.
+pub fn split_entries(entries: Vec<InputEntry>) -> (Vec<Pubkey2>, HashMap<Pubkey, String>) {
+    let mut tags = HashMap::new();
+    let addresses = entries
+        .into_iter()
+        .map(|entry| match entry {
+            InputEntry::Address(address) => address,
+            InputEntry::Tagged { pubkey, tag } => {
+                tags.insert(*pubkey, tag);
+                pubkey
+            }
+        })
+        .collect();
+    (addresses, tags)
+}
+
+pub fn tag<'a>(tags: &'a HashMap<Pubkey, String>, address: &Pubkey2) -> &'a str {
+    tags.get(&**address).map_or(UNTAGGED, String::as_str)
+}
+
+/// Splits addresses, and their weights if any, into one group per tag.
+pub fn group(addresses: &[Pubkey2], weights: Option<&[usize]>, tags: &HashMap<Pubkey, String>) -> BTreeMap<String, (Vec<Pubkey2>, Option<Vec<usize>>)> {
+    let mut groups = BTreeMap::<String, (Vec<Pubkey2>, Option<Vec<usize>>)>::new();
+    for (index, address) in addresses.iter().enumerate() {
+        let (members, member_weights) = groups.entry(tag(tags, address).to_string()).or_insert_with(|| (Vec::new(), weights.map(|_| Vec::new())));
+        members.push(address.clone());
+        if let (Some(member_weights), Some(weights)) = (member_weights.as_mut(), weights) {
+            member_weights.push(weights[index]);
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixed_entries() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let json = format!(r#"["{a}", {{"pubkey": "{b}", "tag": "vote"}}]"#);
+        let (addresses, tags) = split_entries(serde_json::from_str(&json).unwrap());
+        assert_eq!(addresses.iter().map(|address| **address).collect::<Vec<_>>(), vec![a, b]);
+        assert_eq!(tag(&tags, &addresses[0]), UNTAGGED);
+        assert_eq!(tag(&tags, &addresses[1]), "vote");
+    }
+
+    #[test]
+    fn test_group() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let tags = HashMap::from([(a, "stake".to_string()), (c, "stake".to_string())]);
+        let groups = group(&[Pubkey2(a), Pubkey2(b), Pubkey2(c)], Some(&[1, 2, 3]), &tags);
+        assert_eq!(groups.keys().collect::<Vec<_>>(), vec!["stake", UNTAGGED]);
+        assert_eq!(groups["stake"].0.iter().map(|address| **address).collect::<Vec<_>>(), vec![a, c]);
+        assert_eq!(groups["stake"].1, Some(vec![1, 3]));
+        assert_eq!(groups[UNTAGGED].1, Some(vec![2]));
+    }
+}
@@ -0,0 +1,16 @@
+use crate::hashers::AnalysisHasher;
+
+/// Hashes every address to 0, so puts them all in one bucket under every
+/// reduction and seed.
+#[derive(Clone, Default)]
+pub struct ConstantHasher;
+
+impl std::hash::Hasher for ConstantHasher {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, _bytes: &[u8]) {}
+}
+
+impl AnalysisHasher for ConstantHasher {}
@@ -0,0 +1,131 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        rng, Pubkey2,
+    },
+    rand::Rng,
+    std::fmt,
+};
+
+pub const HEADER: &str = "epoch,hasher,rank,bucket,count,example";
+
+/// At most `capacity` items drawn uniformly from everything offered, in
+/// constant memory however many are offered (Vitter's algorithm R).
+#[derive(Clone, Debug)]
+pub struct Reservoir<T> {
+    capacity: usize,
+    seen: u64,
+    items: Vec<T>,
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, seen: 0, items: Vec::new() }
+    }
+
+    pub fn offer(&mut self, item: T, rng: &mut impl Rng) {
+        self.seen += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(item);
+        } else {
+            let slot = rng.gen_range(0..self.seen);
+            if slot < self.capacity as u64 {
+                self.items[slot as usize] = item;
+            }
+        }
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+}
+
+/// One example address from one of an epoch's most loaded buckets; `rank` 0
+/// is the most loaded.
+pub struct Example<'a> {
+    pub epoch: u64,
+    pub hasher: &'static str,
+    pub rank: usize,
+    pub bucket: usize,
+    pub count: usize,
+    pub address: &'a Pubkey2,
+}
+
+impl fmt::Display for Example<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{}", self.epoch, self.hasher, self.rank, self.bucket, self.count, **self.address)
+    }
+}
+
+/// Samples up to `examples` member addresses of each visited hasher's `top`
+/// most loaded buckets, keeping a reservoir per bucket rather than every
+/// bucket's membership.
+pub struct TopBucketsVisitor<'a> {
+    pub epoch: u64,
+    pub buckets: usize,
+    pub reduction: Reduction,
+    pub addresses: &'a [Pubkey2],
+    pub top: usize,
+    pub examples: usize,
+    pub rng_seed: u64,
+    pub rows: Vec<Example<'a>>,
+}
+
+impl<'a> HasherVisitor for TopBucketsVisitor<'a> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let mut rng = rng::stream(self.rng_seed, "top-buckets", self.epoch);
+        let mut counts = vec![0; self.buckets];
+        let mut reservoirs = vec![Reservoir::new(self.examples); self.buckets];
+        let addresses: &'a [Pubkey2] = self.addresses;
+        for address in addresses {
+            let bucket = address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address);
+            counts[bucket] += 1;
+            reservoirs[bucket].offer(address, &mut rng);
+        }
+        let mut ranked = (0..self.buckets).collect::<Vec<_>>();
+        // Most loaded first, lowest bucket on ties.
+        ranked.sort_by_key(|bucket| std::cmp::Reverse(counts[*bucket]));
+        for (rank, bucket) in ranked.into_iter().take(self.top).enumerate() {
+            for address in reservoirs[bucket].items() {
+                self.rows.push(Example { epoch: self.epoch, hasher: name, rank, bucket, count: counts[bucket], address });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::hashers::Blake3Hasher, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn test_reservoir_is_uniform() {
+        let mut rng = rng::stream(0, "test", 0);
+        let mut hits = [0; 10];
+        for _ in 0..10_000 {
+            let mut reservoir = Reservoir::new(2);
+            for item in 0..10 {
+                reservoir.offer(item, &mut rng);
+            }
+            assert_eq!(reservoir.items().len(), 2);
+            for item in reservoir.items() {
+                hits[*item] += 1;
+            }
+        }
+        // Each item is kept with probability 2 / 10.
+        assert!(hits.iter().all(|hits| (1800..2200).contains(hits)), "{hits:?}");
+    }
+
+    #[test]
+    fn test_top_bucket_examples() {
+        let addresses = (0..500).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = TopBucketsVisitor { epoch: 3, buckets: 10, reduction: Reduction::Multiply, addresses: &addresses, top: 2, examples: 4, rng_seed: 0, rows: Vec::new() };
+        visitor.visit("blake3", &Blake3Hasher::key(3), Blake3Hasher::new_with_seed(3));
+        assert_eq!(visitor.rows.iter().map(|row| row.rank).collect::<Vec<_>>(), vec![0, 0, 0, 0, 1, 1, 1, 1]);
+        assert!(visitor.rows[0].count >= visitor.rows[4].count);
+        for row in &visitor.rows {
+            assert_eq!(address_to_bucket_with_reduction(10, Reduction::Multiply, Blake3Hasher::new_with_seed(3), row.address), row.bucket);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use {
+    crate::{rng, Pubkey2},
+    rand::seq::SliceRandom,
+    std::{fmt, time::Duration},
+};
+
+#[derive(Debug, PartialEq)]
+pub struct OrderMismatch {
+    pub epoch: u64,
+    pub hasher: &'static str,
+}
+
+impl fmt::Display for OrderMismatch {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{} bucket counts at epoch {} depend on address order", self.hasher, self.epoch)
+    }
+}
+
+fn collect(
+    hash: &impl Fn(u64, &[Pubkey2], &mut dyn FnMut(&'static str, Vec<usize>, Duration)),
+    epoch: u64,
+    addresses: &[Pubkey2],
+) -> Vec<(&'static str, Vec<usize>)> {
+    let mut results = Vec::new();
+    hash(epoch, addresses, &mut |hasher, buckets, _time| results.push((hasher, buckets)));
+    results
+}
+
+/// Re-runs each epoch over a shuffled copy of `addresses` and checks every
+/// hasher produced the same bucket counts as over the original order.
+pub fn verify_order_independence(
+    epochs: u64,
+    rng_seed: u64,
+    addresses: &[Pubkey2],
+    hash: impl Fn(u64, &[Pubkey2], &mut dyn FnMut(&'static str, Vec<usize>, Duration)),
+) -> Result<(), OrderMismatch> {
+    let mut shuffled = addresses.to_vec();
+    for epoch in 0..epochs {
+        shuffled.shuffle(&mut rng::stream(rng_seed, "verify-order", epoch));
+        let expected = collect(&hash, epoch, addresses);
+        let actual = collect(&hash, epoch, &shuffled);
+        for ((hasher, expected), (_, actual)) in expected.iter().zip(actual.iter()) {
+            if expected != actual {
+                return Err(OrderMismatch { epoch, hasher });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::pubkey::Pubkey};
+
+    fn addresses() -> Vec<Pubkey2> {
+        (0..64).map(|_| Pubkey2(Pubkey::new_unique())).collect()
+    }
+
+    #[test]
+    fn test_verify_order_independence_counts() {
+        let result = verify_order_independence(4, 0, &addresses(), |epoch, addresses, emit| {
+            let mut buckets = vec![0; 8];
+            for address in addresses {
+                let sum = address.as_ref().iter().map(|byte| *byte as u64).sum::<u64>();
+                buckets[((sum + epoch) % 8) as usize] += 1;
+            }
+            emit("counts", buckets, Duration::ZERO);
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_verify_order_independence_order_dependent() {
+        let result = verify_order_independence(4, 0, &addresses(), |_epoch, addresses, emit| {
+            emit("first", addresses[0].as_ref().iter().map(|byte| *byte as usize).collect(), Duration::ZERO);
+        });
+        assert_eq!(result, Err(OrderMismatch { epoch: 0, hasher: "first" }));
+    }
+}
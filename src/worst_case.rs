@@ -0,0 +1,154 @@
+use {
+    crate::{
+        address_to_bucket_with_reduction,
+        hashers::{AnalysisHasher, HasherVisitor},
+        reduction::Reduction,
+        Pubkey2,
+    },
+    std::{collections::HashMap, fmt},
+};
+
+pub const HEADER: &str = "hasher,epochs,worst_overload,worst_overload_epoch,worst_churn,worst_churn_epoch";
+
+/// The single worst epoch a hasher produced over a run: the largest bucket's
+/// excess over the mean bucket, and the largest fraction of addresses moving
+/// bucket from one epoch to the next.
+#[derive(Debug, PartialEq)]
+pub struct WorstCase {
+    pub hasher: &'static str,
+    pub epochs: u64,
+    /// `max / mean - 1` of the most overloaded epoch
+    pub worst_overload: f64,
+    pub worst_overload_epoch: u64,
+    pub worst_churn: f64,
+    /// Epoch whose assignments differed most from the epoch before it
+    pub worst_churn_epoch: u64,
+}
+
+impl WorstCase {
+    /// The observed bound phrased as a guarantee over the epochs run.
+    pub fn guarantee(&self) -> String {
+        format!(
+            "{}: no epoch of {} exceeded {:.2}% overload (epoch {}) or {:.2}% churn (epoch {})",
+            self.hasher,
+            self.epochs,
+            self.worst_overload * 100.0,
+            self.worst_overload_epoch,
+            self.worst_churn * 100.0,
+            self.worst_churn_epoch,
+        )
+    }
+}
+
+impl fmt::Display for WorstCase {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{},{},{},{},{},{}", self.hasher, self.epochs, self.worst_overload, self.worst_overload_epoch, self.worst_churn, self.worst_churn_epoch)
+    }
+}
+
+struct Tracker {
+    worst: WorstCase,
+    previous: Vec<usize>,
+}
+
+/// Tracks each visited hasher's worst overload and churn, treating the n-th
+/// visit of a hasher as epoch n.
+pub struct WorstCaseVisitor<'a> {
+    buckets: usize,
+    reduction: Reduction,
+    addresses: &'a [Pubkey2],
+    names: Vec<&'static str>,
+    trackers: HashMap<&'static str, Tracker>,
+}
+
+impl<'a> WorstCaseVisitor<'a> {
+    pub fn new(buckets: usize, reduction: Reduction, addresses: &'a [Pubkey2]) -> Self {
+        Self { buckets, reduction, addresses, names: Vec::new(), trackers: HashMap::new() }
+    }
+
+    /// Each hasher's worst case, in visiting order.
+    pub fn worst_cases(&self) -> Vec<&WorstCase> {
+        self.names.iter().map(|name| &self.trackers[name].worst).collect()
+    }
+}
+
+impl HasherVisitor for WorstCaseVisitor<'_> {
+    fn visit<H: AnalysisHasher>(&mut self, name: &'static str, _key: &[u8], hasher: H) {
+        let tracker = self.trackers.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            Tracker {
+                worst: WorstCase { hasher: name, epochs: 0, worst_overload: 0.0, worst_overload_epoch: 0, worst_churn: 0.0, worst_churn_epoch: 0 },
+                previous: Vec::new(),
+            }
+        });
+        let epoch = tracker.worst.epochs;
+        tracker.worst.epochs += 1;
+        let current = self.addresses
+            .iter()
+            .map(|address| address_to_bucket_with_reduction(self.buckets, self.reduction, hasher.clone(), address))
+            .collect::<Vec<_>>();
+        let mut counts = vec![0; self.buckets];
+        for bucket in current.iter() {
+            counts[*bucket] += 1;
+        }
+        // No addresses overload nothing, rather than dividing by a zero mean.
+        let mean = current.len() as f64 / self.buckets as f64;
+        let overload = match counts.iter().max() {
+            Some(max) if !current.is_empty() => *max as f64 / mean - 1.0,
+            _ => 0.0,
+        };
+        if epoch == 0 || overload > tracker.worst.worst_overload {
+            tracker.worst.worst_overload = overload;
+            tracker.worst.worst_overload_epoch = epoch;
+        }
+        if epoch > 0 && !current.is_empty() {
+            let moved = tracker.previous.iter().zip(current.iter()).filter(|(previous, current)| previous != current).count();
+            let churn = moved as f64 / current.len() as f64;
+            if epoch == 1 || churn > tracker.worst.worst_churn {
+                tracker.worst.worst_churn = churn;
+                tracker.worst.worst_churn_epoch = epoch;
+            }
+        }
+        tracker.previous = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{hashers::Blake3Hasher, test_hashers::ConstantHasher},
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_worst_cases() {
+        let addresses = (0..1000).map(|_| Pubkey2(Pubkey::new_unique())).collect::<Vec<_>>();
+        let mut visitor = WorstCaseVisitor::new(10, Reduction::Multiply, &addresses);
+        for epoch in 0..5 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+            visitor.visit("constant", &[], ConstantHasher);
+        }
+        let worst_cases = visitor.worst_cases();
+        assert_eq!(worst_cases.iter().map(|worst| (worst.hasher, worst.epochs)).collect::<Vec<_>>(), vec![("blake3", 5), ("constant", 5)]);
+        assert!(worst_cases[0].worst_overload > 0.0 && worst_cases[0].worst_overload < 0.5);
+        assert!(worst_cases[0].worst_churn > 0.8 && worst_cases[0].worst_churn_epoch >= 1);
+        assert_eq!(
+            *worst_cases[1],
+            WorstCase { hasher: "constant", epochs: 5, worst_overload: 9.0, worst_overload_epoch: 0, worst_churn: 0.0, worst_churn_epoch: 1 }
+        );
+        assert_eq!(worst_cases[1].guarantee(), "constant: no epoch of 5 exceeded 900.00% overload (epoch 0) or 0.00% churn (epoch 1)");
+    }
+
+    #[test]
+    fn test_worst_cases_without_addresses() {
+        let mut visitor = WorstCaseVisitor::new(10, Reduction::Multiply, &[]);
+        for epoch in 0..3 {
+            visitor.visit("blake3", &Blake3Hasher::key(epoch), Blake3Hasher::new_with_seed(epoch));
+        }
+        assert_eq!(
+            *visitor.worst_cases()[0],
+            WorstCase { hasher: "blake3", epochs: 3, worst_overload: 0.0, worst_overload_epoch: 0, worst_churn: 0.0, worst_churn_epoch: 0 }
+        );
+    }
+}